@@ -0,0 +1,93 @@
+//! `doc` subcommand generating documentation from the doc comments.
+//!
+//! Usage: `doc [--format markdown|html] [--output <path>] <path>`
+//!
+//! Every function, declaration, infix and prefix operator of the
+//! module is listed with its signature, attributes, precedence and doc text.
+//! Documentation is written to the standard output unless `--output` is given.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ftl_lexer::Lexer;
+use ftl_parser::visitor::visit_ast;
+use ftl_parser::Parser;
+use ftl_pass::doc::{DocGen, Format};
+use ftl_session::{Emitter, Session};
+use ftl_source::string;
+use ftl_utility::RcRef;
+
+use crate::cmd::read_source;
+
+struct Options {
+    format: Format,
+    output: Option<String>,
+    path: String,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut format = Format::Markdown;
+    let mut output = None;
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("markdown") => Format::Markdown,
+                    Some("html") => Format::Html,
+                    _ => return Err(String::from("--format expects markdown or html")),
+                }
+            }
+            "--output" => {
+                output = Some(
+                    args.next()
+                        .cloned()
+                        .ok_or_else(|| String::from("--output expects a path"))?,
+                )
+            }
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok(Options {
+        format,
+        output,
+        path: path.ok_or_else(|| String::from("no path given"))?,
+    })
+}
+
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let opts = match parse_args(args) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            eprintln!("doc: {}", msg);
+            eprintln!("usage: doc [--format markdown|html] [--output <path>] <path>");
+            return Ok(2);
+        }
+    };
+    let src = read_source(&opts.path)?;
+    let sess = RcRef::new(Session::new(string::String::from(src)));
+    let lexer = Lexer::new(sess.clone());
+    let mut parser = Parser::new(lexer, sess.clone());
+    let ast = parser.parse();
+    if sess.borrow().handler.error_msg().is_some() {
+        Emitter::new(sess).emit_err(&mut io::stderr())?;
+        return Ok(1);
+    }
+    let title = match opts.path.as_str() {
+        "-" => "stdin",
+        path => Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(path),
+    };
+    let mut gen = DocGen::new(opts.format).title(title);
+    visit_ast(&mut gen, &ast);
+    match opts.output {
+        Some(ref output) => fs::write(output, gen.get())?,
+        None => gen.write(&mut io::stdout())?,
+    }
+    Ok(0)
+}
@@ -0,0 +1,143 @@
+//! `dump-ast` subcommand printing the syntax tree.
+//!
+//! Usage: `dump-ast [--format tree|json|dot|calls] [--from ftl|json] [--passes] <path>`
+//!
+//! Syntax tree is either parsed from the FTL source or loaded from
+//! the JSON, as written with `--format json`. With `--passes` the
+//! reassociation and declaration merge passes are run on it before printing.
+//!
+//! `dot` format is the Graphviz graph of the syntax tree and `calls`
+//! is the Graphviz graph of the dependencies between the functions.
+
+use std::io;
+
+use ftl_lexer::Lexer;
+use ftl_parser::ast::AST;
+use ftl_parser::visitor::visit_ast;
+use ftl_parser::visitor_mut::visit_ast_mut;
+use ftl_parser::Parser;
+use ftl_pass::dm::DeclarationMerge;
+use ftl_pass::dot::{CallGraph, Dot};
+use ftl_pass::epr::ExprPrecReassoc;
+use ftl_pass::pp::Printer;
+use ftl_session::{Emitter, Session};
+use ftl_source::{string, synthetic::Synthetic, Source};
+use ftl_utility::RcRef;
+
+use crate::cmd::read_source;
+
+#[derive(PartialEq)]
+enum Format {
+    Tree,
+    Json,
+    Dot,
+    Calls,
+}
+
+struct Options {
+    format: Format,
+    json_input: bool,
+    passes: bool,
+    path: String,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut format = Format::Tree;
+    let mut json_input = false;
+    let mut passes = false;
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("tree") => Format::Tree,
+                    Some("json") => Format::Json,
+                    Some("dot") => Format::Dot,
+                    Some("calls") => Format::Calls,
+                    _ => return Err(String::from("--format expects tree, json, dot or calls")),
+                }
+            }
+            "--from" => {
+                json_input = match args.next().map(String::as_str) {
+                    Some("ftl") => false,
+                    Some("json") => true,
+                    _ => return Err(String::from("--from expects ftl or json")),
+                }
+            }
+            "--passes" => passes = true,
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok(Options {
+        format,
+        json_input,
+        passes,
+        path: path.ok_or_else(|| String::from("no path given"))?,
+    })
+}
+
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let opts = match parse_args(args) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            eprintln!("dump-ast: {}", msg);
+            eprintln!(
+                "usage: dump-ast [--format tree|json|dot|calls] [--from ftl|json] [--passes] <path>"
+            );
+            return Ok(2);
+        }
+    };
+    let src = read_source(&opts.path)?;
+    if opts.json_input {
+        let ast: AST<Synthetic> = serde_json::from_str(&src)?;
+        let sess = RcRef::new(Session::new(Synthetic::new()));
+        dump(sess, ast, &opts)
+    } else {
+        let sess = RcRef::new(Session::new(string::String::from(src)));
+        let lexer = Lexer::new(sess.clone());
+        let mut parser = Parser::new(lexer, sess.clone());
+        let ast = parser.parse();
+        dump(sess, ast, &opts)
+    }
+}
+
+fn dump<S: 'static + Source>(
+    sess: RcRef<Session<S>>,
+    mut ast: AST<S>,
+    opts: &Options,
+) -> io::Result<i32> {
+    if opts.passes {
+        {
+            let mut sess_ref = sess.borrow_mut();
+            let mut epr = ExprPrecReassoc::new(&mut sess_ref);
+            visit_ast_mut(&mut epr, &mut ast);
+        }
+        let mut dm = DeclarationMerge::new();
+        visit_ast_mut(&mut dm, &mut ast);
+    }
+    match opts.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&ast)?),
+        Format::Tree => {
+            let mut printer = Printer::new();
+            visit_ast(&mut printer, &ast);
+            printer.write(&mut io::stdout())?;
+        }
+        Format::Dot => {
+            let mut dot = Dot::new();
+            visit_ast(&mut dot, &ast);
+            dot.write(&mut io::stdout())?;
+        }
+        Format::Calls => {
+            let mut calls = CallGraph::new();
+            visit_ast(&mut calls, &ast);
+            calls.write(&mut io::stdout())?;
+        }
+    }
+    let emitter = Emitter::new(sess.clone());
+    if emitter.emit_err(&mut io::stderr())? > 0 {
+        return Ok(1);
+    }
+    Ok(0)
+}
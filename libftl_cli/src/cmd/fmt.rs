@@ -0,0 +1,105 @@
+//! `fmt` subcommand formatting the sources.
+//!
+//! Usage: `fmt [--check] [--width <n>] <path>...`
+//!
+//! Formatted files are rewritten in place, source
+//! read from the standard input (`-`) is written to the standard output.
+//! With `--check` nothing is written, instead every
+//! not formatted path is listed and exit code is 1.
+
+use std::fs;
+use std::io;
+
+use ftl_lexer::Lexer;
+use ftl_parser::visitor::visit_ast;
+use ftl_parser::Parser;
+use ftl_pass::fmt::{collect_comments, Formatter, DEFAULT_WIDTH};
+use ftl_session::{Emitter, Session};
+use ftl_source::string;
+use ftl_utility::RcRef;
+
+use crate::cmd::read_source;
+
+struct Options {
+    check: bool,
+    width: usize,
+    paths: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        check: false,
+        width: DEFAULT_WIDTH,
+        paths: Vec::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => opts.check = true,
+            "--width" => {
+                opts.width = args
+                    .next()
+                    .and_then(|w| w.parse().ok())
+                    .ok_or_else(|| String::from("--width expects a number"))?
+            }
+            _ => opts.paths.push(arg.clone()),
+        }
+    }
+    if opts.paths.is_empty() {
+        return Err(String::from("no path given"));
+    }
+    Ok(opts)
+}
+
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let opts = match parse_args(args) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            eprintln!("fmt: {}", msg);
+            eprintln!("usage: fmt [--check] [--width <n>] <path>...");
+            return Ok(2);
+        }
+    };
+    let mut code = 0;
+    for path in &opts.paths {
+        let src = read_source(path)?;
+        let formatted = match format(&src, opts.width)? {
+            Some(formatted) => formatted,
+            None => {
+                eprintln!("fmt: {} has errors, skipping", path);
+                code = 1;
+                continue;
+            }
+        };
+        if opts.check {
+            if formatted != src {
+                println!("{}", path);
+                code = 1;
+            }
+        } else if path == "-" {
+            print!("{}", formatted);
+        } else if formatted != src {
+            fs::write(path, formatted)?;
+        }
+    }
+    Ok(code)
+}
+
+/// Returns formatted source or `None`
+/// if it has errors, which are written to the standard error.
+fn format(src: &str, width: usize) -> io::Result<Option<String>> {
+    let sess = RcRef::new(Session::new(string::String::from(src)));
+    let lexer = Lexer::new(sess.clone());
+    let mut parser = Parser::new(lexer, sess.clone());
+    let ast = parser.parse();
+    if sess.borrow().handler.error_msg().is_some() {
+        Emitter::new(sess).emit_err(&mut io::stderr())?;
+        return Ok(None);
+    }
+    let mut formatter = Formatter::new()
+        .width(width)
+        .comments(collect_comments(src))
+        .source(src);
+    visit_ast(&mut formatter, &ast);
+    Ok(Some(formatter.get().to_owned()))
+}
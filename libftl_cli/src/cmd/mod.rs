@@ -0,0 +1,24 @@
+//! Subcommands of the command line interface.
+//! Each of them is run with the arguments
+//! following its name and returns process exit code.
+
+pub mod doc;
+pub mod dump_ast;
+pub mod fmt;
+pub mod tokens;
+
+use std::fs;
+use std::io;
+use std::io::Read;
+
+/// Reads whole source from the path.
+/// `-` stands for the standard input.
+pub fn read_source(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut buff = String::new();
+        io::stdin().read_to_string(&mut buff)?;
+        Ok(buff)
+    } else {
+        fs::read_to_string(path)
+    }
+}
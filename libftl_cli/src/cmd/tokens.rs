@@ -0,0 +1,219 @@
+//! `tokens` subcommand printing the tokens the lexer produced.
+//!
+//! Usage: `tokens [--format table|json] <path>`
+//!
+//! Only the lexer is run on the source, so the tokens can be
+//! seen even if the source doesn't parse. Every token is printed
+//! with its kind, value and span, either as a row of the table
+//! or as a JSON object in its own line. Lexing errors are printed
+//! along the tokens they were reported for, which usually are the
+//! `Poisoned` ones, or before all the tokens if there is none they
+//! could be reported for, and make the exit code 1.
+
+use std::io;
+use std::io::Write;
+
+use serde_json::json;
+
+use ftl_lexer::token::{Token, Value};
+use ftl_lexer::Lexer;
+use ftl_session::Session;
+use ftl_source::{string, Pointer, Span};
+use ftl_utility::RcRef;
+
+use crate::cmd::read_source;
+
+#[derive(PartialEq)]
+enum Format {
+    Table,
+    Json,
+}
+
+struct Options {
+    format: Format,
+    path: String,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut format = Format::Table;
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("table") => Format::Table,
+                    Some("json") => Format::Json,
+                    _ => return Err(String::from("--format expects table or json")),
+                }
+            }
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok(Options {
+        format,
+        path: path.ok_or_else(|| String::from("no path given"))?,
+    })
+}
+
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let opts = match parse_args(args) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            eprintln!("tokens: {}", msg);
+            eprintln!("usage: tokens [--format table|json] <path>");
+            return Ok(2);
+        }
+    };
+    let src = read_source(&opts.path)?;
+    if write_tokens(&mut io::stdout(), &opts.format, src)? {
+        Ok(1)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Lexes the source and writes its tokens in the given format.
+/// Returns true if any lexing error has been reported.
+fn write_tokens(out: &mut impl Write, format: &Format, src: String) -> io::Result<bool> {
+    let sess = RcRef::new(Session::new(string::String::from(src)));
+    let tokens: Vec<_> = Lexer::new(sess.clone()).collect();
+
+    // Errors are reported while the token is being lexed,
+    // so they belong to the last token starting before them.
+    // Ones reported before any token are written on their own.
+    let mut errors = vec![Vec::new(); tokens.len()];
+    let mut unattached = Vec::new();
+    let sess = sess.borrow();
+    for err in sess.handler.errors() {
+        match tokens
+            .iter()
+            .rposition(|tok| tok.span.beg.offset() <= err.begin().offset())
+        {
+            Some(i) => errors[i].push(err.desc()),
+            None => unattached.push(err.desc()),
+        }
+    }
+
+    match format {
+        Format::Table => {
+            for err in &unattached {
+                writeln!(out, "error: {}", err)?;
+            }
+            if !tokens.is_empty() {
+                writeln!(out, "{:<15} {:<16} VALUE", "SPAN", "KIND")?;
+            }
+        }
+        Format::Json if !unattached.is_empty() => {
+            writeln!(out, "{}", json!({ "errors": unattached }))?
+        }
+        Format::Json => (),
+    }
+    for (tok, errors) in tokens.iter().zip(&errors) {
+        match format {
+            Format::Table => write_row(out, tok, errors)?,
+            Format::Json => writeln!(out, "{}", to_json(tok, errors))?,
+        }
+    }
+    Ok(!sess.handler.errors().is_empty())
+}
+
+fn write_row<P: Pointer>(
+    out: &mut impl Write,
+    tok: &Token<P>,
+    errors: &[String],
+) -> io::Result<()> {
+    let span = format!(
+        "{}:{}-{}:{}",
+        tok.span.beg.line(),
+        tok.span.beg.position(),
+        tok.span.end.line(),
+        tok.span.end.position()
+    );
+    let value = match &tok.value {
+        Value::Integer(val) => val.to_string(),
+        Value::Float(val) => format!("{:?}", val),
+        Value::Symbol(symbol) => format!("{:?}", symbol),
+        Value::Char(ch) => format!("{:?}", ch),
+        Value::None => String::new(),
+    };
+    writeln!(
+        out,
+        "{:<15} {:<16} {}",
+        span,
+        format!("{:?}", tok.kind),
+        value
+    )?;
+    for err in errors {
+        writeln!(out, "{:<15} error: {}", "", err)?;
+    }
+    Ok(())
+}
+
+fn to_json<P: Pointer>(tok: &Token<P>, errors: &[String]) -> serde_json::Value {
+    let value = match &tok.value {
+        Value::Integer(val) => json!(val),
+        Value::Float(val) => json!(val),
+        Value::Symbol(symbol) => json!(symbol.as_str()),
+        Value::Char(ch) => json!(ch),
+        Value::None => serde_json::Value::Null,
+    };
+    json!({
+        "kind": format!("{:?}", tok.kind),
+        "value": value,
+        "span": span_to_json(&tok.span),
+        "errors": errors,
+    })
+}
+
+fn span_to_json<P: Pointer>(span: &Span<P>) -> serde_json::Value {
+    let ptr = |ptr: &P| {
+        json!({
+            "line": ptr.line(),
+            "column": ptr.position(),
+            "offset": ptr.offset(),
+        })
+    };
+    json!({ "beg": ptr(&span.beg), "end": ptr(&span.end) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(src: &str, format: Format) -> (String, bool) {
+        let mut out = Vec::new();
+        let failed = write_tokens(&mut out, &format, String::from(src)).unwrap();
+        (String::from_utf8(out).unwrap(), failed)
+    }
+
+    #[test]
+    fn errors_without_tokens_are_written() {
+        let (table, failed) = tokens("#{ unterminated", Format::Table);
+        assert!(failed);
+        assert!(table.starts_with("error: "));
+        assert!(!table.contains("KIND"));
+
+        let (json, failed) = tokens("#{ unterminated", Format::Json);
+        assert!(failed);
+        let line: serde_json::Value = serde_json::from_str(json.trim_end()).unwrap();
+        assert_eq!(line["errors"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn errors_are_attached_to_tokens() {
+        let (json, failed) = tokens("def f: 0x", Format::Json);
+        assert!(failed);
+        let lines: Vec<serde_json::Value> = json
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(lines.iter().all(|line| line.get("kind").is_some()));
+        assert_eq!(lines.last().unwrap()["errors"].as_array().unwrap().len(), 1);
+
+        let (table, failed) = tokens("def f: 1", Format::Table);
+        assert!(!failed);
+        assert_eq!(table.lines().count(), 5);
+    }
+}
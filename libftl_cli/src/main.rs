@@ -1,5 +1,6 @@
 use std::env;
 use std::io;
+use std::process;
 
 use simplelog::*;
 
@@ -7,14 +8,20 @@ use ftl_lexer::Lexer;
 use ftl_parser::Parser;
 use ftl_session::{Emitter, Session};
 use ftl_source::file::File;
+use ftl_source::stream::Stream;
+use ftl_source::Source;
 use ftl_utility::RcRef;
 
 // test
+use ftl_parser::visitor::visit_ast;
 use ftl_parser::visitor_mut::visit_ast_mut;
+use ftl_pass::ac::ArityCheck;
+use ftl_pass::av::AttrValidation;
 use ftl_pass::dm::DeclarationMerge;
 use ftl_pass::epr::ExprPrecReassoc;
 // test
 
+mod cmd;
 mod helpers;
 mod phase;
 
@@ -22,12 +29,28 @@ use helpers::*;
 use phase::*;
 
 fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("fmt") => process::exit(cmd::fmt::run(&args[1..])?),
+        Some("doc") => process::exit(cmd::doc::run(&args[1..])?),
+        Some("dump-ast") => process::exit(cmd::dump_ast::run(&args[1..])?),
+        Some("tokens") => process::exit(cmd::tokens::run(&args[1..])?),
+        _ => (),
+    }
     init_logger(if cfg!(debug_assertions) {
         LevelFilter::Trace
     } else {
         LevelFilter::Warn
     });
-    let sess = create_sess();
+    match get_path() {
+        Some(ref path) if path == "-" => compile(Stream::stdin()),
+        Some(path) => compile(File::new(&path)?),
+        None => panic!("Empty source"),
+    }
+}
+
+fn compile<S: 'static + Source>(source: S) -> io::Result<()> {
+    let sess = create_sess(source);
 
     print_green("✔ Created");
     print_line();
@@ -63,6 +86,10 @@ fn main() -> io::Result<()> {
         let mut sess_ref = sess.borrow_mut();
         let mut epr = ExprPrecReassoc::new(&mut sess_ref);
         visit_ast_mut(&mut epr, &mut ast);
+        let mut ac = ArityCheck::new(&mut sess_ref);
+        visit_ast(&mut ac, &ast);
+        let mut av = AttrValidation::new(&mut sess_ref);
+        visit_ast(&mut av, &ast);
     }
     {
         let mut dm = DeclarationMerge::new();
@@ -85,20 +112,15 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
-fn create_sess() -> RcRef<Session<File>> {
+fn create_sess<S: Source>(source: S) -> RcRef<Session<S>> {
     println!();
     print_red("🦊 Compilation starts...");
     print_line();
     print_red("🦒 Creating source and session...");
-    let source = if let Some(file) = get_file() {
-        file
-    } else {
-        panic!("Empty source")
-    };
     RcRef::new(Session::new(source))
 }
 
-fn print_errors<S: ftl_source::Source>(emmiter: &Emitter<S>) -> std::io::Result<()> {
+fn print_errors<S: Source>(emmiter: &Emitter<S>) -> std::io::Result<()> {
     print_line();
     print_red("🐺 Printing errors...");
     print_line();
@@ -113,7 +135,9 @@ fn init_logger(filter: LevelFilter) {
     CombinedLogger::init(vec![TermLogger::new(filter, Config::default()).unwrap()]).unwrap();
 }
 
-fn get_file() -> Option<File> {
+/// Returns path passed with the `--file` argument.
+/// `-` stands for the standard input.
+fn get_path() -> Option<String> {
     let arg_iter = env::args();
     let mut path = None;
     let mut file_present = false;
@@ -125,9 +149,5 @@ fn get_file() -> Option<File> {
             file_present = true;
         }
     }
-    if let Some(path) = path {
-        Some(File::new(&path).unwrap())
-    } else {
-        None
-    }
+    path
 }
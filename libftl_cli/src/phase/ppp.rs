@@ -17,7 +17,7 @@ impl<S: Source> Phase<S> for PrettyPrint {
 
     fn run(&mut self, ast: &mut AST<S>) {
         let mut ppp = pp::Printer::new();
-        visit_ast(&mut ppp, &ast);
+        visit_ast(&mut ppp, ast);
 
         let mut out = std::io::stdout();
         ppp.write(&mut out).unwrap();
@@ -37,11 +37,17 @@ where
     pub fn fatal(&mut self, err: Box<dyn LangError<Ptr = P>>) -> ! {
         self.err(err);
         if let Some(msg) = self.error_msg() {
-            println!("{}", msg);
+            eprintln!("{}", msg);
         }
         panic!("Fatal error");
     }
 
+    /// Returns all of the errors reported so far in the order
+    /// they were reported.
+    pub fn errors(&self) -> &[Box<dyn LangError<Ptr = P>>] {
+        &self.errs
+    }
+
     pub fn error_msg(&self) -> Option<String> {
         if self.errs.is_empty() {
             return None;
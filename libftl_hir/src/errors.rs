@@ -0,0 +1,34 @@
+use ftl_error::LangError;
+use ftl_source::{Pointer, Span};
+
+/// Error reported while lowering the syntax tree,
+/// either during the name resolution or the type inference.
+pub struct LoweringError<P: Pointer> {
+    pub msg: String,
+    pub span: Span<P>,
+}
+
+impl<P: Pointer> LoweringError<P> {
+    pub fn new(msg: String, span: &Span<P>) -> Self {
+        Self {
+            msg,
+            span: span.clone(),
+        }
+    }
+}
+
+impl<P: Pointer> LangError for LoweringError<P> {
+    type Ptr = P;
+
+    fn desc(&self) -> String {
+        self.msg.clone()
+    }
+
+    fn begin(&self) -> &Self::Ptr {
+        &self.span.beg
+    }
+
+    fn end(&self) -> &Self::Ptr {
+        &self.span.end
+    }
+}
@@ -0,0 +1,83 @@
+//! Lang items, the functions the compiler has to know about.
+//!
+//! There are no built-in operators in FTL, even the arithmetic
+//! is done by the functions, usually wrapped in the infixes.
+//! Function implementing such an operation is marked with
+//! the attribute naming the lang item, for example
+//!
+//! ```text
+//! decl fadd float float [lang_fadd]: float
+//! infix 50 +. a b: @fadd a b
+//! ```
+//!
+//! The item can also be given as the argument, `[lang "fadd"]`
+//! is the same as `[lang_fadd]`.
+//!
+//! Every lang item has a fixed type. Functions marked as
+//! lang items are checked against it during the lowering, so
+//! the types of their parameters don't have to be declared.
+
+use crate::{Attr, AttrArg, Ty};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LangItem {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    FAdd,
+    FSub,
+    FMul,
+    FDiv,
+}
+
+impl LangItem {
+    pub const ALL: [LangItem; 8] = [
+        LangItem::Add,
+        LangItem::Sub,
+        LangItem::Mul,
+        LangItem::Div,
+        LangItem::FAdd,
+        LangItem::FSub,
+        LangItem::FMul,
+        LangItem::FDiv,
+    ];
+
+    /// Returns lang item the attribute marks the function as.
+    pub fn from_attr(attr: &Attr) -> Option<Self> {
+        match attr.args.as_slice() {
+            [] => Self::ALL
+                .iter()
+                .copied()
+                .find(|item| attr.name == item.attr()),
+            [AttrArg::Str(name)] if attr.name == "lang" => Self::ALL
+                .iter()
+                .copied()
+                .find(|item| item.attr()["lang_".len()..] == *name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Name of the attribute marking the function as the lang item.
+    pub fn attr(self) -> &'static str {
+        match self {
+            LangItem::Add => "lang_add",
+            LangItem::Sub => "lang_sub",
+            LangItem::Mul => "lang_mul",
+            LangItem::Div => "lang_div",
+            LangItem::FAdd => "lang_fadd",
+            LangItem::FSub => "lang_fsub",
+            LangItem::FMul => "lang_fmul",
+            LangItem::FDiv => "lang_fdiv",
+        }
+    }
+
+    /// Type the function implementing the lang item has to have.
+    pub fn ty(self) -> Ty {
+        let operand = match self {
+            LangItem::Add | LangItem::Sub | LangItem::Mul | LangItem::Div => Ty::Int,
+            LangItem::FAdd | LangItem::FSub | LangItem::FMul | LangItem::FDiv => Ty::Float,
+        };
+        Ty::Func(vec![operand.clone(), operand.clone()], Box::new(operand))
+    }
+}
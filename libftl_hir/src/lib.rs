@@ -0,0 +1,193 @@
+//! High-level intermediate representation of the FTL program.
+//!
+//! Unlike the syntax tree, which holds the program the way
+//! it was written, HIR is produced after the names have been
+//! resolved and the types have been inferred. References
+//! point straight to the definitions and parameters instead
+//! of holding their names, every expression carries its
+//! concrete [`Ty`](ty/enum.Ty.html) and there are no
+//! parenthesized expressions or infix calls, they are all
+//! lowered to the ordinary calls.
+//!
+//! Every node has its [`HirId`](struct.HirId.html) which can be
+//! mapped back to the syntax tree node it was lowered from
+//! and its span, so that later passes can report the errors
+//! in terms of the source.
+//!
+//! HIR is created by the [`Lowering`](lower/struct.Lowering.html)
+//! which runs on the syntax tree after the precedence
+//! reassociation.
+
+use std::collections::HashMap;
+
+use ftl_parser::ast::NodeId;
+use ftl_session::symbol::Symbol;
+use ftl_source::{Pointer, Span};
+
+pub mod errors;
+pub mod lang;
+pub mod lower;
+pub mod ty;
+
+pub use lang::LangItem;
+pub use lower::Lowering;
+pub use ty::Ty;
+
+/// Identifies a node in the HIR.
+///
+/// Ids are assigned sequentially, starting from zero,
+/// in the order the nodes are lowered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HirId(pub usize);
+
+/// Identifies a top level definition.
+///
+/// It is an index of the definition in the
+/// [`Hir::defs`](struct.Hir.html#structfield.defs).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DefId(pub usize);
+
+/// Lowered program.
+pub struct Hir<P: Pointer> {
+    /// Top level definitions in the order
+    /// they first appeared in the source.
+    pub defs: Vec<Def>,
+    lang_items: HashMap<LangItem, DefId>,
+    origins: Vec<Origin<P>>,
+}
+
+/// Syntax tree node the HIR node has been lowered from.
+struct Origin<P: Pointer> {
+    node: NodeId,
+    span: Span<P>,
+}
+
+impl<P: Pointer> Hir<P> {
+    pub fn def(&self, id: DefId) -> &Def {
+        &self.defs[id.0]
+    }
+
+    /// Returns definition of the function or the operator
+    /// with the given name.
+    pub fn find(&self, name: &str) -> Option<&Def> {
+        self.defs.iter().find(|def| def.name == name)
+    }
+
+    /// Returns definition marked as the lang item.
+    pub fn lang_item(&self, item: LangItem) -> Option<&Def> {
+        self.lang_items.get(&item).map(|&id| self.def(id))
+    }
+
+    /// Returns id of the syntax tree node the
+    /// HIR node has been lowered from.
+    pub fn node(&self, id: HirId) -> NodeId {
+        self.origins[id.0].node
+    }
+
+    /// Returns span of the source the
+    /// HIR node has been lowered from.
+    pub fn span(&self, id: HirId) -> &Span<P> {
+        &self.origins[id.0].span
+    }
+
+    /// Returns number of the nodes in the HIR.
+    pub fn len(&self) -> usize {
+        self.origins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+}
+
+/// Function or operator definition.
+///
+/// Declaration and definition of the same function are
+/// merged into one. Declared functions which are
+/// not defined have no body.
+#[derive(Clone, Debug)]
+pub struct Def {
+    pub id: HirId,
+    pub def_id: DefId,
+    pub name: Symbol,
+    pub kind: DefKind,
+    /// Attributes from both the declaration and the definition,
+    /// sorted by name and without duplicates.
+    pub attrs: Vec<Attr>,
+    pub params: Vec<Param>,
+    /// Type of the function, always a
+    /// [`Ty::Func`](ty/enum.Ty.html#variant.Func).
+    pub ty: Ty,
+    pub body: Option<Expr>,
+}
+
+/// Attribute of the definition with its arguments,
+/// both the ones given in the parentheses and the
+/// literals written after the name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attr {
+    pub name: Symbol,
+    pub args: Vec<AttrArg>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttrArg {
+    Int(i64),
+    Float(f64),
+    Str(Symbol),
+    Char(char),
+    Ident(Symbol),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DefKind {
+    Func,
+    Infix {
+        precedence: usize,
+    },
+    /// Prefix operator, without the precedence if it
+    /// binds tighter than any infix.
+    Prefix {
+        precedence: Option<usize>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Param {
+    pub id: HirId,
+    pub name: Symbol,
+    pub ty: Ty,
+}
+
+#[derive(Clone, Debug)]
+pub struct Expr {
+    pub id: HirId,
+    pub ty: Ty,
+    pub kind: ExprKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum ExprKind {
+    Int(i64),
+    Float(f64),
+    Str(Symbol),
+    Char(char),
+    /// Parameter of the enclosing definition
+    /// referred to by its index.
+    Param(usize),
+    Def(DefId),
+    /// Call of the function, which can be partially
+    /// applied or be given more arguments than
+    /// it has parameters if it returns a function.
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    /// Function taking the first argument of the callee
+    /// which is called with it and the given second one.
+    /// Lowered from the right operator sections like `(+ 1)`.
+    RightSection {
+        callee: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
@@ -0,0 +1,1198 @@
+//! Lowering of the syntax tree to the HIR.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::slice;
+
+use log::debug;
+
+use ftl_parser::ast::{self, NodeId, AST};
+use ftl_session::symbol::Symbol;
+use ftl_session::Session;
+use ftl_source::{Pointer, Source, Span};
+
+use crate::errors::LoweringError;
+use crate::{
+    Attr, AttrArg, Def, DefId, DefKind, Expr, ExprKind, Hir, HirId, LangItem, Origin, Param, Ty,
+};
+
+/// Type during the inference, which can
+/// still contain unbound type variables.
+#[derive(Clone)]
+enum Infer {
+    Var(usize),
+    Int,
+    Float,
+    Void,
+    Str,
+    Char,
+    Func(Vec<Infer>, Box<Infer>),
+}
+
+impl From<&Ty> for Infer {
+    fn from(ty: &Ty) -> Self {
+        match ty {
+            Ty::Int => Infer::Int,
+            Ty::Float => Infer::Float,
+            Ty::Void => Infer::Void,
+            Ty::Str => Infer::Str,
+            Ty::Char => Infer::Char,
+            Ty::Func(params, ret) => Infer::Func(
+                params.iter().map(Infer::from).collect(),
+                Box::new(Infer::from(&**ret)),
+            ),
+        }
+    }
+}
+
+/// Displays unbound type variables as `_`.
+/// Types should be fully substituted before displaying.
+impl fmt::Display for Infer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Infer::Var(_) => write!(f, "_"),
+            Infer::Int => write!(f, "int"),
+            Infer::Float => write!(f, "float"),
+            Infer::Void => write!(f, "void"),
+            Infer::Str => write!(f, "str"),
+            Infer::Char => write!(f, "char"),
+            Infer::Func(params, ret) => {
+                write!(f, "(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", param)?;
+                }
+                write!(f, ") {}", ret)
+            }
+        }
+    }
+}
+
+/// Top level function or operator gathered
+/// from its declaration and definition.
+struct Item<'ast, P: Pointer> {
+    name: Symbol,
+    kind: DefKind,
+    /// Node of the definition or, if there is none,
+    /// of the declaration.
+    node: NodeId,
+    span: Span<P>,
+    attrs: Vec<Attr>,
+    /// Declared type of the whole function.
+    ty: Option<&'ast ast::Type<P>>,
+    args: Vec<&'ast ast::FuncArg<P>>,
+    body: Option<&'ast ast::Expr<P>>,
+}
+
+/// Resolves names and infers types in the syntax tree
+/// lowering it to the [`Hir`](../struct.Hir.html).
+///
+/// Identifiers resolve to the parameters of the enclosing
+/// definition and then to the top level functions,
+/// operators resolve to the infix or prefix definitions.
+/// Prefix and infix using the same operator are distinct.
+/// Declaration and definition of the same function
+/// are merged with the declaration providing its type.
+///
+/// Types don't have to be declared, they are inferred
+/// from the way the functions are used. There is no
+/// polymorphism yet so the types which could not be
+/// inferred default to `int`.
+///
+/// Infix calls are lowered by their structure so the
+/// [`ExprPrecReassoc`](../../ftl_pass/epr/struct.ExprPrecReassoc.html)
+/// pass should be run on the syntax tree first.
+///
+/// # Examples
+///
+/// ```
+/// use ftl_hir::{ExprKind, Lowering};
+/// use ftl_lexer::Lexer;
+/// use ftl_parser::Parser;
+/// use ftl_session::Session;
+/// use ftl_source::string;
+/// use ftl_utility::RcRef;
+///
+/// let sess = RcRef::new(Session::new(string::String::from("def f x: x\ndef g: @f 1")));
+/// let mut parser = Parser::new(Lexer::new(sess.clone()), sess.clone());
+/// let ast = parser.parse();
+/// let hir = Lowering::new(&mut sess.borrow_mut()).lower(&ast).unwrap();
+///
+/// let g = hir.find("g").unwrap();
+/// assert_eq!(g.ty.to_string(), "() int");
+/// match g.body.as_ref().unwrap().kind {
+///     ExprKind::Call { ref callee, .. } => assert_eq!(callee.ty.to_string(), "(int) int"),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub struct Lowering<'a, S: Source> {
+    sess: &'a mut Session<S>,
+    origins: Vec<Origin<S::Pointer>>,
+    /// Type of every node indexed by its id.
+    types: Vec<Infer>,
+    /// Types bound to the type variables.
+    vars: Vec<Option<Infer>>,
+    /// Node ids of the definitions.
+    defs: Vec<HirId>,
+    funcs: HashMap<Symbol, DefId>,
+    ops: HashMap<Symbol, DefId>,
+    prefixes: HashMap<Symbol, DefId>,
+    lang_items: HashMap<LangItem, DefId>,
+    errors: usize,
+}
+
+impl<'a, S, P> Lowering<'a, S>
+where
+    P: 'static + Pointer,
+    S: Source<Pointer = P>,
+{
+    pub fn new(sess: &'a mut Session<S>) -> Self {
+        debug!("Lowering syntax tree to HIR");
+        Self {
+            sess,
+            origins: Vec::new(),
+            types: Vec::new(),
+            vars: Vec::new(),
+            defs: Vec::new(),
+            funcs: HashMap::new(),
+            ops: HashMap::new(),
+            prefixes: HashMap::new(),
+            lang_items: HashMap::new(),
+            errors: 0,
+        }
+    }
+
+    /// Lowers the syntax tree reporting the errors to the session.
+    ///
+    /// Returns None if any error has been reported.
+    pub fn lower(mut self, ast: &AST<S>) -> Option<Hir<P>> {
+        let items = self.collect(&ast.root);
+        let mut defs: Vec<Def> = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| self.lower_signature(DefId(i), item))
+            .collect();
+        self.collect_lang_items(&defs, &items);
+        for (def, item) in defs.iter_mut().zip(&items) {
+            if let Some(body) = item.body {
+                let ret = match self.types[def.id.0] {
+                    Infer::Func(_, ref ret) => (**ret).clone(),
+                    _ => unreachable!("defined functions have function types"),
+                };
+                let body = self.lower_expr(&def.params, body);
+                let span = self.origins[body.id.0].span.clone();
+                self.unify(&ret, &self.types[body.id.0].clone(), &span);
+                def.body = Some(body);
+            }
+        }
+        if self.errors > 0 {
+            return None;
+        }
+        for def in &mut defs {
+            def.ty = self.ty(def.id);
+            for param in &mut def.params {
+                param.ty = self.ty(param.id);
+            }
+            if let Some(ref mut body) = def.body {
+                self.fill_types(body);
+            }
+        }
+        Some(Hir {
+            defs,
+            lang_items: self.lang_items,
+            origins: self.origins,
+        })
+    }
+
+    fn err(&mut self, msg: String, span: &Span<P>) {
+        self.errors += 1;
+        self.sess.err(Box::new(LoweringError::new(msg, span)));
+    }
+
+    // Name resolution
+
+    /// Gathers top level functions and operators
+    /// reporting the ones defined more than once.
+    fn collect<'ast>(&mut self, module: &'ast ast::Module<P>) -> Vec<Item<'ast, P>> {
+        let mut items: Vec<Item<'ast, P>> = Vec::new();
+        let mut declared = Vec::new();
+        let mut defined = Vec::new();
+        for decl in &module.decl {
+            match decl.kind {
+                ast::TopLevelDeclKind::FunctionDecl(ref func_decl) => {
+                    let i = self.func_item(&mut items, &func_decl.ident, decl);
+                    items[i]
+                        .attrs
+                        .extend(func_decl.attrs.iter().map(Self::lower_attr));
+                    if declared.contains(&i) {
+                        let msg =
+                            format!("Function `{}` is already declared", func_decl.ident.symbol);
+                        self.err(msg, &func_decl.ident.span);
+                        continue;
+                    }
+                    declared.push(i);
+                    items[i].ty = func_decl.ty.as_ref();
+                }
+                ast::TopLevelDeclKind::FunctionDef(ref func_def) => {
+                    let ident = &func_def.decl.ident;
+                    let i = self.func_item(&mut items, ident, decl);
+                    items[i]
+                        .attrs
+                        .extend(func_def.decl.attrs.iter().map(Self::lower_attr));
+                    if defined.contains(&i) {
+                        let msg = format!("Function `{}` is already defined", ident.symbol);
+                        self.err(msg, &ident.span);
+                        continue;
+                    }
+                    defined.push(i);
+                    let item = &mut items[i];
+                    item.node = decl.id;
+                    item.span = decl.span.clone();
+                    item.args = func_def.args.iter().collect();
+                    item.body = Some(&func_def.body);
+                    if item.ty.is_none() {
+                        item.ty = func_def.decl.ty.as_ref();
+                    }
+                }
+                ast::TopLevelDeclKind::InfixDef(ref infix) => {
+                    if self.ops.contains_key(&infix.op.symbol) {
+                        let msg = format!("Operator `{}` is already defined", infix.op.symbol);
+                        self.err(msg, &infix.op.span);
+                        continue;
+                    }
+                    self.ops.insert(infix.op.symbol.clone(), DefId(items.len()));
+                    items.push(Item {
+                        name: infix.op.symbol.clone(),
+                        kind: DefKind::Infix {
+                            precedence: infix.precedence,
+                        },
+                        node: decl.id,
+                        span: decl.span.clone(),
+                        attrs: Vec::new(),
+                        ty: infix.ty.as_ref(),
+                        args: vec![&infix.args.0, &infix.args.1],
+                        body: Some(&infix.body),
+                    });
+                }
+                ast::TopLevelDeclKind::PrefixDef(ref prefix) => {
+                    if self.prefixes.contains_key(&prefix.op.symbol) {
+                        let msg =
+                            format!("Prefix operator `{}` is already defined", prefix.op.symbol);
+                        self.err(msg, &prefix.op.span);
+                        continue;
+                    }
+                    self.prefixes
+                        .insert(prefix.op.symbol.clone(), DefId(items.len()));
+                    items.push(Item {
+                        name: prefix.op.symbol.clone(),
+                        kind: DefKind::Prefix {
+                            precedence: prefix.precedence,
+                        },
+                        node: decl.id,
+                        span: decl.span.clone(),
+                        attrs: Vec::new(),
+                        ty: prefix.ty.as_ref(),
+                        args: vec![&prefix.arg],
+                        body: Some(&prefix.body),
+                    });
+                }
+            }
+        }
+        for item in &mut items {
+            item.attrs.sort_by(|a, b| a.name.cmp(&b.name));
+            item.attrs.dedup();
+        }
+        items
+    }
+
+    /// Returns index of the function with the given name,
+    /// adding it if it has not been seen yet.
+    fn func_item<'ast>(
+        &mut self,
+        items: &mut Vec<Item<'ast, P>>,
+        ident: &ast::Ident<P>,
+        decl: &ast::TopLevelDecl<P>,
+    ) -> usize {
+        if let Some(def_id) = self.funcs.get(&ident.symbol) {
+            return def_id.0;
+        }
+        self.funcs.insert(ident.symbol.clone(), DefId(items.len()));
+        items.push(Item {
+            name: ident.symbol.clone(),
+            kind: DefKind::Func,
+            node: decl.id,
+            span: decl.span.clone(),
+            attrs: Vec::new(),
+            ty: None,
+            args: Vec::new(),
+            body: None,
+        });
+        items.len() - 1
+    }
+
+    // Lowering
+
+    /// Returns new node id mapped to the syntax tree node.
+    fn node(&mut self, node: NodeId, span: &Span<P>, ty: Infer) -> HirId {
+        self.origins.push(Origin {
+            node,
+            span: span.clone(),
+        });
+        self.types.push(ty);
+        HirId(self.origins.len() - 1)
+    }
+
+    /// Lowers the definition without its body.
+    ///
+    /// Types of the nodes are filled in
+    /// once the whole tree has been lowered.
+    fn lower_signature(&mut self, def_id: DefId, item: &Item<'_, P>) -> Def {
+        let id = self.node(item.node, &item.span, Infer::Void);
+        self.defs.push(id);
+        let mut params = Vec::new();
+        for arg in &item.args {
+            let var = self.fresh();
+            let param_id = self.node(arg.id, &arg.span, var.clone());
+            if let Some(ref ty) = arg.ty {
+                let declared = self.infer_type(ty);
+                self.unify(&declared, &var, &ty.span);
+            }
+            params.push(Param {
+                id: param_id,
+                name: arg.ident.symbol.clone(),
+                ty: Ty::Int,
+            });
+        }
+        let ty = if item.body.is_some() {
+            let ty = Infer::Func(
+                params.iter().map(|p| self.types[p.id.0].clone()).collect(),
+                Box::new(self.fresh()),
+            );
+            if let Some(declared) = item.ty {
+                self.check_declared(item, declared, &ty);
+            }
+            ty
+        } else {
+            match item.ty {
+                Some(declared) => self.infer_type(declared),
+                None => Infer::Func(Vec::new(), Box::new(self.fresh())),
+            }
+        };
+        self.types[id.0] = ty;
+        Def {
+            id,
+            def_id,
+            name: item.name.clone(),
+            kind: item.kind,
+            attrs: item.attrs.clone(),
+            params,
+            ty: Ty::Int,
+            body: None,
+        }
+    }
+
+    fn lower_attr(attr: &ast::FuncAttr<P>) -> Attr {
+        let args = attr
+            .args
+            .iter()
+            .map(|arg| match arg {
+                ast::AttrArg::Lit(lit) => match lit.kind {
+                    ast::LitKind::Int(val) => AttrArg::Int(val),
+                    ast::LitKind::Float(val) => AttrArg::Float(val),
+                    ast::LitKind::Str(ref val) => AttrArg::Str(val.clone()),
+                    ast::LitKind::Char(val) => AttrArg::Char(val),
+                },
+                ast::AttrArg::Ident(ident) => AttrArg::Ident(ident.symbol.clone()),
+            })
+            .collect();
+        Attr {
+            name: attr.ident.symbol.clone(),
+            args,
+        }
+    }
+
+    /// Registers the definitions marked as the lang items
+    /// constraining their types to the types of the items.
+    fn collect_lang_items(&mut self, defs: &[Def], items: &[Item<'_, P>]) {
+        for (def, item) in defs.iter().zip(items) {
+            for attr in &def.attrs {
+                let lang_item = match LangItem::from_attr(attr) {
+                    Some(lang_item) => lang_item,
+                    None => continue,
+                };
+                if let Some(other) = self.lang_items.get(&lang_item) {
+                    let msg = format!(
+                        "Lang item `{}` is already defined by `{}`",
+                        lang_item.attr(),
+                        defs[other.0].name
+                    );
+                    self.err(msg, &item.span);
+                    continue;
+                }
+                self.lang_items.insert(lang_item, def.def_id);
+                let ty = self.types[def.id.0].clone();
+                self.unify(&Infer::from(&lang_item.ty()), &ty, &item.span);
+            }
+        }
+    }
+
+    fn check_declared(&mut self, item: &Item<'_, P>, declared: &ast::Type<P>, ty: &Infer) {
+        if let ast::TypeKind::Function(ref func_t) = declared.kind {
+            if func_t.args.len() != item.args.len() {
+                let msg = format!(
+                    "Function `{}` is declared with {} parameters but defined with {}",
+                    item.name,
+                    func_t.args.len(),
+                    item.args.len()
+                );
+                self.err(msg, &declared.span);
+                return;
+            }
+        }
+        let declared_t = self.infer_type(declared);
+        self.unify(&declared_t, ty, &declared.span);
+    }
+
+    fn lower_expr(&mut self, params: &[Param], expr: &ast::Expr<P>) -> Expr {
+        match expr.kind {
+            ast::ExprKind::Literal(ref lit) => match &lit.kind {
+                ast::LitKind::Int(val) => Expr {
+                    id: self.node(expr.id, &expr.span, Infer::Int),
+                    ty: Ty::Int,
+                    kind: ExprKind::Int(*val),
+                },
+                ast::LitKind::Float(val) => Expr {
+                    id: self.node(expr.id, &expr.span, Infer::Float),
+                    ty: Ty::Float,
+                    kind: ExprKind::Float(*val),
+                },
+                ast::LitKind::Str(val) => Expr {
+                    id: self.node(expr.id, &expr.span, Infer::Str),
+                    ty: Ty::Str,
+                    kind: ExprKind::Str(val.clone()),
+                },
+                ast::LitKind::Char(val) => Expr {
+                    id: self.node(expr.id, &expr.span, Infer::Char),
+                    ty: Ty::Char,
+                    kind: ExprKind::Char(*val),
+                },
+            },
+            ast::ExprKind::Identifier(ref ident) => {
+                self.lower_ident(params, ident, expr.id, &expr.span)
+            }
+            // Parenthesed expression is replaced with its inner
+            // expression which keeps its own id and span.
+            ast::ExprKind::Parenthesed(ref paren) => self.lower_expr(params, &paren.expr),
+            ast::ExprKind::FunctionCall(ref call) => {
+                let callee = self.lower_expr(params, &call.lhs);
+                let args = call
+                    .args
+                    .iter()
+                    .map(|arg| self.lower_expr(params, arg))
+                    .collect();
+                self.lower_call(expr, callee, args)
+            }
+            ast::ExprKind::InfixOpCall(ref call) => {
+                let callee = self.lower_op(&call.op);
+                let args = vec![
+                    self.lower_expr(params, &call.lhs),
+                    self.lower_expr(params, &call.rhs),
+                ];
+                self.lower_call(expr, callee, args)
+            }
+            ast::ExprKind::PrefixOpCall(ref call) => {
+                let callee = self.lower_prefix_op(&call.op);
+                let args = vec![self.lower_expr(params, &call.expr)];
+                self.lower_call(expr, callee, args)
+            }
+            // Section without operands is the operator itself
+            // and the left one is its partial application.
+            ast::ExprKind::Section(ref section) => {
+                let callee = self.lower_op(&section.op);
+                match (&section.lhs, &section.rhs) {
+                    (Some(lhs), _) => {
+                        let args = vec![self.lower_expr(params, lhs)];
+                        self.lower_call(expr, callee, args)
+                    }
+                    (_, Some(rhs)) => {
+                        let rhs = self.lower_expr(params, rhs);
+                        self.lower_right_section(expr, callee, rhs)
+                    }
+                    _ => callee,
+                }
+            }
+            ast::ExprKind::InfixFuncCall(ref call) => {
+                let callee = self.lower_ident(params, &call.ident, call.ident.id, &call.ident.span);
+                let args = vec![
+                    self.lower_expr(params, &call.lhs),
+                    self.lower_expr(params, &call.rhs),
+                ];
+                self.lower_call(expr, callee, args)
+            }
+        }
+    }
+
+    fn lower_ident(
+        &mut self,
+        params: &[Param],
+        ident: &ast::Ident<P>,
+        node: NodeId,
+        span: &Span<P>,
+    ) -> Expr {
+        if let Some(i) = params.iter().position(|p| p.name == ident.symbol) {
+            let ty = self.types[params[i].id.0].clone();
+            return Expr {
+                id: self.node(node, span, ty),
+                ty: Ty::Int,
+                kind: ExprKind::Param(i),
+            };
+        }
+        match self.funcs.get(&ident.symbol).copied() {
+            Some(def_id) => self.def_ref(def_id, node, span),
+            None => {
+                self.err(format!("Unresolved name `{}`", ident.symbol), span);
+                self.invalid(node, span)
+            }
+        }
+    }
+
+    fn lower_op(&mut self, op: &ast::Op<P>) -> Expr {
+        match self.ops.get(&op.symbol).copied() {
+            Some(def_id) => self.def_ref(def_id, op.id, &op.span),
+            None => {
+                self.err(format!("Unknown operator `{}`", op.symbol), &op.span);
+                self.invalid(op.id, &op.span)
+            }
+        }
+    }
+
+    fn lower_prefix_op(&mut self, op: &ast::Op<P>) -> Expr {
+        match self.prefixes.get(&op.symbol).copied() {
+            Some(def_id) => self.def_ref(def_id, op.id, &op.span),
+            None => {
+                self.err(format!("Unknown prefix operator `{}`", op.symbol), &op.span);
+                self.invalid(op.id, &op.span)
+            }
+        }
+    }
+
+    fn def_ref(&mut self, def_id: DefId, node: NodeId, span: &Span<P>) -> Expr {
+        let ty = self.types[self.defs[def_id.0].0].clone();
+        Expr {
+            id: self.node(node, span, ty),
+            ty: Ty::Int,
+            kind: ExprKind::Def(def_id),
+        }
+    }
+
+    /// Expression standing in for the one which could not be
+    /// lowered. It is never returned as lowering fails.
+    fn invalid(&mut self, node: NodeId, span: &Span<P>) -> Expr {
+        let var = self.fresh();
+        Expr {
+            id: self.node(node, span, var),
+            ty: Ty::Int,
+            kind: ExprKind::Int(0),
+        }
+    }
+
+    fn lower_call(&mut self, expr: &ast::Expr<P>, callee: Expr, args: Vec<Expr>) -> Expr {
+        let ty = self.apply(&callee, &args);
+        Expr {
+            id: self.node(expr.id, &expr.span, ty),
+            ty: Ty::Int,
+            kind: ExprKind::Call {
+                callee: Box::new(callee),
+                args,
+            },
+        }
+    }
+
+    fn lower_right_section(&mut self, expr: &ast::Expr<P>, callee: Expr, rhs: Expr) -> Expr {
+        let ty = match self.shallow(&self.types[callee.id.0].clone()) {
+            Infer::Func(params, ret) if params.len() == 2 => {
+                self.unify_args(&params[1..], slice::from_ref(&rhs));
+                Infer::Func(vec![params[0].clone()], ret)
+            }
+            ty => {
+                let (lhs, ret) = (self.fresh(), self.fresh());
+                let rhs_t = self.types[rhs.id.0].clone();
+                let func = Infer::Func(vec![lhs.clone(), rhs_t], Box::new(ret.clone()));
+                let span = self.origins[callee.id.0].span.clone();
+                self.unify(&func, &ty, &span);
+                Infer::Func(vec![lhs], Box::new(ret))
+            }
+        };
+        Expr {
+            id: self.node(expr.id, &expr.span, ty),
+            ty: Ty::Int,
+            kind: ExprKind::RightSection {
+                callee: Box::new(callee),
+                rhs: Box::new(rhs),
+            },
+        }
+    }
+
+    // Type inference
+
+    fn fresh(&mut self) -> Infer {
+        self.vars.push(None);
+        Infer::Var(self.vars.len() - 1)
+    }
+
+    fn infer_type(&self, ty: &ast::Type<P>) -> Infer {
+        match ty.kind {
+            ast::TypeKind::Literal(ast::LitType::Int) => Infer::Int,
+            ast::TypeKind::Literal(ast::LitType::Float) => Infer::Float,
+            ast::TypeKind::Literal(ast::LitType::Void) => Infer::Void,
+            ast::TypeKind::Literal(ast::LitType::Str) => Infer::Str,
+            ast::TypeKind::Literal(ast::LitType::Char) => Infer::Char,
+            ast::TypeKind::Function(ref func_t) => Infer::Func(
+                func_t.args.iter().map(|arg| self.infer_type(arg)).collect(),
+                Box::new(self.infer_type(&func_t.ret)),
+            ),
+        }
+    }
+
+    /// Returns type of the call.
+    ///
+    /// Functions can be given fewer arguments than they have
+    /// parameters, which results in a function taking the rest
+    /// of them, or more if they return a function
+    /// which takes the remaining ones.
+    fn apply(&mut self, callee: &Expr, args: &[Expr]) -> Infer {
+        let mut ty = self.types[callee.id.0].clone();
+        let mut args = args;
+        loop {
+            match self.shallow(&ty) {
+                Infer::Func(params, ret) => {
+                    if args.len() < params.len() {
+                        self.unify_args(&params, args);
+                        return Infer::Func(params[args.len()..].to_vec(), ret);
+                    }
+                    self.unify_args(&params, &args[..params.len()]);
+                    args = &args[params.len()..];
+                    ty = *ret;
+                    if args.is_empty() {
+                        return ty;
+                    }
+                }
+                var @ Infer::Var(_) => {
+                    let ret = self.fresh();
+                    let params = args.iter().map(|arg| self.types[arg.id.0].clone());
+                    let func = Infer::Func(params.collect(), Box::new(ret.clone()));
+                    let span = self.origins[callee.id.0].span.clone();
+                    self.unify(&func, &var, &span);
+                    return ret;
+                }
+                other => {
+                    let msg = format!(
+                        "Expression of type `{}` is not a function",
+                        self.resolve(&other)
+                    );
+                    let span = self.origins[callee.id.0].span.clone();
+                    self.err(msg, &span);
+                    return self.fresh();
+                }
+            }
+        }
+    }
+
+    fn unify_args(&mut self, params: &[Infer], args: &[Expr]) {
+        for (param, arg) in params.iter().zip(args) {
+            let span = self.origins[arg.id.0].span.clone();
+            self.unify(param, &self.types[arg.id.0].clone(), &span);
+        }
+    }
+
+    /// Unifies types reporting the mismatch.
+    fn unify(&mut self, expected: &Infer, found: &Infer, span: &Span<P>) {
+        if !self.unify_inner(expected, found) {
+            let msg = format!(
+                "Mismatched types: expected `{}`, found `{}`",
+                self.resolve(expected),
+                self.resolve(found)
+            );
+            self.err(msg, span);
+        }
+    }
+
+    fn unify_inner(&mut self, a: &Infer, b: &Infer) -> bool {
+        match (self.shallow(a), self.shallow(b)) {
+            (Infer::Var(x), Infer::Var(y)) if x == y => true,
+            (Infer::Var(x), ty) | (ty, Infer::Var(x)) => {
+                if self.occurs(x, &ty) {
+                    return false;
+                }
+                self.vars[x] = Some(ty);
+                true
+            }
+            (Infer::Int, Infer::Int)
+            | (Infer::Float, Infer::Float)
+            | (Infer::Void, Infer::Void)
+            | (Infer::Str, Infer::Str)
+            | (Infer::Char, Infer::Char) => true,
+            (Infer::Func(a_params, a_ret), Infer::Func(b_params, b_ret)) => {
+                a_params.len() == b_params.len()
+                    && a_params
+                        .iter()
+                        .zip(&b_params)
+                        .all(|(a, b)| self.unify_inner(a, b))
+                    && self.unify_inner(&a_ret, &b_ret)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns type with the variables bound at the top level substituted.
+    fn shallow(&self, ty: &Infer) -> Infer {
+        let mut ty = ty;
+        while let Infer::Var(var) = *ty {
+            match self.vars[var] {
+                Some(ref bound) => ty = bound,
+                None => break,
+            }
+        }
+        ty.clone()
+    }
+
+    fn occurs(&self, var: usize, ty: &Infer) -> bool {
+        match self.shallow(ty) {
+            Infer::Var(other) => var == other,
+            Infer::Func(params, ret) => {
+                params.iter().any(|param| self.occurs(var, param)) || self.occurs(var, &ret)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns type with all of the bound variables substituted.
+    fn resolve(&self, ty: &Infer) -> Infer {
+        match self.shallow(ty) {
+            Infer::Func(params, ret) => Infer::Func(
+                params.iter().map(|param| self.resolve(param)).collect(),
+                Box::new(self.resolve(&ret)),
+            ),
+            ty => ty,
+        }
+    }
+
+    /// Returns concrete type of the node.
+    fn ty(&self, id: HirId) -> Ty {
+        fn concrete(ty: Infer) -> Ty {
+            match ty {
+                Infer::Var(_) | Infer::Int => Ty::Int,
+                Infer::Float => Ty::Float,
+                Infer::Void => Ty::Void,
+                Infer::Str => Ty::Str,
+                Infer::Char => Ty::Char,
+                Infer::Func(params, ret) => Ty::Func(
+                    params.into_iter().map(concrete).collect(),
+                    Box::new(concrete(*ret)),
+                ),
+            }
+        }
+        concrete(self.resolve(&self.types[id.0]))
+    }
+
+    fn fill_types(&self, expr: &mut Expr) {
+        expr.ty = self.ty(expr.id);
+        match expr.kind {
+            ExprKind::Call {
+                ref mut callee,
+                ref mut args,
+            } => {
+                self.fill_types(callee);
+                for arg in args {
+                    self.fill_types(arg);
+                }
+            }
+            ExprKind::RightSection {
+                ref mut callee,
+                ref mut rhs,
+            } => {
+                self.fill_types(callee);
+                self.fill_types(rhs);
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_lexer::Lexer;
+    use ftl_parser::visitor_mut::visit_ast_mut;
+    use ftl_parser::Parser;
+    use ftl_pass::epr::ExprPrecReassoc;
+    use ftl_source::string;
+    use ftl_utility::RcRef;
+
+    type Lowered = (Option<Hir<string::Pointer>>, Vec<String>);
+
+    fn lower_with(src: &str, reassoc: bool) -> Lowered {
+        let sess = RcRef::new(Session::new(string::String::from(src)));
+        let mut parser = Parser::new(Lexer::new(sess.clone()), sess.clone());
+        let mut ast = parser.parse();
+        let mut sess = sess.borrow_mut();
+        if reassoc {
+            visit_ast_mut(&mut ExprPrecReassoc::new(&mut sess), &mut ast);
+        }
+        let hir = Lowering::new(&mut sess).lower(&ast);
+        let errors = sess.handler.errors().iter().map(|e| e.desc()).collect();
+        (hir, errors)
+    }
+
+    fn lower(src: &str) -> Hir<string::Pointer> {
+        match lower_with(src, true) {
+            (Some(hir), errors) if errors.is_empty() => hir,
+            (_, errors) => panic!("lowering failed: {:?}", errors),
+        }
+    }
+
+    fn errors(src: &str) -> Vec<String> {
+        let (hir, errors) = lower_with(src, true);
+        assert!(hir.is_none());
+        errors
+    }
+
+    fn types(hir: &Hir<string::Pointer>) -> Vec<String> {
+        hir.defs
+            .iter()
+            .map(|def| format!("{}: {}", def.name, def.ty))
+            .collect()
+    }
+
+    #[test]
+    fn resolving_and_desugaring() {
+        let hir = lower(
+            "infix 5 + a b: a\ninfix 10 * a b: b\n\
+             decl f int [b a]: int\ndef f x [a]: (x + 1) * x\ndef g: @f 2",
+        );
+        assert_eq!(
+            types(&hir),
+            vec![
+                "+: (int int) int",
+                "*: (int int) int",
+                "f: (int) int",
+                "g: () int"
+            ]
+        );
+        let f = hir.find("f").unwrap();
+        assert_eq!(f.def_id, DefId(2));
+        assert_eq!(f.kind, DefKind::Func);
+        let attrs: Vec<&str> = f.attrs.iter().map(|attr| attr.name.as_str()).collect();
+        assert_eq!(attrs, vec!["a", "b"]);
+        assert_eq!(
+            hir.find("*").unwrap().kind,
+            DefKind::Infix { precedence: 10 }
+        );
+        match f.body.as_ref().unwrap().kind {
+            ExprKind::Call {
+                ref callee,
+                ref args,
+            } => {
+                assert!(matches!(callee.kind, ExprKind::Def(DefId(1))));
+                match args[0].kind {
+                    ExprKind::Call {
+                        ref callee,
+                        ref args,
+                    } => {
+                        assert!(matches!(callee.kind, ExprKind::Def(DefId(0))));
+                        assert!(matches!(args[0].kind, ExprKind::Param(0)));
+                        assert!(matches!(args[1].kind, ExprKind::Int(1)));
+                    }
+                    _ => panic!("parentheses should be removed"),
+                }
+                assert!(matches!(args[1].kind, ExprKind::Param(0)));
+            }
+            _ => panic!("infix call should be lowered to call"),
+        }
+    }
+
+    #[test]
+    fn inferring_types_without_declarations() {
+        let hir = lower(
+            "infix 5 + a b: a\n\
+             def apply f x: @f x\n\
+             def inc a: a + 1\n\
+             def main: @apply inc 1\n\
+             def first a b: a\n\
+             def add1: @first 1\n\
+             def two: @add1 2\n\
+             def run g: @g",
+        );
+        assert_eq!(
+            types(&hir),
+            vec![
+                "+: (int int) int",
+                "apply: ((int) int int) int",
+                "inc: (int) int",
+                "main: () int",
+                "first: (int int) int",
+                "add1: () (int) int",
+                "two: () int",
+                "run: (() int) int",
+            ]
+        );
+    }
+
+    #[test]
+    fn declared_types() {
+        let hir = lower("decl f (int) void: void\ndef f g: @g 1\ndecl h: void");
+        assert_eq!(types(&hir), vec!["f: ((int) void) void", "h: () void"]);
+        assert!(hir.find("h").unwrap().body.is_none());
+        assert_eq!(
+            hir.find("f").unwrap().params[0].ty.to_string(),
+            "(int) void"
+        );
+    }
+
+    #[test]
+    fn typing_string_and_char_literals() {
+        let hir = lower("decl greet str char: void\ndef greet s c: @greet \"hi\" 'x'\ndef s: \"\"");
+        assert_eq!(types(&hir), vec!["greet: (str char) void", "s: () str"]);
+        assert_eq!(
+            errors("decl f: str\ndef f: 'a'"),
+            vec!["Mismatched types: expected `str`, found `char`"]
+        );
+    }
+
+    #[test]
+    fn lang_items() {
+        let hir = lower(
+            "decl fadd float float [lang_fadd]: float\n\
+             infix 5 +. a b: @fadd a b\n\
+             def add a b [inline lang_add]: a\n\
+             def f: 1.5 +. 2e3",
+        );
+        assert_eq!(
+            types(&hir),
+            vec![
+                "fadd: (float float) float",
+                "+.: (float float) float",
+                "add: (int int) int",
+                "f: () float"
+            ]
+        );
+        assert_eq!(hir.lang_item(LangItem::Add).unwrap().name, "add");
+        assert!(hir.lang_item(LangItem::FMul).is_none());
+    }
+
+    #[test]
+    fn lang_items_given_as_arguments() {
+        let hir = lower("decl mul int int [lang \"mul\"]: int\ndef f: @mul 1 2");
+        assert_eq!(hir.lang_item(LangItem::Mul).unwrap().name, "mul");
+        assert_eq!(
+            errors("def mul a [lang \"mul\"]: a\ndef times a b [lang_mul]: a"),
+            vec![
+                "Mismatched types: expected `(int int) int`, found `(_) _`",
+                "Lang item `lang_mul` is already defined by `mul`",
+            ]
+        );
+    }
+
+    #[test]
+    fn lowering_attribute_arguments() {
+        let hir = lower("def f a b [inline(always) lang \"add\" align(8)]: a");
+        let f = hir.find("f").unwrap();
+        assert_eq!(
+            f.attrs,
+            vec![
+                Attr {
+                    name: Symbol::new("align"),
+                    args: vec![AttrArg::Int(8)],
+                },
+                Attr {
+                    name: Symbol::new("inline"),
+                    args: vec![AttrArg::Ident(Symbol::new("always"))],
+                },
+                Attr {
+                    name: Symbol::new("lang"),
+                    args: vec![AttrArg::Str(Symbol::new("add"))],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reporting_lang_item_errors() {
+        assert_eq!(
+            errors(
+                "def add a [lang_add]: a\ndef plus a b [lang_add]: a\ndef sub a b [lang_fsub]: 1"
+            ),
+            vec![
+                "Mismatched types: expected `(int int) int`, found `(_) _`",
+                "Lang item `lang_add` is already defined by `add`",
+                "Mismatched types: expected `float`, found `int`",
+            ]
+        );
+    }
+
+    #[test]
+    fn lowering_prefix_calls() {
+        let hir = lower(
+            "decl fneg float: float\nprefix - a: @fneg a\ninfix 5 - a b: a\n\
+             def f x: x - -x\nprefix 3 ! b: b",
+        );
+        assert_eq!(
+            types(&hir),
+            vec![
+                "fneg: (float) float",
+                "-: (float) float",
+                "-: (float float) float",
+                "f: (float) float",
+                "!: (int) int"
+            ]
+        );
+        assert_eq!(hir.defs[1].kind, DefKind::Prefix { precedence: None });
+        assert_eq!(
+            hir.find("!").unwrap().kind,
+            DefKind::Prefix {
+                precedence: Some(3)
+            }
+        );
+        match hir.find("f").unwrap().body.as_ref().unwrap().kind {
+            ExprKind::Call {
+                ref callee,
+                ref args,
+            } => {
+                assert!(matches!(callee.kind, ExprKind::Def(DefId(2))));
+                assert!(matches!(args[0].kind, ExprKind::Param(0)));
+                match args[1].kind {
+                    ExprKind::Call { ref callee, .. } => {
+                        assert!(matches!(callee.kind, ExprKind::Def(DefId(1))))
+                    }
+                    _ => panic!("prefix call should be lowered to call"),
+                }
+            }
+            _ => panic!("infix call should be lowered to call"),
+        }
+        assert_eq!(
+            errors("infix 5 - a b: a\nprefix ! a: a\nprefix ! b: b\ndef f x: -x"),
+            vec![
+                "Prefix operator `!` is already defined",
+                "Unknown prefix operator `-`",
+            ]
+        );
+    }
+
+    #[test]
+    fn lowering_operator_sections() {
+        let hir = lower(
+            "decl fadd float float: float\ninfix 5 + a b: @fadd a b\n\
+             def plus: (+)\ndef inc: (+ 1.0)\ndef twice x: (x +)",
+        );
+        assert_eq!(
+            types(&hir),
+            vec![
+                "fadd: (float float) float",
+                "+: (float float) float",
+                "plus: () (float float) float",
+                "inc: () (float) float",
+                "twice: (float) (float) float",
+            ]
+        );
+        assert!(matches!(
+            hir.find("plus").unwrap().body.as_ref().unwrap().kind,
+            ExprKind::Def(DefId(1))
+        ));
+        match hir.find("inc").unwrap().body.as_ref().unwrap().kind {
+            ExprKind::RightSection {
+                ref callee,
+                ref rhs,
+            } => {
+                assert!(matches!(callee.kind, ExprKind::Def(DefId(1))));
+                assert!(matches!(rhs.kind, ExprKind::Float(_)));
+            }
+            _ => panic!("right section should be lowered to right section"),
+        }
+        match hir.find("twice").unwrap().body.as_ref().unwrap().kind {
+            ExprKind::Call { ref args, .. } => {
+                assert_eq!(args.len(), 1);
+                assert!(matches!(args[0].kind, ExprKind::Param(0)));
+            }
+            _ => panic!("left section should be lowered to partial call"),
+        }
+        assert_eq!(
+            errors("decl fadd float float: float\ninfix 5 + a b: @fadd a b\ndef f: (+ 'a')"),
+            vec!["Mismatched types: expected `float`, found `char`"]
+        );
+    }
+
+    #[test]
+    fn reporting_resolution_errors() {
+        assert_eq!(
+            errors("def f x: @g x y"),
+            vec!["Unresolved name `g`", "Unresolved name `y`"]
+        );
+        assert_eq!(
+            errors(
+                "decl f: int\ndecl f: int\ndef f: 1\ndef f: 2\ninfix 5 + a b: a\ninfix 6 + a b: b"
+            ),
+            vec![
+                "Function `f` is already declared",
+                "Function `f` is already defined",
+                "Operator `+` is already defined",
+            ]
+        );
+        let (hir, errors) = lower_with("def f: 1 + 2", false);
+        assert!(hir.is_none());
+        assert_eq!(errors, vec!["Unknown operator `+`"]);
+    }
+
+    #[test]
+    fn reporting_type_errors() {
+        assert_eq!(
+            errors("decl f int: int\ndef f a: a\ndef g: @f f"),
+            vec!["Mismatched types: expected `int`, found `(int) int`"]
+        );
+        assert_eq!(
+            errors("decl f: void\ndef f: 1"),
+            vec!["Mismatched types: expected `void`, found `int`"]
+        );
+        assert_eq!(
+            errors("def f: @1 2"),
+            vec!["Expression of type `int` is not a function"]
+        );
+        assert_eq!(
+            errors("decl f int: int\ndef f a b: a"),
+            vec!["Function `f` is declared with 1 parameters but defined with 2"]
+        );
+        assert_eq!(
+            errors("def f x: @x x"),
+            vec!["Mismatched types: expected `(_) _`, found `_`"]
+        );
+    }
+
+    #[test]
+    fn mapping_ids_to_source() {
+        let src = "infix 5 + a b: a\ndef f x: ((x + 1))";
+        let hir = lower(src);
+        let text = |id: HirId| {
+            let span = hir.span(id);
+            &src[span.beg.offset()..span.end.offset()]
+        };
+        let f = hir.find("f").unwrap();
+        assert_eq!(text(f.params[0].id), "x");
+        let body = f.body.as_ref().unwrap();
+        assert_eq!(text(body.id), "x + 1");
+        match body.kind {
+            ExprKind::Call {
+                ref callee,
+                ref args,
+            } => {
+                assert_eq!(text(callee.id), "+");
+                assert_eq!(text(args[1].id), "1");
+            }
+            _ => unreachable!(),
+        }
+        // infix: def, a, b, a; def: def, x, x, +, 1, call
+        assert_eq!(hir.len(), 10);
+        assert_ne!(hir.node(body.id), hir.node(f.id));
+    }
+}
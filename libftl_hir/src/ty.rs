@@ -0,0 +1,57 @@
+//! Types of the HIR nodes.
+
+use std::fmt;
+
+/// Concrete type.
+///
+/// Displayed the same way types are written in the source.
+///
+/// # Examples
+///
+/// ```
+/// use ftl_hir::Ty;
+///
+/// let ty = Ty::Func(vec![Ty::Int, Ty::Func(vec![], Ty::Void.into())], Ty::Int.into());
+/// assert_eq!(ty.to_string(), "(int () void) int");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Ty {
+    Int,
+    Float,
+    Void,
+    Str,
+    Char,
+    Func(Vec<Ty>, Box<Ty>),
+}
+
+impl Ty {
+    /// Returns parameters and the return type of the function type.
+    pub fn as_func(&self) -> Option<(&[Ty], &Ty)> {
+        match self {
+            Ty::Func(params, ret) => Some((params, ret)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Int => write!(f, "int"),
+            Ty::Float => write!(f, "float"),
+            Ty::Void => write!(f, "void"),
+            Ty::Str => write!(f, "str"),
+            Ty::Char => write!(f, "char"),
+            Ty::Func(params, ret) => {
+                write!(f, "(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", param)?;
+                }
+                write!(f, ") {}", ret)
+            }
+        }
+    }
+}
@@ -9,18 +9,11 @@ pub fn is_part_of_ident(ch: char) -> bool {
 }
 
 pub fn is_part_of_op(ch: char) -> bool {
-    match ch {
-        '+' | ',' | '-' | ':' | '~' | '!' | '@' | '$' | '%' | '^' | '&' | '*' | '=' | ';' | '<'
-        | '.' | '?' | '>' | '|' | '/' | '{' | '}' => true,
-        _ => false,
-    }
+    "+,-:~!@$%^&*=;<.?>|/{}".contains(ch)
 }
 
 pub fn is_part_of_parenthesis(ch: char) -> bool {
-    match ch {
-        '(' | ')' | '[' | ']' => true,
-        _ => false,
-    }
+    matches!(ch, '(' | ')' | '[' | ']')
 }
 
 pub fn is_operator(symbol: &str) -> Option<token::Kind> {
@@ -46,6 +39,7 @@ pub fn is_keyword(symbol: &str) -> Option<token::Kind> {
     match symbol {
         "def" => Some(token::Kind::FuncDef),
         "infix" => Some(token::Kind::InfixDef),
+        "prefix" => Some(token::Kind::PrefixDef),
         "decl" => Some(token::Kind::FuncDecl),
         _ => None,
     }
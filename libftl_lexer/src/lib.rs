@@ -1,15 +1,12 @@
-#![allow(clippy::should_implement_trait)]
-
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Debug, Display};
 
 use log::trace;
 
-use ftl_error;
 use ftl_error::LangError;
+use ftl_session::symbol::Symbol;
 use ftl_session::Session;
-use ftl_source;
 use ftl_source::{Source, Span};
 use ftl_utility::RcRef;
 
@@ -27,7 +24,30 @@ pub struct Lexer<S: Source> {
     /// session.borrow().src.borrow_mut() nad so on.
     src: RcRef<S>,
 
-    curr_token: Option<Token<S>>,
+    /// Tokens from the oldest checkpoint, or from the current
+    /// token if there are none, up to the furthest peeked one.
+    /// Ends with None once the end of the source has been reached.
+    buffer: Vec<Option<Token<S>>>,
+    /// Index of the first buffered token in the token stream.
+    base: usize,
+    /// Index of the current token in the token stream.
+    pos: usize,
+    /// Number of the checkpoints which haven't been
+    /// rewound to or released yet.
+    checkpoints: usize,
+
+    /// If set whitespaces and comments are attached to
+    /// the tokens and every token is remembered.
+    lossless: bool,
+    tokens: Vec<Token<S>>,
+    /// Trivia after the last token.
+    trailing: Vec<token::Trivia<S::Pointer>>,
+    /// Operator lexed together with the integer preceding it.
+    pending: Option<Token<S>>,
+    /// Identifier or operator being collected, reused
+    /// for every one of them so that they are interned
+    /// without allocating a string each.
+    symbol: String,
 }
 
 impl<S> Lexer<S>
@@ -36,47 +56,178 @@ where
     S::Pointer: 'static,
 {
     pub fn new(session: RcRef<Session<S>>) -> Self {
+        Self::_new(session, false)
+    }
+
+    /// Creates lexer which attaches skipped whitespaces
+    /// and comments to the tokens following them and
+    /// remembers every token it returned, so that
+    /// the source can be reconstructed from them.
+    pub fn lossless(session: RcRef<Session<S>>) -> Self {
+        Self::_new(session, true)
+    }
+
+    fn _new(session: RcRef<Session<S>>, lossless: bool) -> Self {
         let mut s = Self {
             session: session.clone(),
             src: session.borrow().src.clone(),
-            curr_token: None,
+            buffer: Vec::new(),
+            base: 0,
+            pos: 0,
+            checkpoints: 0,
+            lossless,
+            tokens: Vec::new(),
+            trailing: Vec::new(),
+            pending: None,
+            symbol: String::new(),
         };
-        s.next();
+        let tok = s.lex();
+        s.buffer.push(tok);
         s
     }
 
+    pub fn is_lossless(&self) -> bool {
+        self.lossless
+    }
+
+    /// Returns every token lexed so far, emptying the list.
+    /// Only the lossless lexer remembers the tokens.
+    pub fn take_tokens(&mut self) -> Vec<Token<S>> {
+        std::mem::take(&mut self.tokens)
+    }
+
+    /// Returns trivia after the last token.
+    /// It's known only after the end of the source has been reached
+    /// by the lossless lexer.
+    pub fn trailing_trivia(&self) -> &[token::Trivia<S::Pointer>] {
+        &self.trailing
+    }
+
     pub fn curr(&self) -> Option<Token<S>> {
-        self.curr_token.clone()
+        self.buffer[self.pos - self.base].clone()
+    }
+
+    /// Moves to the next token and returns it.
+    pub fn advance(&mut self) -> Option<Token<S>> {
+        // There are no tokens past the end of the source.
+        self.buffer[self.pos - self.base].as_ref()?;
+        self.pos += 1;
+        self.fill(self.pos);
+        self.trim();
+        self.curr()
+    }
+
+    /// Returns the token `k` tokens after the current one
+    /// without moving to it, `peek_nth(0)` being the current token.
+    ///
+    /// Tokens are lexed only once, the peeked
+    /// ones are remembered until they are passed.
+    pub fn peek_nth(&mut self, k: usize) -> Option<Token<S>> {
+        if self.fill(self.pos + k) {
+            self.buffer[self.pos + k - self.base].clone()
+        } else {
+            None
+        }
+    }
+
+    /// Remembers the current place in the token stream
+    /// so that it can be rewound to later.
+    ///
+    /// Every checkpoint has to be either rewound to or released,
+    /// as the tokens after the oldest one are kept until then.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.checkpoints += 1;
+        Checkpoint { pos: self.pos }
+    }
+
+    /// Moves back to the checkpoint without lexing
+    /// the tokens after it again.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.pos;
+        self.release(checkpoint);
+    }
+
+    /// Forgets the checkpoint keeping the current place.
+    pub fn release(&mut self, _checkpoint: Checkpoint) {
+        self.checkpoints -= 1;
+        self.trim();
     }
 
-    pub fn next(&mut self) -> Option<Token<S>> {
-        trace!("next(): Getting curr char from src");
+    /// Lexes tokens up to the one with the given index in the stream.
+    /// Returns false if the source ends before it.
+    fn fill(&mut self, pos: usize) -> bool {
+        while self.base + self.buffer.len() <= pos {
+            if let Some(None) = self.buffer.last() {
+                return false;
+            }
+            let tok = self.lex();
+            self.buffer.push(tok);
+        }
+        true
+    }
+
+    /// Drops the passed tokens no checkpoint can be rewound to.
+    fn trim(&mut self) {
+        if self.checkpoints == 0 && self.pos > self.base {
+            self.buffer.drain(..self.pos - self.base);
+            self.base = self.pos;
+        }
+    }
+
+    fn lex(&mut self) -> Option<Token<S>> {
+        if let Some(tok) = self.pending.take() {
+            trace!("lex(): Returning operator following the integer");
+            if self.lossless {
+                self.tokens.push(tok.clone());
+            }
+            return Some(tok);
+        }
+        trace!("lex(): Getting curr char from src");
+        let mut trivia = Vec::new();
         let mut opt = self.curr_char();
         while let Some(ch) = opt {
-            trace!("next(): Its Some(_)");
-            if ch == '#' || ch.is_whitespace() || ch == '\n' {
-                trace!("next(): Whitespace or newline, skipping");
+            trace!("lex(): Its Some(_)");
+            let beg = self.src_ptr();
+            let kind = if ch == '#' {
+                trace!("lex(): Comment, skipping");
+                self.skip_comment()
+            } else if ch.is_whitespace() {
+                trace!("lex(): Whitespace or newline, skipping");
                 self.skip_whitespaces();
-                self.skip_comments();
-                trace!("next(): Skipped, taking next char");
-                opt = self.curr_char();
+                token::Kind::Whitespace
             } else {
-                trace!("next(): Valid character");
+                trace!("lex(): Valid character");
                 break;
+            };
+            if self.lossless || kind == token::Kind::DocComment {
+                trivia.push(self.trivia(kind, beg));
             }
+            trace!("lex(): Skipped, taking next char");
+            opt = self.curr_char();
         }
-        let tok = match opt {
-            Some(ch) if ch.is_digit(10) => self.collect_integer(),
+        let mut tok = match opt {
+            Some(ch) if ch.is_ascii_digit() => self.collect_number(),
             Some(ch) if helpers::is_beg_of_ident(ch) => self.collect_identifier(),
             Some(ch) if helpers::is_part_of_op(ch) => self.collect_operator(),
-            Some(ch) if ch == '`' => self.collect_infix(),
+            Some('`') => self.collect_infix(),
+            Some('"') => self.collect_string(),
+            Some('\'') => self.collect_char_literal(),
             Some(ch) if helpers::is_part_of_parenthesis(ch) => self.collect_parenthesis(),
             Some(_) => self.collect_char(),
             _ => None,
         };
-        self.curr_token = tok;
-        trace!("next(): Returning token");
-        self.curr()
+        match tok {
+            Some(ref mut tok) => {
+                tok.trivia = trivia;
+                if self.lossless {
+                    self.tokens.push(tok.clone());
+                }
+            }
+            None if self.lossless => self.trailing.extend(trivia),
+            None => (),
+        }
+        trace!("lex(): Returning token");
+        tok
     }
 
     fn curr_char(&self) -> Option<char> {
@@ -87,7 +238,16 @@ where
         self.src.borrow_mut().next_char()
     }
 
+    /// Returns pointer to the end of the current token,
+    /// or to the end of the source if there are no more tokens.
     pub fn curr_ptr(&self) -> S::Pointer {
+        match self.buffer[self.pos - self.base] {
+            Some(ref tok) => tok.span.end.clone(),
+            None => self.src_ptr(),
+        }
+    }
+
+    fn src_ptr(&self) -> S::Pointer {
         self.src.borrow().curr_ptr()
     }
 
@@ -101,85 +261,247 @@ where
         }
     }
 
-    fn skip_comments(&mut self) {
-        if let Some('#') = self.curr_char() {
-            while let Some(ch) = self.next_char() {
-                if ch == '\n' {
-                    self.next_char();
-                    break;
-                }
+    /// Skips comment returning its kind.
+    ///
+    /// Line comment, as well as the doc comment starting with `##`,
+    /// is skipped leaving the new line character ending it as the current one.
+    /// Block comment `#{ ... }#` can span many lines and have
+    /// other block comments nested in it.
+    fn skip_comment(&mut self) -> token::Kind {
+        let beg = self.src_ptr();
+        let kind = match self.next_char() {
+            Some('{') => {
+                self.skip_block_comment(beg);
+                return token::Kind::Comment;
             }
+            Some('#') => token::Kind::DocComment,
+            _ => token::Kind::Comment,
+        };
+        let mut opt = self.curr_char();
+        while let Some(ch) = opt {
+            if ch == '\n' {
+                break;
+            }
+            opt = self.next_char();
         }
+        kind
     }
 
-    fn collect_integer(&mut self) -> Option<Token<S>> {
-        trace!("collect_integer(): collecting integer");
-        if let Some('0') = self.curr_char() {
-            trace!("collect_integer(): It's 0 literal");
-            return self.collect_integer_zero_literal();
+    /// Skips block comment past its closing `}#`,
+    /// the opening `#{` being already the current characters.
+    fn skip_block_comment(&mut self, beg: S::Pointer) {
+        let mut depth = 1;
+        let mut prev = None;
+        while depth > 0 {
+            let ch = match self.next_char() {
+                Some(ch) => ch,
+                None => {
+                    self.unterminated_comment_error(beg);
+                    return;
+                }
+            };
+            // Characters closing or opening the comment
+            // cannot be a part of the next delimiter.
+            prev = match (prev, ch) {
+                (Some('#'), '{') => {
+                    depth += 1;
+                    None
+                }
+                (Some('}'), '#') => {
+                    depth -= 1;
+                    None
+                }
+                _ => Some(ch),
+            };
         }
-        trace!("collect_integer(): None 0 literal");
-        self.collect_non_zero_integer_literal()
-    }
-
-    fn collect_integer_zero_literal(&mut self) -> Option<Token<S>> {
-        let beg = self.curr_ptr();
-        match self.next_char() {
-            Some(ch) if ch.is_digit(10) => {
-                trace!("collect_integer_zero_literal(): Integer starting with 0 error");
-                self.integers_cannot_start_with_zero_error(beg.clone());
-                let symbol = String::from("0");
-                self.collect_poisoned_integer(symbol, beg)
-            }
-            Some(ch) if ch.is_alphabetic() => {
-                trace!("collect_integer_zero_literal(): {} is not an integer", ch);
-                self.not_an_integer_error(beg.clone());
-                let symbol = String::from("0");
-                self.collect_poisoned_integer(symbol, beg)
-            }
-            _ => Some(token::Token {
-                kind: token::Kind::IntLiteral,
-                value: token::Value::Integer(0),
-                span: Span {
-                    beg,
-                    end: self.curr_ptr(),
-                },
-            }),
+        self.next_char();
+    }
+
+    fn trivia(&self, kind: token::Kind, beg: S::Pointer) -> token::Trivia<S::Pointer> {
+        let end = self.src_ptr();
+        token::Trivia {
+            kind,
+            text: self.src.borrow().source_between(&beg, &end),
+            span: Span { beg, end },
         }
     }
 
-    fn collect_non_zero_integer_literal(&mut self) -> Option<Token<S>> {
+    /// Collects number literal.
+    ///
+    /// Integers can be written in the binary (`0b`), octal (`0o`),
+    /// hexadecimal (`0x`) or decimal base. Decimal numbers with
+    /// the fractional part (`1.5`) or the exponent (`2e10`)
+    /// are floats. In both cases digits can be separated with `_`.
+    ///
+    /// As `.` can also be a part of an operator the number is
+    /// a float only if `.` is followed by a digit, otherwise the
+    /// operator starting with it is left as the next token.
+    ///
+    /// Literals which are not valid numbers or don't fit
+    /// in `u64` or `f64` are reported and returned as poisoned tokens.
+    fn collect_number(&mut self) -> Option<Token<S>> {
+        trace!("collect_number(): collecting number");
+        let beg = self.src_ptr();
         let mut symbol = String::new();
-        trace!("collect_non_zero_integer_literal(): collecting integer");
-
-        symbol.push(self.curr_char().unwrap());
-        trace!("collect_non_zero_integer_literal(): symbol is {}", symbol);
-        let beg = self.curr_ptr();
+        let mut digits = String::new();
+        let mut radix = 10;
+        let mut zero_end = None;
+        if let Some('0') = self.curr_char() {
+            symbol.push('0');
+            let next = self.next_char();
+            zero_end = Some(self.src_ptr());
+            radix = match next {
+                Some('b') => 2,
+                Some('o') => 8,
+                Some('x') => 16,
+                _ => 10,
+            };
+            if radix == 10 {
+                digits.push('0');
+            } else {
+                trace!("collect_number(): integer in base {}", radix);
+                symbol.push(self.curr_char().unwrap());
+                self.next_char();
+            }
+        }
+        if !self.collect_digits(radix, &beg, &mut symbol, &mut digits) {
+            return self.collect_poisoned_integer(symbol, beg);
+        }
+        if digits.is_empty() {
+            trace!("collect_number(): no digits after the base prefix");
+            self.not_an_integer_error(beg.clone());
+            return self.collect_poisoned_integer(symbol, beg);
+        }
+        if radix == 10 && digits.len() > 1 && digits.starts_with('0') {
+            trace!("collect_number(): Integer starting with 0 error");
+            self.integers_cannot_start_with_zero_error(beg.clone(), zero_end.unwrap());
+            return self.collect_poisoned_integer(symbol, beg);
+        }
+        let mut end = None;
+        let mut is_float = false;
+        if radix == 10 {
+            if let Some('.') = self.curr_char() {
+                let dot = self.src_ptr();
+                match self.next_char() {
+                    Some(ch) if ch.is_ascii_digit() => {
+                        trace!("collect_number(): fractional part");
+                        is_float = true;
+                        symbol.push('.');
+                        digits.push('.');
+                        if !self.collect_digits(radix, &beg, &mut symbol, &mut digits) {
+                            return self.collect_poisoned_integer(symbol, beg);
+                        }
+                    }
+                    _ => {
+                        trace!("collect_number(): operator after the integer");
+                        self.symbol.clear();
+                        self.symbol.push('.');
+                        self.pending = self.collect_operator_rest(dot.clone());
+                        end = Some(dot);
+                    }
+                }
+            }
+            if let (None, Some(exp @ 'e')) | (None, Some(exp @ 'E')) = (&end, self.curr_char()) {
+                trace!("collect_number(): exponent");
+                is_float = true;
+                symbol.push(exp);
+                digits.push('e');
+                if let Some(sign @ '+') | Some(sign @ '-') = self.next_char() {
+                    symbol.push(sign);
+                    digits.push(sign);
+                    self.next_char();
+                }
+                let len = digits.len();
+                if !self.collect_digits(radix, &beg, &mut symbol, &mut digits) {
+                    return self.collect_poisoned_integer(symbol, beg);
+                }
+                if digits.len() == len {
+                    trace!("collect_number(): exponent without digits");
+                    self.number_error(LexingErrorKind::MissingExponent, beg.clone());
+                    return self.collect_poisoned_integer(symbol, beg);
+                }
+            }
+        }
+        let end = end.unwrap_or_else(|| self.src_ptr());
+        let value = if is_float {
+            match digits.parse::<f64>() {
+                Ok(value) if value.is_finite() => token::Value::Float(value),
+                _ => {
+                    trace!("collect_number(): float {} overflows", symbol);
+                    self.number_error(LexingErrorKind::FloatOverflow, beg.clone());
+                    return Some(self.poisoned_number(symbol, beg, end));
+                }
+            }
+        } else {
+            match u64::from_str_radix(&digits, radix) {
+                Ok(value) => token::Value::Integer(value),
+                Err(_) => {
+                    trace!("collect_number(): integer {} overflows", symbol);
+                    self.number_error(LexingErrorKind::IntegerOverflow, beg.clone());
+                    return Some(self.poisoned_number(symbol, beg, end));
+                }
+            }
+        };
+        trace!("collect_number(): collected number {:?}", value);
+        Some(token::Token {
+            kind: if is_float {
+                token::Kind::FloatLiteral
+            } else {
+                token::Kind::IntLiteral
+            },
+            value,
+            span: Span { beg, end },
+            trivia: Vec::new(),
+        })
+    }
 
-        while let Some(ch) = self.next_char() {
-            trace!("collect_non_zero_integer_literal(): character is {}", ch);
+    /// Collects digits in the given base, which can be separated with `_`,
+    /// up to the exponent of the decimal number. Digits are pushed
+    /// to `digits`, and together with the separators to `symbol`.
+    ///
+    /// Returns false if the literal turned out not to be
+    /// a number, in which case the error has been reported.
+    fn collect_digits(
+        &mut self,
+        radix: u32,
+        beg: &S::Pointer,
+        symbol: &mut String,
+        digits: &mut String,
+    ) -> bool {
+        let mut opt = self.curr_char();
+        while let Some(ch) = opt {
+            trace!("collect_digits(): character is {}", ch);
             match ch {
-                ch if ch.is_digit(10) => symbol.push(ch),
+                '_' => symbol.push(ch),
+                ch if ch.is_digit(radix) => {
+                    symbol.push(ch);
+                    digits.push(ch);
+                }
+                'e' | 'E' if radix == 10 => break,
+                ch if ch.is_ascii_digit() => {
+                    trace!("collect_digits(): invalid digit for base {}", radix);
+                    self.invalid_digit_error(ch, radix);
+                    return false;
+                }
                 ch if ch.is_alphabetic() => {
-                    trace!("collect_non_zero_integer_literal(): not an integer");
+                    trace!("collect_digits(): not a number");
                     self.not_an_integer_error(beg.clone());
-                    return self.collect_poisoned_integer(symbol, beg);
+                    return false;
                 }
                 _ => break,
             }
+            opt = self.next_char();
+        }
+        true
+    }
+
+    fn poisoned_number(&self, symbol: String, beg: S::Pointer, end: S::Pointer) -> Token<S> {
+        token::Token {
+            kind: token::Kind::Poisoned,
+            value: token::Value::Symbol(self.intern(&symbol)),
+            span: Span { beg, end },
+            trivia: Vec::new(),
         }
-        trace!(
-            "collect_non_zero_integer_literal(): collected integer {}",
-            symbol
-        );
-        Some(token::Token {
-            kind: token::Kind::IntLiteral,
-            value: token::Value::Integer(symbol.parse().unwrap()),
-            span: Span {
-                beg,
-                end: self.curr_ptr(),
-            },
-        })
     }
 
     fn collect_poisoned_integer(
@@ -195,22 +517,15 @@ where
             symbol.push(ch);
             opt = self.next_char();
         }
-        Some(token::Token {
-            kind: token::Kind::Poisoned,
-            value: token::Value::String(symbol),
-            span: Span {
-                beg,
-                end: self.curr_ptr(),
-            },
-        })
+        Some(self.poisoned_number(symbol, beg, self.src_ptr()))
     }
 
     fn collect_identifier(&mut self) -> Option<Token<S>> {
         trace!("collect_identifier(): collecting identifier");
-        let mut symbol = String::new();
+        self.symbol.clear();
         trace!("collect_identifier(): unwraping curr_char");
-        symbol.push(self.curr_char().unwrap());
-        let beg = self.curr_ptr();
+        self.symbol.push(self.curr_char().unwrap());
+        let beg = self.src_ptr();
         while let Some(ch) = self.next_char() {
             trace!("collect_identifier(): curr_char {}", ch);
             if !helpers::is_part_of_ident(ch) {
@@ -218,100 +533,279 @@ where
                 break;
             }
             trace!("collect_identifier(): adding char to symbol");
-            symbol.push(ch);
-            trace!("collect_identifier(): curr symbol {}", symbol);
+            self.symbol.push(ch);
+            trace!("collect_identifier(): curr symbol {}", self.symbol);
         }
 
         let mut kind = token::Kind::Identifier;
-        if let Some(k_kind) = helpers::is_keyword(&symbol) {
-            trace!("collect_identifier(): {} is a keyword", symbol);
+        if let Some(k_kind) = helpers::is_keyword(&self.symbol) {
+            trace!("collect_identifier(): {} is a keyword", self.symbol);
             kind = k_kind;
         }
         trace!("collect_identifier(): returning token");
         Some(token::Token {
             kind,
-            value: token::Value::String(symbol),
+            value: token::Value::Symbol(self.intern(&self.symbol)),
             span: Span {
                 beg,
-                end: self.curr_ptr(),
+                end: self.src_ptr(),
             },
+            trivia: Vec::new(),
         })
     }
 
     fn collect_operator(&mut self) -> Option<Token<S>> {
         trace!("collect_operator(): collecting operator");
-        let mut symbol = String::new();
+        self.symbol.clear();
         trace!("collect_operator(): unwraping curr_char");
-        symbol.push(self.curr_char().unwrap());
-        let beg = self.curr_ptr();
-        while let Some(ch) = self.next_char() {
+        self.symbol.push(self.curr_char().unwrap());
+        let beg = self.src_ptr();
+        self.next_char();
+        self.collect_operator_rest(beg)
+    }
+
+    /// Collects rest of the operator which starts with
+    /// the collected symbol and the current character.
+    fn collect_operator_rest(&mut self, beg: S::Pointer) -> Option<Token<S>> {
+        let mut opt = self.curr_char();
+        while let Some(ch) = opt {
             trace!("collect_operator(): curr_char {}", ch);
             if !helpers::is_part_of_op(ch) {
                 trace!("collect_operator(): char {} is not part of an operator", ch);
                 break;
             }
             trace!("collect_operator(): adding char to symbol");
-            symbol.push(ch);
-            trace!("collect_operator(): curr symbol {}", symbol);
+            self.symbol.push(ch);
+            trace!("collect_operator(): curr symbol {}", self.symbol);
+            opt = self.next_char();
         }
         trace!("collect_operator(): Returning operator");
         Some(token::Token {
-            kind: if let Some(kind) = helpers::is_operator(&symbol) {
+            kind: if let Some(kind) = helpers::is_operator(&self.symbol) {
                 kind
             } else {
                 token::Kind::Operator
             },
-            value: token::Value::String(symbol),
+            value: token::Value::Symbol(self.intern(&self.symbol)),
             span: Span {
                 beg,
-                end: self.curr_ptr(),
+                end: self.src_ptr(),
             },
+            trivia: Vec::new(),
         })
     }
 
     fn collect_parenthesis(&mut self) -> Option<Token<S>> {
         trace!("collect_parenthesis(): collecting parenthesis");
-        let beg = self.curr_ptr();
-        let mut symbol = String::new();
-        symbol.push(self.curr_char().unwrap());
+        let beg = self.src_ptr();
+        let mut buf = [0; 4];
+        let symbol = self.curr_char().unwrap().encode_utf8(&mut buf);
+        let kind = helpers::is_parenthesis(symbol).unwrap();
+        let value = token::Value::Symbol(self.intern(symbol));
         self.next_char();
         Some(token::Token {
-            kind: helpers::is_parenthesis(&symbol).unwrap(),
-            value: token::Value::String(symbol),
+            kind,
+            value,
             span: Span {
                 beg,
-                end: self.curr_ptr(),
+                end: self.src_ptr(),
             },
+            trivia: Vec::new(),
         })
     }
 
     fn collect_infix(&mut self) -> Option<Token<S>> {
+        let beg = self.src_ptr();
         self.next_char();
         if let Some(mut tok) = self.collect_identifier() {
             tok.kind = token::Kind::InfixIdent;
+            tok.span.beg = beg;
             Some(tok)
         } else {
             None
         }
     }
 
+    fn collect_string(&mut self) -> Option<Token<S>> {
+        trace!("collect_string(): collecting string");
+        let beg = self.src_ptr();
+        let (kind, value) = match self.collect_quoted('"') {
+            Some(content) => (
+                token::Kind::StrLiteral,
+                token::Value::Symbol(self.intern(&content)),
+            ),
+            None => (token::Kind::Poisoned, self.poisoned_value(&beg)),
+        };
+        Some(token::Token {
+            kind,
+            value,
+            span: Span {
+                beg,
+                end: self.src_ptr(),
+            },
+            trivia: Vec::new(),
+        })
+    }
+
+    fn collect_char_literal(&mut self) -> Option<Token<S>> {
+        trace!("collect_char_literal(): collecting character");
+        let beg = self.src_ptr();
+        let mut value = None;
+        if let Some(content) = self.collect_quoted('\'') {
+            let mut chars = content.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => value = Some(token::Value::Char(ch)),
+                _ => {
+                    trace!("collect_char_literal(): {:?} is not one character", content);
+                    self.invalid_char_literal_error(beg.clone());
+                }
+            }
+        }
+        let (kind, value) = match value {
+            Some(value) => (token::Kind::CharLiteral, value),
+            None => (token::Kind::Poisoned, self.poisoned_value(&beg)),
+        };
+        Some(token::Token {
+            kind,
+            value,
+            span: Span {
+                beg,
+                end: self.src_ptr(),
+            },
+            trivia: Vec::new(),
+        })
+    }
+
+    /// Collects characters between the current quote and the matching
+    /// one, replacing the escape sequences with the characters they
+    /// stand for. Literals cannot span multiple lines so the new line
+    /// ends them as unterminated.
+    ///
+    /// Returns `None` if the errors have been reported,
+    /// in which case the whole literal is still consumed.
+    fn collect_quoted(&mut self, quote: char) -> Option<String> {
+        let beg = self.src_ptr();
+        let mut content = String::new();
+        let mut valid = true;
+        let mut opt = self.next_char();
+        loop {
+            match opt {
+                Some(ch) if ch == quote => {
+                    self.next_char();
+                    break;
+                }
+                None | Some('\n') => {
+                    trace!("collect_quoted(): unterminated literal");
+                    self.unterminated_literal_error(quote, beg);
+                    return None;
+                }
+                Some('\\') => match self.collect_escape() {
+                    Some(ch) => content.push(ch),
+                    None => valid = false,
+                },
+                Some(ch) => {
+                    content.push(ch);
+                    self.next_char();
+                }
+            }
+            opt = self.curr_char();
+        }
+        if valid {
+            Some(content)
+        } else {
+            None
+        }
+    }
+
+    /// Collects escape sequence starting with the current backslash.
+    /// Supported are `\n`, `\r`, `\t`, `\0`, `\\`, `\"`, `\'`
+    /// and unicode escapes written as `\u{1F600}`.
+    fn collect_escape(&mut self) -> Option<char> {
+        let beg = self.src_ptr();
+        let ch = match self.next_char() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('u') => return self.collect_unicode_escape(beg),
+            // Left for the quoted literal to report it as unterminated.
+            None | Some('\n') => return None,
+            Some(ch) => {
+                trace!("collect_escape(): unknown escape {}", ch);
+                self.next_char();
+                self.invalid_escape_error(LexingErrorKind::UnknownEscape(ch), beg);
+                return None;
+            }
+        };
+        self.next_char();
+        Some(ch)
+    }
+
+    fn collect_unicode_escape(&mut self, beg: S::Pointer) -> Option<char> {
+        let mut digits = String::new();
+        let mut closed = false;
+        if let Some('{') = self.next_char() {
+            let mut opt = self.next_char();
+            while let Some(ch) = opt {
+                if ch == '}' {
+                    closed = true;
+                    self.next_char();
+                    break;
+                }
+                if !ch.is_ascii_hexdigit() {
+                    break;
+                }
+                digits.push(ch);
+                opt = self.next_char();
+            }
+        }
+        let ch = if closed && !digits.is_empty() && digits.len() <= 6 {
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(std::char::from_u32)
+        } else {
+            None
+        };
+        if ch.is_none() {
+            trace!("collect_unicode_escape(): invalid escape {}", digits);
+            self.invalid_escape_error(LexingErrorKind::InvalidUnicodeEscape, beg);
+        }
+        ch
+    }
+
+    /// Returns symbol of the string interned by the session.
+    fn intern(&self, s: &str) -> Symbol {
+        self.session.borrow_mut().intern(s)
+    }
+
+    /// Value of the poisoned token which is the
+    /// source between the pointer and the current one.
+    fn poisoned_value(&self, beg: &S::Pointer) -> token::Value {
+        let text = self.src.borrow().source_between(beg, &self.src_ptr());
+        token::Value::Symbol(self.intern(&text))
+    }
+
     fn collect_char(&mut self) -> Option<Token<S>> {
         let ch = self.curr_char().unwrap();
         trace!("next(): Unknown character, returning None");
         self.unknown_character_error();
         let mut symbol = String::new();
         symbol.push(ch);
-        let ptr = self.curr_ptr();
+        let ptr = self.src_ptr();
         {
             self.src.borrow_mut().next_char();
         }
         Some(token::Token {
             kind: token::Kind::Poisoned,
-            value: token::Value::String(symbol),
+            value: token::Value::Symbol(self.intern(&symbol)),
             span: Span {
                 beg: ptr.clone(),
                 end: ptr,
             },
+            trivia: Vec::new(),
         })
     }
 
@@ -319,8 +813,8 @@ where
         trace!("unknown_character_error(): Error");
         self.session.borrow_mut().err(Box::new(LexingError {
             kind: LexingErrorKind::UnknownCharacter(self.curr_char().unwrap()),
-            beg: self.curr_ptr(),
-            end: self.curr_ptr(),
+            beg: self.src_ptr(),
+            end: self.src_ptr(),
         }));
     }
 
@@ -329,20 +823,102 @@ where
         self.session.borrow_mut().err(Box::new(LexingError {
             kind: LexingErrorKind::NotAnInterger,
             beg,
-            end: self.curr_ptr(),
+            end: self.src_ptr(),
         }));
     }
 
-    fn integers_cannot_start_with_zero_error(&mut self, beg: S::Pointer) {
+    fn integers_cannot_start_with_zero_error(&mut self, beg: S::Pointer, end: S::Pointer) {
         trace!("integers_cannot_start_with_zero_error(): Error");
         self.session.borrow_mut().err(Box::new(LexingError {
             kind: LexingErrorKind::IntegersCannotStartWithZero,
             beg,
-            end: self.curr_ptr(),
+            end,
+        }));
+    }
+
+    /// Reports the current character as not being
+    /// a digit valid in the given base.
+    fn invalid_digit_error(&mut self, digit: char, radix: u32) {
+        trace!("invalid_digit_error(): Error");
+        self.session.borrow_mut().err(Box::new(LexingError {
+            kind: LexingErrorKind::InvalidDigit { digit, radix },
+            beg: self.src_ptr(),
+            end: self.src_ptr(),
+        }));
+    }
+
+    fn number_error(&mut self, kind: LexingErrorKind, beg: S::Pointer) {
+        trace!("number_error(): Error");
+        self.session.borrow_mut().err(Box::new(LexingError {
+            kind,
+            beg,
+            end: self.src_ptr(),
+        }));
+    }
+
+    fn unterminated_literal_error(&mut self, quote: char, beg: S::Pointer) {
+        trace!("unterminated_literal_error(): Error");
+        self.session.borrow_mut().err(Box::new(LexingError {
+            kind: if quote == '"' {
+                LexingErrorKind::UnterminatedString
+            } else {
+                LexingErrorKind::UnterminatedChar
+            },
+            beg,
+            end: self.src_ptr(),
+        }));
+    }
+
+    fn invalid_escape_error(&mut self, kind: LexingErrorKind, beg: S::Pointer) {
+        trace!("invalid_escape_error(): Error");
+        self.session.borrow_mut().err(Box::new(LexingError {
+            kind,
+            beg,
+            end: self.src_ptr(),
+        }));
+    }
+
+    fn unterminated_comment_error(&mut self, beg: S::Pointer) {
+        trace!("unterminated_comment_error(): Error");
+        self.session.borrow_mut().err(Box::new(LexingError {
+            kind: LexingErrorKind::UnterminatedComment,
+            beg,
+            end: self.src_ptr(),
+        }));
+    }
+
+    fn invalid_char_literal_error(&mut self, beg: S::Pointer) {
+        trace!("invalid_char_literal_error(): Error");
+        self.session.borrow_mut().err(Box::new(LexingError {
+            kind: LexingErrorKind::InvalidCharLiteral,
+            beg,
+            end: self.src_ptr(),
         }));
     }
 }
 
+/// Yields the tokens starting from the current one.
+impl<S> Iterator for Lexer<S>
+where
+    S: Source,
+    S::Pointer: 'static,
+{
+    type Item = Token<S>;
+
+    fn next(&mut self) -> Option<Token<S>> {
+        let tok = self.curr();
+        self.advance();
+        tok
+    }
+}
+
+/// Place in the token stream of the lexer
+/// it can be rewound to.
+#[must_use]
+pub struct Checkpoint {
+    pos: usize,
+}
+
 // Errors
 
 #[derive(Debug)]
@@ -350,6 +926,28 @@ pub enum LexingErrorKind {
     IntegersCannotStartWithZero,
     NotAnInterger,
     UnknownCharacter(char),
+    /// Digit which is not valid in the base of the literal.
+    InvalidDigit {
+        digit: char,
+        radix: u32,
+    },
+    /// Integer literal which doesn't fit in `u64`.
+    IntegerOverflow,
+    /// Float literal which is too large to be represented as `f64`.
+    FloatOverflow,
+    /// Float literal with the exponent marker but no exponent digits.
+    MissingExponent,
+    UnterminatedString,
+    UnterminatedChar,
+    /// Block comment without the closing `}#`.
+    UnterminatedComment,
+    /// Escape sequence with the character which cannot be escaped.
+    UnknownEscape(char),
+    /// Unicode escape which is malformed or
+    /// doesn't name a valid character.
+    InvalidUnicodeEscape,
+    /// Character literal which is empty or has more than one character.
+    InvalidCharLiteral,
 }
 
 #[derive(Debug)]
@@ -388,6 +986,24 @@ impl<T: ftl_source::Pointer> LangError for LexingError<T> {
                 String::from("literal is not an interger but it starts like one")
             }
             LexingErrorKind::UnknownCharacter(_) => String::from("unkown character"),
+            LexingErrorKind::InvalidDigit { digit, radix } => {
+                format!("invalid digit {} in the base {} integer", digit, radix)
+            }
+            LexingErrorKind::IntegerOverflow => {
+                String::from("integer literal is too large to fit in 64 bits")
+            }
+            LexingErrorKind::FloatOverflow => String::from("float literal is out of range"),
+            LexingErrorKind::MissingExponent => String::from("expected digits of the exponent"),
+            LexingErrorKind::UnterminatedString => String::from("unterminated string literal"),
+            LexingErrorKind::UnterminatedChar => String::from("unterminated character literal"),
+            LexingErrorKind::UnterminatedComment => String::from("unterminated block comment"),
+            LexingErrorKind::UnknownEscape(ch) => format!("unknown escape sequence \\{}", ch),
+            LexingErrorKind::InvalidUnicodeEscape => {
+                String::from("invalid unicode escape sequence")
+            }
+            LexingErrorKind::InvalidCharLiteral => {
+                String::from("character literal must contain exactly one character")
+            }
         }
     }
 
@@ -430,7 +1046,7 @@ mod tests {
     fn reading_past_first_token_from_empty_lexer() {
         let sess = make_sess_with_src("");
         let mut l = Lexer::new(sess);
-        assert_match!(l.next(), None);
+        assert_match!(l.advance(), None);
     }
 
     #[test]
@@ -467,6 +1083,94 @@ mod tests {
         );
     }
 
+    fn trivia_of<P: ftl_source::Pointer>(trivia: &[token::Trivia<P>]) -> Vec<(token::Kind, &str)> {
+        trivia
+            .iter()
+            .map(|t| (t.kind.clone(), t.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn lossless_lexer_attaches_trivia_to_tokens() {
+        use token::Kind::{Comment, Whitespace};
+        let sess = make_sess_with_src("  # comment 😁\n\t12 a\n# end\n");
+        let mut l = Lexer::lossless(sess);
+        assert!(l.is_lossless());
+        assert_eq!(
+            trivia_of(&l.curr().unwrap().trivia),
+            vec![
+                (Whitespace, "  "),
+                (Comment, "# comment 😁"),
+                (Whitespace, "\n\t")
+            ]
+        );
+        assert_eq!(
+            trivia_of(&l.advance().unwrap().trivia),
+            vec![(Whitespace, " ")]
+        );
+        assert_match!(l.advance(), None);
+        assert_eq!(
+            trivia_of(l.trailing_trivia()),
+            vec![(Whitespace, "\n"), (Comment, "# end"), (Whitespace, "\n")]
+        );
+        assert_eq!(l.take_tokens().len(), 2);
+        assert!(l.take_tokens().is_empty());
+    }
+
+    #[test]
+    fn lexing_block_and_doc_comments() {
+        use token::Kind::{Comment, DocComment, Whitespace};
+        let sess = make_sess_with_src("#{ a #{ b }# c\n}#1 #{}#2 ## doc\n3");
+        let mut l = Lexer::lossless(sess.clone());
+        assert_eq!(
+            trivia_of(&l.curr().unwrap().trivia),
+            vec![(Comment, "#{ a #{ b }# c\n}#")]
+        );
+        assert_eq!(
+            trivia_of(&l.advance().unwrap().trivia),
+            vec![(Whitespace, " "), (Comment, "#{}#")]
+        );
+        assert_eq!(
+            trivia_of(&l.advance().unwrap().trivia),
+            vec![
+                (Whitespace, " "),
+                (DocComment, "## doc"),
+                (Whitespace, "\n")
+            ]
+        );
+        assert!(sess.borrow_mut().handler.error_msg().is_none());
+    }
+
+    #[test]
+    fn doc_comments_are_kept_when_not_lossless() {
+        use token::Kind::DocComment;
+        let sess = make_sess_with_src("# comment\n## first\n##second\n12");
+        let l = Lexer::new(sess);
+        assert_eq!(
+            trivia_of(&l.curr().unwrap().trivia),
+            vec![(DocComment, "## first"), (DocComment, "##second")]
+        );
+    }
+
+    #[test]
+    fn error_on_unterminated_block_comment() {
+        let sess = make_sess_with_src("#{ a #{ b }# 12");
+        let l = Lexer::new(sess.clone());
+        assert_match!(l.curr(), None);
+        let msg = sess.borrow_mut().handler.error_msg().unwrap();
+        assert!(msg.contains("unterminated block comment"), "{}", msg);
+    }
+
+    #[test]
+    fn lexer_skips_trivia_when_not_lossless() {
+        let sess = make_sess_with_src("  # comment\n12 # end");
+        let mut l = Lexer::new(sess);
+        assert!(l.curr().unwrap().trivia.is_empty());
+        assert_match!(l.advance(), None);
+        assert!(l.trailing_trivia().is_empty());
+        assert!(l.take_tokens().is_empty());
+    }
+
     #[test]
     fn get_zero_integer_literal() {
         let sess = make_sess_with_src("0");
@@ -512,10 +1216,10 @@ mod tests {
         "#,
         );
         let mut l = Lexer::new(sess);
-        l.next();
-        l.next();
-        l.next();
-        l.next();
+        l.advance();
+        l.advance();
+        l.advance();
+        l.advance();
         assert_match!(
             l.curr(),
             Some(token::Token {
@@ -534,12 +1238,12 @@ mod tests {
         "#,
         );
         let mut l = Lexer::new(sess);
-        l.next();
-        let tok = l.next().unwrap();
+        l.advance();
+        let tok = l.advance().unwrap();
         assert!(match tok {
             token::Token {
                 kind: token::Kind::Identifier,
-                value: token::Value::String(ref s),
+                value: token::Value::Symbol(ref s),
                 ..
             } => s == "identifiers",
             _ => false,
@@ -554,12 +1258,12 @@ mod tests {
         "#,
         );
         let mut l = Lexer::new(sess);
-        l.next();
-        let tok = l.next().unwrap();
+        l.advance();
+        let tok = l.advance().unwrap();
         assert!(match tok {
             token::Token {
                 kind: token::Kind::FuncDef,
-                value: token::Value::String(ref s),
+                value: token::Value::Symbol(ref s),
                 ..
             } => s == "def",
             _ => false,
@@ -574,8 +1278,8 @@ mod tests {
         "#,
         );
         let mut l = Lexer::new(sess);
-        l.next();
-        l.next();
+        l.advance();
+        l.advance();
         assert_match!(
             l.curr(),
             Some(token::Token {
@@ -594,13 +1298,13 @@ mod tests {
         "#,
         );
         let mut l = Lexer::new(sess);
-        assert_match!(l.next().unwrap().kind, token::Kind::Operator);
-        assert_match!(l.next().unwrap().kind, token::Kind::Operator);
-        assert_match!(l.next().unwrap().kind, token::Kind::Operator);
-        assert_match!(l.next().unwrap().kind, token::Kind::Operator);
-        assert_match!(l.next().unwrap().kind, token::Kind::Comma);
-        assert_match!(l.next().unwrap().kind, token::Kind::LeftParenthesis);
-        assert_match!(l.next().unwrap().kind, token::Kind::RightParenthesis);
+        assert_match!(l.advance().unwrap().kind, token::Kind::Operator);
+        assert_match!(l.advance().unwrap().kind, token::Kind::Operator);
+        assert_match!(l.advance().unwrap().kind, token::Kind::Operator);
+        assert_match!(l.advance().unwrap().kind, token::Kind::Operator);
+        assert_match!(l.advance().unwrap().kind, token::Kind::Comma);
+        assert_match!(l.advance().unwrap().kind, token::Kind::LeftParenthesis);
+        assert_match!(l.advance().unwrap().kind, token::Kind::RightParenthesis);
     }
 
     #[test]
@@ -611,16 +1315,18 @@ mod tests {
         "#,
         );
         let mut l = Lexer::new(sess);
-        assert_match!(l.next().unwrap(),
-            token::Token{
+        assert_match!(
+            l.advance().unwrap(),
+            token::Token {
                 kind: token::Kind::IntLiteral,
                 value: token::Value::Integer(12),
                 ..
             }
         );
-        assert_match!(l.next().unwrap().kind, token::Kind::Operator);
-        assert_match!(l.next().unwrap(),
-            token::Token{
+        assert_match!(l.advance().unwrap().kind, token::Kind::Operator);
+        assert_match!(
+            l.advance().unwrap(),
+            token::Token {
                 kind: token::Kind::IntLiteral,
                 value: token::Value::Integer(0),
                 ..
@@ -636,9 +1342,9 @@ mod tests {
         "#,
         );
         let mut l = Lexer::new(sess);
-        assert_match!(l.next().unwrap().kind, token::Kind::Identifier);
-        assert_match!(l.next().unwrap().kind, token::Kind::Operator);
-        assert_match!(l.next().unwrap().kind, token::Kind::Identifier);
+        assert_match!(l.advance().unwrap().kind, token::Kind::Identifier);
+        assert_match!(l.advance().unwrap().kind, token::Kind::Operator);
+        assert_match!(l.advance().unwrap().kind, token::Kind::Identifier);
     }
 
     #[test]
@@ -649,10 +1355,10 @@ mod tests {
         "#,
         );
         let mut l = Lexer::new(sess);
-        assert!(match l.next().unwrap() {
+        assert!(match l.advance().unwrap() {
             token::Token {
                 kind: token::Kind::Operator,
-                value: token::Value::String(ref s),
+                value: token::Value::Symbol(ref s),
                 ..
             } => {
                 trace!("Symbol is: {}", s);
@@ -669,7 +1375,7 @@ mod tests {
         assert!(match l.curr().unwrap() {
             token::Token {
                 kind: token::Kind::Poisoned,
-                value: token::Value::String(ref s),
+                value: token::Value::Symbol(ref s),
                 ..
             } => {
                 trace!("Symbol is: {}", s);
@@ -677,35 +1383,249 @@ mod tests {
             }
             _ => false,
         });
-        assert_match!(l.next().unwrap(), token::Token{
-            kind: token::Kind::IntLiteral,
-            value: token::Value::Integer(123),
-            ..
-        });
+        assert_match!(
+            l.advance().unwrap(),
+            token::Token {
+                kind: token::Kind::IntLiteral,
+                value: token::Value::Integer(123),
+                ..
+            }
+        );
     }
 
     #[test]
     fn returning_poisoned_integers_2() {
         let sess = make_sess_with_src("123 12asdafe3");
         let mut l = Lexer::new(sess);
-        assert!(match l.next().unwrap() {
+        assert!(match l.advance().unwrap() {
             token::Token {
                 kind: token::Kind::Poisoned,
-                value: token::Value::String(ref s),
+                value: token::Value::Symbol(ref s),
                 ..
             } => s == "12asdafe3",
             _ => false,
         });
     }
 
+    fn values(src: &str) -> Vec<token::Value> {
+        Lexer::new(make_sess_with_src(src))
+            .map(|tok| tok.value)
+            .collect()
+    }
+
+    fn error_descs(src: &str) -> Vec<String> {
+        let sess = make_sess_with_src(src);
+        Lexer::new(sess.clone()).for_each(drop);
+        let sess = sess.borrow();
+        sess.handler.errors().iter().map(|err| err.desc()).collect()
+    }
+
+    #[test]
+    fn returning_integers_in_other_bases() {
+        assert_eq!(
+            values("0b101 0o17 0xfF 0x0 0"),
+            vec![
+                token::Value::Integer(5),
+                token::Value::Integer(15),
+                token::Value::Integer(255),
+                token::Value::Integer(0),
+                token::Value::Integer(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn returning_integers_with_separators() {
+        assert_eq!(
+            values("1_000_000 0b1111_0000 0x_ff 1_"),
+            vec![
+                token::Value::Integer(1_000_000),
+                token::Value::Integer(0b1111_0000),
+                token::Value::Integer(0xff),
+                token::Value::Integer(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn returning_poisoned_integers_on_overflow() {
+        assert_eq!(
+            values("18446744073709551615 18446744073709551616 0x1_0000_0000_0000_0000"),
+            vec![
+                token::Value::Integer(u64::MAX),
+                token::Value::Symbol(Symbol::new("18446744073709551616")),
+                token::Value::Symbol(Symbol::new("0x1_0000_0000_0000_0000")),
+            ]
+        );
+        assert_eq!(
+            error_descs("18446744073709551616"),
+            vec!["integer literal is too large to fit in 64 bits"]
+        );
+    }
+
+    #[test]
+    fn returning_poisoned_integers_on_invalid_digits() {
+        assert_eq!(
+            values("0b102 0o78 0xfg 0x 12"),
+            vec![
+                token::Value::Symbol(Symbol::new("0b102")),
+                token::Value::Symbol(Symbol::new("0o78")),
+                token::Value::Symbol(Symbol::new("0xfg")),
+                token::Value::Symbol(Symbol::new("0x")),
+                token::Value::Integer(12),
+            ]
+        );
+        assert_eq!(
+            error_descs("0b102 0o78 0xfg 0x"),
+            vec![
+                "invalid digit 2 in the base 2 integer",
+                "invalid digit 8 in the base 8 integer",
+                "literal is not an interger but it starts like one",
+                "literal is not an interger but it starts like one",
+            ]
+        );
+    }
+
+    #[test]
+    fn returning_float_literals() {
+        assert_eq!(
+            values("1.5 2e10 1_000.25 3E-2 0.5 1e+3"),
+            vec![
+                token::Value::Float(1.5),
+                token::Value::Float(2e10),
+                token::Value::Float(1000.25),
+                token::Value::Float(0.03),
+                token::Value::Float(0.5),
+                token::Value::Float(1000.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn returning_operators_starting_with_dot_after_integers() {
+        let sess = make_sess_with_src("1.x 1..2 3.");
+        let tokens: Vec<_> = Lexer::lossless(sess.clone())
+            .map(|tok| (tok.kind, tok.value))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (token::Kind::IntLiteral, token::Value::Integer(1)),
+                (token::Kind::Operator, token::Value::Symbol(".".into())),
+                (token::Kind::Identifier, token::Value::Symbol("x".into())),
+                (token::Kind::IntLiteral, token::Value::Integer(1)),
+                (token::Kind::Operator, token::Value::Symbol("..".into())),
+                (token::Kind::IntLiteral, token::Value::Integer(2)),
+                (token::Kind::IntLiteral, token::Value::Integer(3)),
+                (token::Kind::Operator, token::Value::Symbol(".".into())),
+            ]
+        );
+        assert!(sess.borrow().handler.errors().is_empty());
+    }
+
+    #[test]
+    fn returning_poisoned_floats() {
+        let src = "1e 2.5x 1e400 3";
+        assert_eq!(
+            values(src),
+            vec![
+                token::Value::Symbol("1e".into()),
+                token::Value::Symbol("2.5x".into()),
+                token::Value::Symbol("1e400".into()),
+                token::Value::Integer(3),
+            ]
+        );
+        assert_eq!(
+            error_descs(src),
+            vec![
+                "expected digits of the exponent",
+                "literal is not an interger but it starts like one",
+                "float literal is out of range",
+            ]
+        );
+    }
+
+    #[test]
+    fn returning_string_and_char_literals() {
+        let sess = make_sess_with_src(r#""abc" "a\n\"b\"\\" "" 'x' '\'' '\u{1F600}' "\u{48}\t""#);
+        let tokens: Vec<_> = Lexer::new(sess.clone())
+            .map(|tok| (tok.kind, tok.value))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (token::Kind::StrLiteral, token::Value::Symbol("abc".into())),
+                (
+                    token::Kind::StrLiteral,
+                    token::Value::Symbol("a\n\"b\"\\".into())
+                ),
+                (token::Kind::StrLiteral, token::Value::Symbol("".into())),
+                (token::Kind::CharLiteral, token::Value::Char('x')),
+                (token::Kind::CharLiteral, token::Value::Char('\'')),
+                (token::Kind::CharLiteral, token::Value::Char('😀')),
+                (token::Kind::StrLiteral, token::Value::Symbol("H\t".into())),
+            ]
+        );
+        assert!(sess.borrow().handler.errors().is_empty());
+    }
+
+    #[test]
+    fn returning_poisoned_unterminated_literals() {
+        let sess = make_sess_with_src("\"abc\ndef 'a");
+        let tokens: Vec<_> = Lexer::new(sess).map(|tok| tok.value).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                token::Value::Symbol("\"abc".into()),
+                token::Value::Symbol("def".into()),
+                token::Value::Symbol("'a".into()),
+            ]
+        );
+        assert_eq!(
+            error_descs("\"abc\ndef 'a"),
+            vec![
+                "unterminated string literal",
+                "unterminated character literal"
+            ]
+        );
+    }
+
+    #[test]
+    fn returning_poisoned_literals_on_invalid_escapes() {
+        let src = r#""a\qb" "\u{110000}" "\u{}" "\u00" '' 'ab' 1"#;
+        assert_eq!(
+            values(src),
+            vec![
+                token::Value::Symbol(r#""a\qb""#.into()),
+                token::Value::Symbol(r#""\u{110000}""#.into()),
+                token::Value::Symbol(r#""\u{}""#.into()),
+                token::Value::Symbol(r#""\u00""#.into()),
+                token::Value::Symbol("''".into()),
+                token::Value::Symbol("'ab'".into()),
+                token::Value::Integer(1),
+            ]
+        );
+        assert_eq!(
+            error_descs(src),
+            vec![
+                "unknown escape sequence \\q",
+                "invalid unicode escape sequence",
+                "invalid unicode escape sequence",
+                "invalid unicode escape sequence",
+                "character literal must contain exactly one character",
+                "character literal must contain exactly one character",
+            ]
+        );
+    }
+
     #[test]
     fn returning_unknown_character() {
         let sess = make_sess_with_src("123 😁0");
         let mut l = Lexer::new(sess);
-        assert!(match l.next().unwrap() {
+        assert!(match l.advance().unwrap() {
             token::Token {
                 kind: token::Kind::Poisoned,
-                value: token::Value::String(ref s),
+                value: token::Value::Symbol(ref s),
                 ..
             } => s == "😁",
             _ => false,
@@ -724,16 +1644,16 @@ mod tests {
                 ..
             }
         );
-        assert!(match l.next().unwrap() {
+        assert!(match l.advance().unwrap() {
             token::Token {
                 kind: token::Kind::InfixIdent,
-                value: token::Value::String(ref s),
+                value: token::Value::Symbol(ref s),
                 ..
             } => s == "abc",
             _ => false,
         });
         assert_match!(
-            l.next().unwrap(),
+            l.advance().unwrap(),
             token::Token {
                 kind: token::Kind::IntLiteral,
                 value: token::Value::Integer(123),
@@ -741,4 +1661,102 @@ mod tests {
             }
         );
     }
+
+    fn kinds<P: ftl_source::Pointer>(
+        tokens: impl Iterator<Item = token::Token<P>>,
+    ) -> Vec<token::Kind> {
+        tokens.map(|tok| tok.kind).collect()
+    }
+
+    #[test]
+    fn iterating_over_tokens() {
+        use token::Kind::*;
+        let sess = make_sess_with_src("def f a: a + 1");
+        let l = Lexer::new(sess);
+        assert_eq!(
+            kinds(l),
+            vec![FuncDef, Identifier, Identifier, Colon, Identifier, Operator, IntLiteral]
+        );
+
+        let sess = make_sess_with_src("a + 1");
+        let mut l = Lexer::new(sess);
+        l.advance();
+        assert_eq!(kinds(l.by_ref()), vec![Operator, IntLiteral]);
+        assert_match!(l.curr(), None);
+    }
+
+    #[test]
+    fn peeking_tokens() {
+        use ftl_source::Pointer;
+
+        let sess = make_sess_with_src("a 1 b");
+        let mut l = Lexer::new(sess);
+        assert_match!(l.peek_nth(1).unwrap().value, token::Value::Integer(1));
+        assert_match!(l.peek_nth(3), None);
+        assert_match!(l.peek_nth(4), None);
+        assert_eq!(l.curr_ptr().offset(), 1);
+        assert_match!(l.curr().unwrap().kind, token::Kind::Identifier);
+        assert_match!(l.advance().unwrap().value, token::Value::Integer(1));
+        assert_match!(l.peek_nth(0).unwrap().value, token::Value::Integer(1));
+        assert!(match l.peek_nth(1).unwrap().value {
+            token::Value::Symbol(s) => s == "b",
+            _ => false,
+        });
+        assert_match!(l.advance().unwrap().kind, token::Kind::Identifier);
+        assert_match!(l.advance(), None);
+        assert_eq!(l.curr_ptr().offset(), 5);
+    }
+
+    #[test]
+    fn symbols_are_interned_by_the_session() {
+        let sess = make_sess_with_src("foo + foo \"foo\" + bar");
+        let symbols: Vec<Symbol> = Lexer::new(sess.clone())
+            .filter_map(|tok| match tok.value {
+                token::Value::Symbol(s) => Some(s),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(symbols.len(), 6);
+        assert!(std::ptr::eq(symbols[0].as_str(), symbols[2].as_str()));
+        assert!(std::ptr::eq(symbols[0].as_str(), symbols[3].as_str()));
+        assert_eq!(sess.borrow().symbols.len(), 3);
+    }
+
+    #[test]
+    fn rewinding_to_checkpoints() {
+        use token::Kind::*;
+        let sess = make_sess_with_src("a 1 + b");
+        let mut l = Lexer::lossless(sess.clone());
+        let outer = l.checkpoint();
+        l.advance();
+        let inner = l.checkpoint();
+        l.advance();
+        l.advance();
+        l.rewind(inner);
+        assert_match!(l.curr().unwrap().kind, IntLiteral);
+        l.advance();
+        l.rewind(outer);
+        assert_eq!(
+            kinds(l.by_ref()),
+            vec![Identifier, IntLiteral, Operator, Identifier]
+        );
+
+        // every token is lexed once
+        assert_eq!(l.take_tokens().len(), 4);
+        let checkpoint = l.checkpoint();
+        l.release(checkpoint);
+        assert_eq!(l.buffer.len(), 1);
+    }
+
+    #[test]
+    fn lexing_errors_are_reported_once() {
+        let sess = make_sess_with_src("01 a");
+        let mut l = Lexer::new(sess.clone());
+        let checkpoint = l.checkpoint();
+        l.peek_nth(1);
+        l.rewind(checkpoint);
+        l.peek_nth(1);
+        assert_eq!(l.by_ref().count(), 2);
+        assert_eq!(sess.borrow().handler.errors().len(), 1);
+    }
 }
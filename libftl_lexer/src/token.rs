@@ -1,6 +1,7 @@
 use std::fmt;
 use std::fmt::Display;
 
+use ftl_session::symbol::Symbol;
 use ftl_source;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -8,13 +9,20 @@ pub enum Kind {
     // General
     Identifier,
     InfixIdent,
-    Comment,
     Operator,
 
+    // Trivia
+    Comment,
+    /// Line comment starting with `##` documenting
+    /// the declaration following it.
+    DocComment,
+    Whitespace,
+
     // Keywords
     FuncDef,
     FuncDecl,
     InfixDef,
+    PrefixDef,
 
     // Operators
     LeftParenthesis,
@@ -27,16 +35,22 @@ pub enum Kind {
 
     // Literals
     IntLiteral,
+    FloatLiteral,
+    StrLiteral,
+    CharLiteral,
 
     // Special
     /// Poisoned token represents invalid token
     Poisoned,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     Integer(u64),
-    String(std::string::String),
+    Float(f64),
+    /// Names, operators and contents of the string literals.
+    Symbol(Symbol),
+    Char(char),
     None,
 }
 
@@ -45,6 +59,20 @@ pub struct Token<T: ftl_source::Pointer> {
     pub kind: Kind,
     pub value: Value,
     pub span: ftl_source::Span<T>,
+    /// Whitespaces and comments preceding the token.
+    /// Collected only by the lossless lexer,
+    /// except for the doc comments which are always kept.
+    pub trivia: Vec<Trivia<T>>,
+}
+
+/// Part of the source which is not meaningful for the parser,
+/// either a whitespace, a comment or a doc comment.
+#[derive(Clone, Debug)]
+pub struct Trivia<T: ftl_source::Pointer> {
+    /// Either `Kind::Whitespace`, `Kind::Comment` or `Kind::DocComment`.
+    pub kind: Kind,
+    pub text: std::string::String,
+    pub span: ftl_source::Span<T>,
 }
 
 impl<Ptr: ftl_source::Pointer> Display for Token<Ptr> {
@@ -0,0 +1,381 @@
+//! Runs the compiler frontend on the document
+//! and collects everything the server needs to
+//! answer the requests about it.
+
+use std::collections::{BTreeSet, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+
+use ftl_lexer::Lexer;
+use ftl_parser::ast::*;
+use ftl_parser::visitor::*;
+use ftl_parser::visitor_mut::visit_ast_mut;
+use ftl_parser::Parser;
+use ftl_pass::ac::ArityCheck;
+use ftl_pass::av::AttrValidation;
+use ftl_pass::epr::ExprPrecReassoc;
+use ftl_pass::fmt::{attr_repr, type_repr};
+use ftl_session::Session;
+use ftl_source::{string, Pointer};
+use ftl_utility::RcRef;
+
+use crate::protocol::*;
+
+/// Result of analyzing one version of the document.
+#[derive(Default)]
+pub(crate) struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub symbols: Vec<DocumentSymbol>,
+    pub references: Vec<Reference>,
+}
+
+/// Occurrence of the name in the document.
+pub(crate) struct Reference {
+    pub range: Range,
+    /// Where the name is defined, if it is known.
+    pub target: Option<Range>,
+    /// Markdown shown when hovering over the name.
+    pub hover: Option<String>,
+}
+
+impl Analysis {
+    /// Returns the first reference containing the position.
+    pub fn reference_at(&self, pos: Position) -> Option<&Reference> {
+        self.references.iter().find(|r| r.range.contains(pos))
+    }
+}
+
+/// Lexes, parses and runs the passes reporting errors on the document.
+///
+/// Fatal errors stop the compilation with the panic, which
+/// is caught here so that the errors reported until that
+/// point can still be published.
+pub(crate) fn analyze(text: &str) -> Analysis {
+    let sess = RcRef::new(Session::new(string::String::from(text)));
+    let parsed = panic::catch_unwind(AssertUnwindSafe(|| {
+        let lexer = Lexer::new(sess.clone());
+        let mut parser = Parser::new(lexer, sess.clone());
+        parser.parse()
+    }));
+    let mut analysis = Analysis::default();
+    if let Ok(mut ast) = parsed {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut sess_ref = sess.borrow_mut();
+            let mut epr = ExprPrecReassoc::new(&mut sess_ref);
+            visit_ast_mut(&mut epr, &mut ast);
+            visit_ast(&mut ArityCheck::new(&mut sess_ref), &ast);
+            visit_ast(&mut AttrValidation::new(&mut sess_ref), &ast);
+        }));
+        let mut indexer = Indexer::new();
+        visit_ast(&mut indexer, &ast);
+        analysis.symbols = indexer.symbols;
+        analysis.references = indexer.references;
+    }
+    analysis.diagnostics = sess
+        .borrow()
+        .handler
+        .errors()
+        .iter()
+        .map(|err| Diagnostic {
+            range: Range::from_ptrs(err.begin(), err.end()),
+            severity: SEVERITY_ERROR,
+            source: String::from("ftl"),
+            message: err.desc(),
+        })
+        .collect();
+    analysis
+}
+
+fn code_block(code: &str) -> String {
+    format!("```ftl\n{}\n```", code)
+}
+
+/// Returns hover text with the signature followed by the doc comment.
+fn signature_hover(sig: &str, doc: Option<&String>) -> String {
+    match doc {
+        Some(doc) => format!("{}\n\n{}", code_block(sig), doc),
+        None => code_block(sig),
+    }
+}
+
+/// Collects document symbols and resolves every name
+/// used in the document to its definition.
+///
+/// Arguments shadow the top level names. Function
+/// names resolve to their definitions or, if there
+/// are none, to their declarations.
+struct Indexer<'ast, P: Pointer> {
+    /// Every function declaration, both standalone and
+    /// the ones being part of the definitions.
+    decls: HashMap<&'ast str, Vec<&'ast FuncDecl<P>>>,
+    defs: HashMap<&'ast str, &'ast FuncDef<P>>,
+    infixes: HashMap<&'ast str, &'ast InfixDef<P>>,
+    prefixes: HashMap<&'ast str, &'ast PrefixDef<P>>,
+
+    /// Name of the visited definition with its arguments.
+    scope: Option<(&'ast str, Vec<&'ast FuncArg<P>>)>,
+
+    symbols: Vec<DocumentSymbol>,
+    references: Vec<Reference>,
+}
+
+impl<'ast, P: Pointer> Indexer<'ast, P> {
+    fn new() -> Self {
+        Self {
+            decls: HashMap::new(),
+            defs: HashMap::new(),
+            infixes: HashMap::new(),
+            prefixes: HashMap::new(),
+            scope: None,
+            symbols: Vec::new(),
+            references: Vec::new(),
+        }
+    }
+
+    fn collect_top_level(&mut self, node: &'ast Module<P>) {
+        for decl in &node.decl {
+            match decl.kind {
+                TopLevelDeclKind::FunctionDecl(ref func_decl) => {
+                    self.decls
+                        .entry(&func_decl.ident.symbol)
+                        .or_default()
+                        .push(func_decl);
+                }
+                TopLevelDeclKind::FunctionDef(ref func_def) => {
+                    let name = func_def.decl.ident.symbol.as_str();
+                    self.decls.entry(name).or_default().push(&func_def.decl);
+                    self.defs.entry(name).or_insert(func_def);
+                }
+                TopLevelDeclKind::InfixDef(ref infix_def) => {
+                    self.infixes
+                        .entry(&infix_def.op.symbol)
+                        .or_insert(infix_def);
+                }
+                TopLevelDeclKind::PrefixDef(ref prefix_def) => {
+                    self.prefixes
+                        .entry(&prefix_def.op.symbol)
+                        .or_insert(prefix_def);
+                }
+            }
+        }
+    }
+
+    /// Returns where the function is defined.
+    fn func_target(&self, name: &str) -> Option<Range> {
+        if let Some(def) = self.defs.get(name) {
+            return Some(Range::from_span(&def.decl.ident.span));
+        }
+        self.decls
+            .get(name)
+            .and_then(|decls| decls.first())
+            .map(|decl| Range::from_span(&decl.ident.span))
+    }
+
+    /// Returns functions type, if any of its declarations has it.
+    fn func_type(&self, name: &str) -> Option<&'ast Type<P>> {
+        self.decls
+            .get(name)
+            .and_then(|decls| decls.iter().find_map(|decl| decl.ty.as_ref()))
+    }
+
+    /// Returns functions signature with its type and attributes
+    /// merged from all of its declarations.
+    fn func_signature(&self, name: &str) -> Option<String> {
+        let decls = self.decls.get(name)?;
+        let attrs: BTreeSet<String> = decls
+            .iter()
+            .flat_map(|decl| decl.attrs.iter())
+            .map(attr_repr)
+            .collect();
+        let mut sig = String::new();
+        match self.func_type(name).map(|ty| &ty.kind) {
+            Some(TypeKind::Function(func_t)) => {
+                sig += &format!("decl {}", name);
+                for arg in &func_t.args {
+                    sig += &format!(" {}", type_repr(arg));
+                }
+                Self::push_attrs(&mut sig, &attrs);
+                sig += &format!(" : {}", type_repr(&func_t.ret));
+            }
+            _ => {
+                sig += &format!("def {}", name);
+                if let Some(def) = self.defs.get(name) {
+                    for arg in &def.args {
+                        sig += &format!(" {}", arg.ident.symbol);
+                    }
+                }
+                Self::push_attrs(&mut sig, &attrs);
+            }
+        }
+        Some(sig)
+    }
+
+    /// Returns the first doc comment of the functions declarations.
+    fn func_doc(&self, name: &str) -> Option<&'ast String> {
+        self.decls
+            .get(name)
+            .and_then(|decls| decls.iter().find_map(|decl| decl.doc.as_ref()))
+    }
+
+    fn push_attrs(sig: &mut String, attrs: &BTreeSet<String>) {
+        if !attrs.is_empty() {
+            *sig += &format!(" [{}]", attrs.iter().cloned().collect::<Vec<_>>().join(" "));
+        }
+    }
+
+    fn infix_signature(infix: &InfixDef<P>) -> String {
+        format!(
+            "infix {} {} {} {}",
+            infix.precedence, infix.op.symbol, infix.args.0.ident.symbol, infix.args.1.ident.symbol
+        )
+    }
+
+    fn prefix_signature(prefix: &PrefixDef<P>) -> String {
+        match prefix.precedence {
+            Some(prec) => format!(
+                "prefix {} {} {}",
+                prec, prefix.op.symbol, prefix.arg.ident.symbol
+            ),
+            None => format!("prefix {} {}", prefix.op.symbol, prefix.arg.ident.symbol),
+        }
+    }
+
+    fn prefix_reference(&mut self, op: &Op<P>) {
+        let prefix = self.prefixes.get(op.symbol.as_str());
+        self.references.push(Reference {
+            range: Range::from_span(&op.span),
+            target: prefix.map(|prefix| Range::from_span(&prefix.op.span)),
+            hover: prefix.map(|prefix| {
+                signature_hover(&Self::prefix_signature(prefix), prefix.doc.as_ref())
+            }),
+        });
+    }
+
+    fn func_reference(&mut self, ident: &Ident<P>) {
+        let hover = self
+            .func_signature(&ident.symbol)
+            .map(|s| signature_hover(&s, self.func_doc(&ident.symbol)));
+        self.references.push(Reference {
+            range: Range::from_span(&ident.span),
+            target: self.func_target(&ident.symbol),
+            hover,
+        });
+    }
+
+    fn arg_reference(&mut self, func: &str, arg: &FuncArg<P>, ident: &Ident<P>) {
+        self.references.push(Reference {
+            range: Range::from_span(&ident.span),
+            target: Some(Range::from_span(&arg.ident.span)),
+            hover: Some(format!(
+                "{}\n\nargument of `{}`",
+                code_block(&arg.ident.symbol),
+                func
+            )),
+        });
+    }
+
+    fn symbol(&mut self, node: &TopLevelDecl<P>, name: &Ident<P>, kind: u8) {
+        self.symbols.push(DocumentSymbol {
+            name: name.symbol.to_string(),
+            detail: self.func_type(&name.symbol).map(type_repr),
+            kind,
+            range: Range::from_span(&node.span),
+            selection_range: Range::from_span(&name.span),
+        });
+    }
+
+    fn visit_args(&mut self, name: &'ast str, args: Vec<&'ast FuncArg<P>>) {
+        for arg in &args {
+            self.arg_reference(name, arg, &arg.ident);
+        }
+        self.scope = Some((name, args));
+    }
+}
+
+impl<'ast, P: Pointer> Pass<'ast, P> for Indexer<'ast, P> {
+    fn visit_module(&mut self, node: &'ast Module<P>) {
+        self.collect_top_level(node);
+        walk_module(self, node);
+    }
+
+    fn visit_top_level_decl(&mut self, node: &'ast TopLevelDecl<P>) {
+        match node.kind {
+            TopLevelDeclKind::FunctionDecl(ref decl) => {
+                self.symbol(node, &decl.ident, SYMBOL_FUNCTION)
+            }
+            TopLevelDeclKind::FunctionDef(ref def) => {
+                self.symbol(node, &def.decl.ident, SYMBOL_FUNCTION)
+            }
+            TopLevelDeclKind::InfixDef(ref infix) => {
+                self.symbols.push(DocumentSymbol {
+                    name: infix.op.symbol.to_string(),
+                    detail: None,
+                    kind: SYMBOL_OPERATOR,
+                    range: Range::from_span(&node.span),
+                    selection_range: Range::from_span(&infix.op.span),
+                });
+            }
+            TopLevelDeclKind::PrefixDef(ref prefix) => {
+                self.symbols.push(DocumentSymbol {
+                    name: prefix.op.symbol.to_string(),
+                    detail: None,
+                    kind: SYMBOL_OPERATOR,
+                    range: Range::from_span(&node.span),
+                    selection_range: Range::from_span(&prefix.op.span),
+                });
+            }
+        }
+        walk_top_level_decl(self, node);
+    }
+
+    fn visit_func_decl(&mut self, node: &'ast FuncDecl<P>) {
+        self.func_reference(&node.ident);
+    }
+
+    fn visit_func_def(&mut self, node: &'ast FuncDef<P>) {
+        self.func_reference(&node.decl.ident);
+        self.visit_args(&node.decl.ident.symbol, node.args.iter().collect());
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_infix_def(&mut self, node: &'ast InfixDef<P>) {
+        self.visit_op(&node.op);
+        self.visit_args(&node.op.symbol, vec![&node.args.0, &node.args.1]);
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_prefix_def(&mut self, node: &'ast PrefixDef<P>) {
+        self.prefix_reference(&node.op);
+        self.visit_args(&node.op.symbol, vec![&node.arg]);
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &'ast PrefixOpCall<P>) {
+        self.prefix_reference(&node.op);
+        self.visit_expr(&node.expr);
+    }
+
+    fn visit_ident(&mut self, node: &'ast Ident<P>) {
+        let arg = self.scope.as_ref().and_then(|(func, args)| {
+            args.iter()
+                .find(|arg| arg.ident.symbol == node.symbol)
+                .map(|arg| (*func, *arg))
+        });
+        match arg {
+            Some((func, arg)) => self.arg_reference(func, arg, node),
+            None => self.func_reference(node),
+        }
+    }
+
+    fn visit_op(&mut self, node: &'ast Op<P>) {
+        let infix = self.infixes.get(node.symbol.as_str());
+        self.references.push(Reference {
+            range: Range::from_span(&node.span),
+            target: infix.map(|infix| Range::from_span(&infix.op.span)),
+            hover: infix
+                .map(|infix| signature_hover(&Self::infix_signature(infix), infix.doc.as_ref())),
+        });
+    }
+}
@@ -0,0 +1,23 @@
+//! Language server for the FTL language.
+//!
+//! Implements the subset of the
+//! [Language Server Protocol](https://microsoft.github.io/language-server-protocol/)
+//! over any pair of reader and writer (in the `ftl_lsp` binary
+//! those are the standard input and output).
+//!
+//! Supported features are:
+//! * diagnostics reported by the lexer, parser and passes
+//!   published on every change of the document;
+//! * go to definition of functions, infixes and arguments;
+//! * hover showing function type and attributes;
+//! * document symbols for every top level declaration.
+//!
+//! Only full document synchronization is supported.
+
+pub mod protocol;
+pub mod rpc;
+pub mod server;
+
+mod analysis;
+
+pub use server::Server;
@@ -0,0 +1,9 @@
+use std::io;
+
+use ftl_lsp::Server;
+
+fn main() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut server = Server::new(io::stdout());
+    server.run(&mut stdin.lock())
+}
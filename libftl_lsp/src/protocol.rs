@@ -0,0 +1,83 @@
+//! Language server protocol structures
+//! used by the server.
+//!
+//! Only fields the server actually uses are defined.
+
+use serde::{Deserialize, Serialize};
+
+use ftl_source::{Pointer, Span};
+
+/// Position in the document. Both the line and
+/// the character are counted from zero and the character
+/// is counted in utf-16 code units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+impl Position {
+    /// Returns position the source pointer is pointing to.
+    pub fn from_ptr<P: Pointer>(ptr: &P) -> Self {
+        Self {
+            line: ptr.line().saturating_sub(1),
+            character: ptr.utf16_position() - 1,
+        }
+    }
+}
+
+/// Range in the document, end is exclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    /// Returns range covered by the span.
+    pub fn from_span<P: Pointer>(span: &Span<P>) -> Self {
+        Self::from_ptrs(&span.beg, &span.end)
+    }
+
+    /// Returns range between two pointers.
+    pub fn from_ptrs<P: Pointer>(beg: &P, end: &P) -> Self {
+        Self {
+            start: Position::from_ptr(beg),
+            end: Position::from_ptr(end),
+        }
+    }
+
+    /// Returns true if the position lies in the range.
+    /// Position right after the end of the range
+    /// is considered a part of it as thats where the cursor
+    /// is after typing a word.
+    pub fn contains(&self, pos: Position) -> bool {
+        self.start <= pos && pos <= self.end
+    }
+}
+
+/// Severity of the diagnostic as defined in the protocol.
+pub const SEVERITY_ERROR: u8 = 1;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: u8,
+    pub source: String,
+    pub message: String,
+}
+
+/// Kinds of symbols as defined in the protocol.
+pub const SYMBOL_FUNCTION: u8 = 12;
+pub const SYMBOL_OPERATOR: u8 = 25;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbol {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub kind: u8,
+    pub range: Range,
+    pub selection_range: Range,
+}
@@ -0,0 +1,82 @@
+//! Reading and writing of the JSON-RPC messages
+//! framed with the `Content-Length` header.
+
+use std::io;
+use std::io::{BufRead, Write};
+
+use serde_json::Value;
+
+/// Reads one message from the reader.
+///
+/// Returns None if the reader has ended before the next message.
+///
+/// # Errors
+///
+/// Returns error of the `InvalidData` kind if the message
+/// is missing its `Content-Length` header or is not a valid JSON.
+pub fn read_message(r: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if r.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let lowercase = line.to_ascii_lowercase();
+        if let Some(val) = lowercase.strip_prefix("content-length:") {
+            len = Some(val.trim().parse::<usize>().map_err(invalid_data)?);
+        }
+    }
+    let len = len.ok_or_else(|| invalid_data("Missing Content-Length header"))?;
+    let mut body = vec![0; len];
+    r.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(invalid_data)
+}
+
+/// Writes message to the writer and flushes it.
+pub fn write_message(w: &mut impl Write, msg: &Value) -> io::Result<()> {
+    let body = msg.to_string();
+    write!(w, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    w.flush()
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn written_message_can_be_read_back() {
+        let mut buff = Vec::new();
+        write_message(&mut buff, &json!({"id": 1, "method": "ąę"})).unwrap();
+        write_message(&mut buff, &json!({"id": 2})).unwrap();
+        let mut r = &buff[..];
+        assert_eq!(
+            read_message(&mut r).unwrap(),
+            Some(json!({"id": 1, "method": "ąę"}))
+        );
+        assert_eq!(read_message(&mut r).unwrap(), Some(json!({"id": 2})));
+        assert_eq!(read_message(&mut r).unwrap(), None);
+    }
+
+    #[test]
+    fn message_without_length_is_an_error() {
+        let mut r = &b"Content-Type: foo\r\n\r\n{}"[..];
+        assert_eq!(
+            read_message(&mut r).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,469 @@
+//! Language server dispatching the protocol messages.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::analysis::{analyze, Analysis};
+use crate::protocol::Position;
+use crate::rpc;
+
+/// Error codes as defined in the JSON-RPC specification.
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+type RequestResult = Result<Value, (i64, String)>;
+
+/// Language server writing its responses and notifications
+/// to the given writer.
+///
+/// Documents are analyzed anew on every change
+/// and the diagnostics are published right after.
+///
+/// # Examples
+///
+/// Running server over the standard input and output:
+///
+/// ```no_run
+/// use ftl_lsp::Server;
+///
+/// let stdin = std::io::stdin();
+/// let mut server = Server::new(std::io::stdout());
+/// server.run(&mut stdin.lock()).unwrap();
+/// ```
+pub struct Server<W: Write> {
+    out: W,
+    docs: HashMap<String, Analysis>,
+    shutdown_requested: bool,
+}
+
+impl<W: Write> Server<W> {
+    /// Creates new server writing to `out`.
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            docs: HashMap::new(),
+            shutdown_requested: false,
+        }
+    }
+
+    /// Handles messages read from `input` until the `exit`
+    /// notification is received or the input ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if reading or writing a message fails.
+    pub fn run(&mut self, input: &mut impl BufRead) -> io::Result<()> {
+        while let Some(msg) = rpc::read_message(input)? {
+            if !self.handle(msg)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the writer the server has been writing to.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Handles one message.
+    /// Returns false if the server should stop.
+    fn handle(&mut self, msg: Value) -> io::Result<bool> {
+        let method = match msg.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            // Responses to our requests, we don't send any.
+            None => return Ok(true),
+        };
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+        match msg.get("id") {
+            Some(id) => {
+                let res = if self.shutdown_requested {
+                    Err((INVALID_REQUEST, String::from("Server is shutting down")))
+                } else {
+                    self.request(method, &params)
+                };
+                self.respond(id.clone(), res)?;
+            }
+            None if method == "exit" => return Ok(false),
+            None => self.notification(method, &params)?,
+        }
+        Ok(true)
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> RequestResult {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "documentSymbolProvider": true,
+                },
+                "serverInfo": { "name": "ftl_lsp" },
+            })),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => {
+                let (uri, pos) = Self::text_document_position(params)?;
+                Ok(self
+                    .reference_at(&uri, pos)
+                    .and_then(|r| r.target)
+                    .map_or(Value::Null, |range| json!({ "uri": uri, "range": range })))
+            }
+            "textDocument/hover" => {
+                let (uri, pos) = Self::text_document_position(params)?;
+                Ok(self
+                    .reference_at(&uri, pos)
+                    .and_then(|r| r.hover.as_ref().map(|hover| (r.range, hover)))
+                    .map_or(Value::Null, |(range, hover)| {
+                        json!({
+                            "contents": { "kind": "markdown", "value": hover },
+                            "range": range,
+                        })
+                    }))
+            }
+            "textDocument/documentSymbol" => {
+                let uri = Self::uri(params)?;
+                Ok(self
+                    .docs
+                    .get(&uri)
+                    .map_or(Value::Null, |doc| json!(doc.symbols)))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<()> {
+        match method {
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                if let (Some(uri), Some(text)) = (doc["uri"].as_str(), doc["text"].as_str()) {
+                    self.update(uri, text)?;
+                }
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str();
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.update(uri, text)?;
+                }
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    self.docs.remove(uri);
+                    self.publish_diagnostics(uri, json!([]))?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, uri: &str, text: &str) -> io::Result<()> {
+        let analysis = analyze(text);
+        let diagnostics = json!(analysis.diagnostics);
+        self.docs.insert(uri.to_owned(), analysis);
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn reference_at(&self, uri: &str, pos: Position) -> Option<&crate::analysis::Reference> {
+        self.docs.get(uri).and_then(|doc| doc.reference_at(pos))
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Value) -> io::Result<()> {
+        rpc::write_message(
+            &mut self.out,
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/publishDiagnostics",
+                "params": { "uri": uri, "diagnostics": diagnostics },
+            }),
+        )
+    }
+
+    fn respond(&mut self, id: Value, res: RequestResult) -> io::Result<()> {
+        let msg = match res {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        rpc::write_message(&mut self.out, &msg)
+    }
+
+    fn uri(params: &Value) -> Result<String, (i64, String)> {
+        params["textDocument"]["uri"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| (INVALID_PARAMS, String::from("Missing text document uri")))
+    }
+
+    fn text_document_position(params: &Value) -> Result<(String, Position), (i64, String)> {
+        let uri = Self::uri(params)?;
+        let pos = serde_json::from_value(params["position"].clone())
+            .map_err(|err| (INVALID_PARAMS, err.to_string()))?;
+        Ok((uri, pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///test.ftl";
+
+    const SOURCE: &str = "infix 5 + a b: @add a b
+decl add int int [lang_add] : int
+def add_one x: x + 1
+decl add_one int [inline] : int
+";
+
+    /// Runs the server over the scripted messages
+    /// and returns everything it has written.
+    fn run_script(msgs: Vec<Value>) -> Vec<Value> {
+        let mut input = Vec::new();
+        for msg in msgs {
+            rpc::write_message(&mut input, &msg).unwrap();
+        }
+        let mut server = Server::new(Vec::new());
+        server.run(&mut &input[..]).unwrap();
+        let out = server.into_inner();
+        let mut out = &out[..];
+        let mut res = Vec::new();
+        while let Some(msg) = rpc::read_message(&mut out).unwrap() {
+            res.push(msg);
+        }
+        res
+    }
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn notification(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "method": method, "params": params })
+    }
+
+    fn did_open(text: &str) -> Value {
+        notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "languageId": "ftl", "version": 1, "text": text } }),
+        )
+    }
+
+    fn at(id: u64, method: &str, line: usize, character: usize) -> Value {
+        request(
+            id,
+            method,
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+            }),
+        )
+    }
+
+    fn response(msgs: &[Value], id: u64) -> &Value {
+        msgs.iter()
+            .find(|msg| msg["id"] == json!(id))
+            .expect("No response with the given id")
+    }
+
+    fn published_diagnostics(msgs: &[Value]) -> Vec<&Value> {
+        msgs.iter()
+            .filter(|msg| msg["method"] == "textDocument/publishDiagnostics")
+            .map(|msg| &msg["params"]["diagnostics"])
+            .collect()
+    }
+
+    fn range(l1: usize, c1: usize, l2: usize, c2: usize) -> Value {
+        json!({
+            "start": { "line": l1, "character": c1 },
+            "end": { "line": l2, "character": c2 },
+        })
+    }
+
+    #[test]
+    fn initialize_returns_capabilities() {
+        let msgs = run_script(vec![request(1, "initialize", json!({}))]);
+        let caps = &response(&msgs, 1)["result"]["capabilities"];
+        assert_eq!(caps["textDocumentSync"], 1);
+        assert_eq!(caps["definitionProvider"], true);
+        assert_eq!(caps["hoverProvider"], true);
+        assert_eq!(caps["documentSymbolProvider"], true);
+    }
+
+    #[test]
+    fn correct_document_has_no_diagnostics() {
+        let msgs = run_script(vec![did_open(SOURCE)]);
+        assert_eq!(published_diagnostics(&msgs), vec![&json!([])]);
+    }
+
+    #[test]
+    fn lexer_and_parser_errors_are_published() {
+        let msgs = run_script(vec![did_open("def foo: 01")]);
+        let diags = published_diagnostics(&msgs);
+        let diags = diags[0].as_array().unwrap();
+        assert!(diags.len() >= 2);
+        assert_eq!(diags[0]["message"], "integers cannot start with 0");
+        assert_eq!(diags[0]["range"], range(0, 9, 0, 10));
+        assert_eq!(diags[0]["severity"], 1);
+    }
+
+    #[test]
+    fn pass_errors_are_published() {
+        let msgs = run_script(vec![did_open("def foo: 1 ++ 2")]);
+        let diags = published_diagnostics(&msgs);
+        assert_eq!(diags[0][0]["message"], "Uknown operator precedence: ++");
+        assert_eq!(diags[0][0]["range"], range(0, 11, 0, 13));
+    }
+
+    #[test]
+    fn attribute_errors_are_published() {
+        let msgs = run_script(vec![did_open("def foo [inline(now)]: 1")]);
+        let diags = published_diagnostics(&msgs);
+        assert_eq!(
+            diags[0][0]["message"],
+            "Attribute `inline` takes an optional argument, one of `always`, `never`"
+        );
+        assert_eq!(diags[0][0]["range"], range(0, 9, 0, 20));
+    }
+
+    #[test]
+    fn diagnostics_are_republished_on_change() {
+        let msgs = run_script(vec![
+            did_open("def foo: 1 ++ 2"),
+            notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": URI, "version": 2 },
+                    "contentChanges": [{ "text": "def foo: 1" }],
+                }),
+            ),
+            notification(
+                "textDocument/didClose",
+                json!({ "textDocument": { "uri": URI } }),
+            ),
+        ]);
+        let diags = published_diagnostics(&msgs);
+        assert_eq!(diags.len(), 3);
+        assert_eq!(diags[0].as_array().unwrap().len(), 1);
+        assert_eq!(diags[1], &json!([]));
+        assert_eq!(diags[2], &json!([]));
+    }
+
+    #[test]
+    fn go_to_definition() {
+        let msgs = run_script(vec![
+            did_open(SOURCE),
+            // `add` in the infix body goes to its declaration
+            at(1, "textDocument/definition", 0, 17),
+            // `x` in the function body goes to the argument
+            at(2, "textDocument/definition", 2, 15),
+            // `+` goes to the infix
+            at(3, "textDocument/definition", 2, 17),
+            // `add_one` declaration goes to the definition
+            at(4, "textDocument/definition", 3, 6),
+            // `1` is not a name
+            at(5, "textDocument/definition", 2, 19),
+        ]);
+        let def = |id| &response(&msgs, id)["result"];
+        assert_eq!(def(1)["uri"], URI);
+        assert_eq!(def(1)["range"], range(1, 5, 1, 8));
+        assert_eq!(def(2)["range"], range(2, 12, 2, 13));
+        assert_eq!(def(3)["range"], range(0, 8, 0, 9));
+        assert_eq!(def(4)["range"], range(2, 4, 2, 11));
+        assert_eq!(def(5), &Value::Null);
+    }
+
+    #[test]
+    fn hover_shows_type_and_attributes() {
+        let msgs = run_script(vec![
+            did_open(SOURCE),
+            at(1, "textDocument/hover", 0, 17),
+            at(2, "textDocument/hover", 2, 5),
+            at(3, "textDocument/hover", 2, 17),
+        ]);
+        let hover = |id| &response(&msgs, id)["result"];
+        assert_eq!(
+            hover(1)["contents"]["value"],
+            "```ftl\ndecl add int int [lang_add] : int\n```"
+        );
+        assert_eq!(hover(1)["range"], range(0, 16, 0, 19));
+        assert_eq!(
+            hover(2)["contents"]["value"],
+            "```ftl\ndecl add_one int [inline] : int\n```"
+        );
+        assert_eq!(hover(3)["contents"]["value"], "```ftl\ninfix 5 + a b\n```");
+    }
+
+    #[test]
+    fn hover_shows_doc_comments() {
+        let src = "## Adds two numbers.\ndecl add int int: int\n## Plus.\ninfix 5 + a b: @add a b\ndef f: 1 + 2\n";
+        let msgs = run_script(vec![
+            did_open(src),
+            at(1, "textDocument/hover", 3, 17),
+            at(2, "textDocument/hover", 4, 9),
+        ]);
+        let hover = |id| &response(&msgs, id)["result"];
+        assert_eq!(
+            hover(1)["contents"]["value"],
+            "```ftl\ndecl add int int : int\n```\n\nAdds two numbers."
+        );
+        assert_eq!(
+            hover(2)["contents"]["value"],
+            "```ftl\ninfix 5 + a b\n```\n\nPlus."
+        );
+    }
+
+    #[test]
+    fn document_symbols_for_every_top_level_decl() {
+        let msgs = run_script(vec![
+            did_open(SOURCE),
+            request(
+                1,
+                "textDocument/documentSymbol",
+                json!({ "textDocument": { "uri": URI } }),
+            ),
+        ]);
+        let symbols = response(&msgs, 1)["result"].as_array().unwrap();
+        let names: Vec<_> = symbols.iter().map(|s| (&s["name"], &s["kind"])).collect();
+        assert_eq!(
+            names,
+            vec![
+                (&json!("+"), &json!(25)),
+                (&json!("add"), &json!(12)),
+                (&json!("add_one"), &json!(12)),
+                (&json!("add_one"), &json!(12)),
+            ]
+        );
+        assert_eq!(symbols[1]["range"], range(1, 0, 1, 33));
+        assert_eq!(symbols[1]["selectionRange"], range(1, 5, 1, 8));
+        assert_eq!(symbols[1]["detail"], "(int int) int");
+    }
+
+    #[test]
+    fn unknown_requests_and_requests_after_shutdown_fail() {
+        let msgs = run_script(vec![
+            request(1, "textDocument/rename", json!({})),
+            request(2, "shutdown", Value::Null),
+            request(3, "initialize", json!({})),
+            notification("exit", Value::Null),
+            request(4, "initialize", json!({})),
+        ]);
+        assert_eq!(response(&msgs, 1)["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(response(&msgs, 2)["result"], Value::Null);
+        assert_eq!(response(&msgs, 3)["error"]["code"], INVALID_REQUEST);
+        assert_eq!(msgs.len(), 3);
+    }
+}
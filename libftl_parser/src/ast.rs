@@ -1,5 +1,16 @@
+use ftl_session::symbol::Symbol;
 use ftl_source::{Pointer, Source, Span};
 
+#[cfg(feature = "serde")]
+use crate::serialize::FromPosition;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "S::Pointer: FromPosition"))
+)]
 pub struct AST<S: Source> {
     pub root: Module<S::Pointer>,
 }
@@ -12,31 +23,81 @@ impl<S: Source> AST<S> {
 
 pub type NodeId = usize;
 
+/// Allocates unique ids for the nodes of one syntax tree.
+///
+/// Ids are given sequentially, starting from zero.
+#[derive(Default)]
+pub struct NodeIds {
+    next: NodeId,
+}
+
+impl NodeIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the next unused id.
+    pub fn next_id(&mut self) -> NodeId {
+        let id = self.next;
+        self.next += 1;
+        id
+    }
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct Module<T: Pointer> {
     pub id: NodeId,
     pub decl: Vec<TopLevelDecl<T>>,
 }
 
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct TopLevelDecl<T: Pointer> {
     pub id: NodeId,
     pub kind: TopLevelDeclKind<T>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::span"))]
     pub span: Span<T>,
 }
 
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub enum TopLevelDeclKind<T: Pointer> {
     FunctionDef(FuncDef<T>),
     FunctionDecl(FuncDecl<T>),
     InfixDef(InfixDef<T>),
+    PrefixDef(PrefixDef<T>),
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct FuncDecl<T: Pointer> {
     pub id: NodeId,
     pub ty: Option<Type<T>>, // for now, we dont have infering yet
     pub attrs: Vec<FuncAttr<T>>,
     pub ident: Ident<T>,
+    /// Text of the `##` comments preceding the declaration.
+    pub doc: Option<String>,
 }
 
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct FuncDef<T: Pointer> {
     pub id: NodeId,
     pub decl: FuncDecl<T>,
@@ -44,6 +105,11 @@ pub struct FuncDef<T: Pointer> {
     pub body: Expr<T>,
 }
 
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct InfixDef<T: Pointer> {
     pub id: NodeId,
     pub ty: Option<Type<T>>,
@@ -51,35 +117,104 @@ pub struct InfixDef<T: Pointer> {
     pub op: Op<T>,
     pub args: (FuncArg<T>, FuncArg<T>),
     pub body: Expr<T>,
+    /// Text of the `##` comments preceding the definition.
+    pub doc: Option<String>,
 }
 
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
+pub struct PrefixDef<T: Pointer> {
+    pub id: NodeId,
+    pub ty: Option<Type<T>>,
+    /// Prefix without the precedence binds tighter
+    /// than any infix operator.
+    pub precedence: Option<usize>,
+    pub op: Op<T>,
+    pub arg: FuncArg<T>,
+    pub body: Expr<T>,
+    /// Text of the `##` comments preceding the definition.
+    pub doc: Option<String>,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct FuncArg<T: Pointer> {
     pub id: NodeId,
     pub ty: Option<Type<T>>,
     pub ident: Ident<T>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::span"))]
     pub span: Span<T>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct FuncAttr<T: Pointer> {
     pub id: NodeId,
     pub ident: Ident<T>,
+    /// Arguments given in the parenthesis, like in `inline(always)`,
+    /// followed by the literals written after the name, like in `lang "add"`.
+    pub args: Vec<AttrArg<T>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::span"))]
+    pub span: Span<T>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
+pub enum AttrArg<T: Pointer> {
+    Lit(Lit<T>),
+    Ident(Ident<T>),
+}
+
+impl<T: Pointer> AttrArg<T> {
+    pub fn span(&self) -> &Span<T> {
+        match self {
+            AttrArg::Lit(lit) => &lit.span,
+            AttrArg::Ident(ident) => &ident.span,
+        }
+    }
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct Expr<T: Pointer> {
     pub id: NodeId,
     pub kind: ExprKind<T>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::span"))]
     pub span: Span<T>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub enum ExprKind<T: Pointer> {
     FunctionCall(FuncCall<T>),
     Literal(Lit<T>),
     Identifier(Ident<T>),
     InfixFuncCall(InfixFuncCall<T>),
     InfixOpCall(InfixOpCall<T>),
+    PrefixOpCall(PrefixOpCall<T>),
+    Section(Section<T>),
     // We need it because parser doesn't
     // know about precedence but the
     // later passes need to know about them.
@@ -87,6 +222,11 @@ pub enum ExprKind<T: Pointer> {
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct FuncCall<T: Pointer> {
     pub id: NodeId,
     pub lhs: Box<Expr<T>>,
@@ -94,12 +234,22 @@ pub struct FuncCall<T: Pointer> {
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct Paren<T: Pointer> {
     pub id: NodeId,
     pub expr: Box<Expr<T>>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct InfixFuncCall<T: Pointer> {
     pub id: NodeId,
     pub ident: Ident<T>,
@@ -108,6 +258,11 @@ pub struct InfixFuncCall<T: Pointer> {
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct InfixOpCall<T: Pointer> {
     pub id: NodeId,
     pub op: Op<T>,
@@ -116,47 +271,116 @@ pub struct InfixOpCall<T: Pointer> {
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
+pub struct PrefixOpCall<T: Pointer> {
+    pub id: NodeId,
+    pub op: Op<T>,
+    pub expr: Box<Expr<T>>,
+}
+
+/// Infix operator in the parenthesis with one or both of
+/// its operands missing, like `(+)`, `(2 *)` or `(+ 1)`.
+/// It is the function taking the missing operands.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
+pub struct Section<T: Pointer> {
+    pub id: NodeId,
+    pub op: Op<T>,
+    pub lhs: Option<Box<Expr<T>>>,
+    pub rhs: Option<Box<Expr<T>>>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct Lit<T: Pointer> {
     pub id: NodeId,
     pub kind: LitKind,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::span"))]
     pub span: Span<T>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LitKind {
-    Int(u64),
+    Int(i64),
+    Float(f64),
+    /// String with its escape sequences already replaced.
+    Str(#[cfg_attr(feature = "serde", serde(with = "crate::serialize::symbol"))] Symbol),
+    Char(char),
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct Op<T: Pointer> {
     pub id: NodeId,
-    pub symbol: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::symbol"))]
+    pub symbol: Symbol,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::span"))]
     pub span: Span<T>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct Ident<T: Pointer> {
     pub id: NodeId,
-    pub symbol: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::symbol"))]
+    pub symbol: Symbol,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::span"))]
     pub span: Span<T>,
 }
 
 /// Types
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct Type<T: Pointer> {
     pub id: NodeId,
     pub kind: TypeKind<T>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::span"))]
     pub span: Span<T>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub enum TypeKind<T: Pointer> {
     Function(FuncType<T>),
     Literal(LitType),
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(serialize = "", deserialize = "T: FromPosition"))
+)]
 pub struct FuncType<T: Pointer> {
     pub id: NodeId,
     pub ret: Box<Type<T>>,
@@ -164,16 +388,23 @@ pub struct FuncType<T: Pointer> {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LitType {
     Int,
+    Float,
     Void,
+    Str,
+    Char,
 }
 
 pub fn is_lit_type(symbol: &str) -> Option<LitType> {
     use LitType::*;
     match symbol {
         "int" => Some(Int),
+        "float" => Some(Float),
         "void" => Some(Void),
+        "str" => Some(Str),
+        "char" => Some(Char),
         _ => None,
     }
 }
@@ -25,13 +25,13 @@ where
 
 impl<'a, S, P, R, F> Combinator<'a, S, PRes<R, P>> for TryComb<'a, S, R, F>
 where
-    P: Pointer,
+    P: 'static + Pointer,
     S: 'static + Source<Pointer = P>,
     F: FnOnce(&mut Parser<S>) -> PRes<R, P>,
 {
     fn run_chain(self) -> (&'a mut Parser<S>, PRes<R, P>) {
         let Self(parser, func) = self;
-        let res = func(parser);
+        let res = parser.attempt(func);
         (parser, res)
     }
 }
@@ -42,6 +42,7 @@ where
     C: Combinator<'a, S, PRes<R, S::Pointer>>,
 {
     prev: C,
+    beg: S::Pointer,
     kind: token::Kind,
     val: token::Value,
     msg: String,
@@ -55,9 +56,16 @@ where
     S: 'static + Source,
     C: Combinator<'a, S, PRes<R, S::Pointer>>,
 {
-    pub fn chain(prev: C, kind: token::Kind, val: token::Value, msg: String) -> Self {
+    pub fn chain(
+        prev: C,
+        beg: S::Pointer,
+        kind: token::Kind,
+        val: token::Value,
+        msg: String,
+    ) -> Self {
         Self {
             prev,
+            beg,
             kind,
             val,
             msg,
@@ -76,24 +84,22 @@ where
     fn run_chain(self) -> (&'a mut Parser<S>, R) {
         let Self {
             prev,
+            beg,
             kind,
             val,
             msg,
             ..
         } = self;
         let (parser, res) = prev.run_chain();
-        let res = res.unwrap_or_else(|err| {
-            let beg = parser.pop_ptr();
-            match err {
-                ParseErr::EOF => parser.eof_reached_fatal(beg, parser.curr_ptr()),
-                ParseErr::NotThisItem(tok) => {
-                    parser.fatal(Parser::<S>::unexpected_token_err(
-                        kind.clone(),
-                        val,
-                        tok,
-                        msg,
-                    ));
-                }
+        let res = res.unwrap_or_else(|err| match err {
+            ParseErr::EOF => parser.eof_reached_fatal(beg, parser.curr_ptr()),
+            ParseErr::NotThisItem(tok) => {
+                parser.fatal(Parser::<S>::unexpected_token_err(
+                    kind.clone(),
+                    val,
+                    tok,
+                    msg,
+                ));
             }
         });
         (parser, res)
@@ -106,6 +112,7 @@ where
     C: Combinator<'a, S, PRes<R, S::Pointer>>,
 {
     prev: C,
+    beg: S::Pointer,
     msg: String,
 
     _s: PhantomData<&'a S>,
@@ -117,9 +124,10 @@ where
     S: 'static + Source,
     C: Combinator<'a, S, PRes<R, S::Pointer>>,
 {
-    pub fn chain(prev: C, msg: String) -> Self {
+    pub fn chain(prev: C, beg: S::Pointer, msg: String) -> Self {
         Self {
             prev,
+            beg,
             msg,
             _r: PhantomData,
             _s: PhantomData,
@@ -133,15 +141,12 @@ where
     C: Combinator<'a, S, PRes<R, S::Pointer>>,
 {
     fn run_chain(self) -> (&'a mut Parser<S>, R) {
-        let Self { prev, msg, .. } = self;
+        let Self { prev, beg, msg, .. } = self;
         let (parser, res) = prev.run_chain();
-        let res = res.unwrap_or_else(|err| {
-            let beg = parser.pop_ptr();
-            match err {
-                ParseErr::EOF => parser.eof_reached_fatal(beg, parser.curr_ptr()),
-                ParseErr::NotThisItem(_) => {
-                    parser.fatal(Parser::<S>::msg_err(msg, beg, parser.curr_ptr()))
-                }
+        let res = res.unwrap_or_else(|err| match err {
+            ParseErr::EOF => parser.eof_reached_fatal(beg, parser.curr_ptr()),
+            ParseErr::NotThisItem(_) => {
+                parser.fatal(Parser::<S>::msg_err(msg, beg, parser.curr_ptr()))
             }
         });
         (parser, res)
@@ -180,7 +185,7 @@ where
 
 impl<'a, S, P, R, C, F> Combinator<'a, S, PRes<R, P>> for OrComb<'a, S, R, C, F>
 where
-    P: Pointer,
+    P: 'static + Pointer,
     S: 'static + Source<Pointer = P>,
     C: Combinator<'a, S, PRes<R, P>>,
     F: FnOnce(&mut Parser<S>) -> PRes<R, P>,
@@ -192,7 +197,7 @@ where
             ..
         } = self;
         let (parser, res) = prev_comb.run_chain();
-        let res = res.or_else(|_| fallback(parser));
+        let res = res.or_else(|_| parser.attempt(fallback));
         (parser, res)
     }
 }
@@ -294,7 +299,7 @@ where
             prev, meth, mapper, ..
         } = self;
         let (parser, res) = prev.run_chain();
-        let res = res.or_else(|_| meth(parser).and_then(move |val| Ok(mapper(val))));
+        let res = res.or_else(|_| meth(parser).map(mapper));
         (parser, res)
     }
 }
@@ -26,6 +26,7 @@ where
 {
     fn fail_unex_tok(
         self,
+        beg: P,
         kind: token::Kind,
         val: token::Value,
         msg: String,
@@ -33,14 +34,14 @@ where
     where
         Self: Combinator<'a, S, PRes<R, P>> + Sized,
     {
-        TryFailUnexpectedErrParser::chain(self, kind, val, msg)
+        TryFailUnexpectedErrParser::chain(self, beg, kind, val, msg)
     }
 
-    fn fail_msg(self, msg: String) -> TryFailMsgErrorParser<'a, S, R, Self>
+    fn fail_msg(self, beg: P, msg: String) -> TryFailMsgErrorParser<'a, S, R, Self>
     where
         Self: Combinator<'a, S, PRes<R, P>> + Sized,
     {
-        TryFailMsgErrorParser::chain(self, msg)
+        TryFailMsgErrorParser::chain(self, beg, msg)
     }
 
     fn or<F>(self, meth: F) -> OrComb<'a, S, R, Self, F>
@@ -51,6 +52,7 @@ where
         OrComb::chain(self, meth)
     }
 
+    #[allow(dead_code)]
     fn or_and_map<F, M, R2>(self, meth: F, mapper: M) -> OrAndThenMapComb<'a, S, Self, R2, R, F, M>
     where
         Self: Combinator<'a, S, PRes<R, P>> + Sized,
@@ -90,7 +92,7 @@ pub(crate) struct Comb<'a, S: 'static + Source>(pub &'a mut Parser<S>);
 
 impl<'a, S, P> Comb<'a, S>
 where
-    P: Pointer,
+    P: 'static + Pointer,
     S: 'static + Source<Pointer = P>,
 {
     pub fn r#try<R, F>(self, meth: F) -> impl Combinator<'a, S, PRes<R, P>>
@@ -8,5 +8,5 @@ where
     P: Pointer,
     F1: FnOnce(R1) -> R2,
 {
-    move |res| res.and_then(move |val| Ok(f(val)))
+    move |res| res.map(f)
 }
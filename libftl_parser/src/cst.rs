@@ -0,0 +1,423 @@
+//! Concrete syntax tree.
+//!
+//! Concrete syntax tree keeps every token of the source,
+//! with whitespaces and comments preceding them, grouped in nodes
+//! mirroring the syntax tree nodes which have a span.
+//! Exact source can be reconstructed from it with [`Cst::text`].
+//!
+//! It is built with [`Parser::concrete_tree`](../struct.Parser.html#method.concrete_tree)
+//! from the tokens of the lossless lexer.
+
+use ftl_lexer::token::{Token, Trivia};
+use ftl_source::{Pointer, Span};
+
+use crate::ast::*;
+
+pub struct Cst<T: Pointer> {
+    pub root: Node<T>,
+    /// Whitespaces and comments after the last token.
+    pub trailing: Vec<Trivia<T>>,
+}
+
+impl<T: Pointer> Cst<T> {
+    /// Returns source the tree has been built from.
+    pub fn text(&self) -> String {
+        let mut res = self.root.text();
+        for trivia in &self.trailing {
+            res += &trivia.text;
+        }
+        res
+    }
+}
+
+pub struct Node<T: Pointer> {
+    pub kind: NodeKind,
+    /// Id of the corresponding syntax tree node.
+    pub id: NodeId,
+    /// Span of the corresponding syntax tree node,
+    /// `None` for the module as it has no span.
+    pub span: Option<Span<T>>,
+    pub children: Vec<Element<T>>,
+}
+
+impl<T: Pointer> Node<T> {
+    /// Returns source of the node including trivia
+    /// preceding its tokens.
+    pub fn text(&self) -> String {
+        let mut res = String::new();
+        self.push_text(&mut res);
+        res
+    }
+
+    fn push_text(&self, res: &mut String) {
+        for child in &self.children {
+            match child {
+                Element::Node(node) => node.push_text(res),
+                Element::Leaf(leaf) => {
+                    for trivia in &leaf.token.trivia {
+                        *res += &trivia.text;
+                    }
+                    *res += &leaf.text;
+                }
+            }
+        }
+    }
+
+    /// Returns direct child nodes.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node<T>> {
+        self.children.iter().filter_map(|child| match child {
+            Element::Node(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    /// Returns tokens directly in the node.
+    pub fn leaves(&self) -> impl Iterator<Item = &Leaf<T>> {
+        self.children.iter().filter_map(|child| match child {
+            Element::Leaf(leaf) => Some(leaf),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Module,
+    FuncDef,
+    FuncDecl,
+    InfixDef,
+    PrefixDef,
+    FuncArg,
+    Expr,
+    Type,
+    Lit,
+    Op,
+    Ident,
+}
+
+pub enum Element<T: Pointer> {
+    Node(Node<T>),
+    Leaf(Leaf<T>),
+}
+
+/// Token with its source.
+pub struct Leaf<T: Pointer> {
+    pub token: Token<T>,
+    pub text: String,
+}
+
+/// Builds concrete syntax tree from the lexed tokens
+/// and the syntax tree parsed from them.
+///
+/// Every token is put into the innermost node whose span
+/// contains the tokens beginning. Tokens not covered by any
+/// node, like the ones after the parsing error, end up in the module.
+pub(crate) fn build<T: Pointer>(
+    module: &Module<T>,
+    leaves: Vec<Leaf<T>>,
+    trailing: Vec<Trivia<T>>,
+) -> Cst<T> {
+    let mut builder = Builder {
+        leaves: leaves.into_iter().peekable(),
+    };
+    let mut children = Vec::new();
+    for decl in &module.decl {
+        builder.child(Child::Decl(decl), &mut children);
+    }
+    builder.leaves_before(usize::MAX, &mut children);
+    Cst {
+        root: Node {
+            kind: NodeKind::Module,
+            id: module.id,
+            span: None,
+            children,
+        },
+        trailing,
+    }
+}
+
+struct Builder<T: Pointer> {
+    leaves: std::iter::Peekable<std::vec::IntoIter<Leaf<T>>>,
+}
+
+impl<T: Pointer> Builder<T> {
+    /// Adds to the children every leaf which begins before the offset.
+    fn leaves_before(&mut self, offset: usize, children: &mut Vec<Element<T>>) {
+        while let Some(leaf) = self.leaves.peek() {
+            if leaf.token.span.beg.offset() >= offset {
+                break;
+            }
+            children.push(Element::Leaf(self.leaves.next().unwrap()));
+        }
+    }
+
+    fn child(&mut self, child: Child<'_, T>, children: &mut Vec<Element<T>>) {
+        let span = child.span();
+        self.leaves_before(span.beg.offset(), children);
+        let mut grandchildren = Vec::new();
+        let mut nested = child.children();
+        nested.sort_by_key(|c| c.span().beg.offset());
+        for c in nested {
+            self.child(c, &mut grandchildren);
+        }
+        self.leaves_before(span.end.offset(), &mut grandchildren);
+        children.push(Element::Node(Node {
+            kind: child.kind(),
+            id: child.id(),
+            span: Some(span.clone()),
+            children: grandchildren,
+        }));
+    }
+}
+
+/// Syntax tree node with a span.
+enum Child<'ast, T: Pointer> {
+    Decl(&'ast TopLevelDecl<T>),
+    Arg(&'ast FuncArg<T>),
+    Expr(&'ast Expr<T>),
+    Type(&'ast Type<T>),
+    Lit(&'ast Lit<T>),
+    Op(&'ast Op<T>),
+    Ident(&'ast Ident<T>),
+}
+
+impl<'ast, T: Pointer> Child<'ast, T> {
+    fn span(&self) -> &'ast Span<T> {
+        match self {
+            Child::Decl(node) => &node.span,
+            Child::Arg(node) => &node.span,
+            Child::Expr(node) => &node.span,
+            Child::Type(node) => &node.span,
+            Child::Lit(node) => &node.span,
+            Child::Op(node) => &node.span,
+            Child::Ident(node) => &node.span,
+        }
+    }
+
+    fn id(&self) -> NodeId {
+        match self {
+            Child::Decl(node) => node.id,
+            Child::Arg(node) => node.id,
+            Child::Expr(node) => node.id,
+            Child::Type(node) => node.id,
+            Child::Lit(node) => node.id,
+            Child::Op(node) => node.id,
+            Child::Ident(node) => node.id,
+        }
+    }
+
+    fn kind(&self) -> NodeKind {
+        match self {
+            Child::Decl(node) => match node.kind {
+                TopLevelDeclKind::FunctionDef(_) => NodeKind::FuncDef,
+                TopLevelDeclKind::FunctionDecl(_) => NodeKind::FuncDecl,
+                TopLevelDeclKind::InfixDef(_) => NodeKind::InfixDef,
+                TopLevelDeclKind::PrefixDef(_) => NodeKind::PrefixDef,
+            },
+            Child::Arg(_) => NodeKind::FuncArg,
+            Child::Expr(_) => NodeKind::Expr,
+            Child::Type(_) => NodeKind::Type,
+            Child::Lit(_) => NodeKind::Lit,
+            Child::Op(_) => NodeKind::Op,
+            Child::Ident(_) => NodeKind::Ident,
+        }
+    }
+
+    /// Returns children with spans, in any order.
+    fn children(&self) -> Vec<Child<'ast, T>> {
+        let mut res = Vec::new();
+        match self {
+            Child::Decl(node) => match node.kind {
+                TopLevelDeclKind::FunctionDef(ref def) => {
+                    Self::decl_children(&def.decl, &mut res);
+                    res.extend(def.args.iter().map(Child::Arg));
+                    res.push(Child::Expr(&def.body));
+                }
+                TopLevelDeclKind::FunctionDecl(ref decl) => {
+                    Self::decl_children(decl, &mut res);
+                    match decl.ty {
+                        // Type of the declaration is made of the types
+                        // separated with attributes so its children are
+                        // put directly in the declaration.
+                        Some(Type {
+                            kind: TypeKind::Function(ref func_t),
+                            ..
+                        }) => {
+                            res.extend(func_t.args.iter().map(Child::Type));
+                            res.push(Child::Type(&func_t.ret));
+                        }
+                        Some(ref ty) => res.push(Child::Type(ty)),
+                        None => (),
+                    }
+                }
+                TopLevelDeclKind::InfixDef(ref infix) => {
+                    res.push(Child::Op(&infix.op));
+                    res.push(Child::Arg(&infix.args.0));
+                    res.push(Child::Arg(&infix.args.1));
+                    res.push(Child::Expr(&infix.body));
+                }
+                TopLevelDeclKind::PrefixDef(ref prefix) => {
+                    res.push(Child::Op(&prefix.op));
+                    res.push(Child::Arg(&prefix.arg));
+                    res.push(Child::Expr(&prefix.body));
+                }
+            },
+            Child::Arg(node) => {
+                res.push(Child::Ident(&node.ident));
+                res.extend(node.ty.iter().map(Child::Type));
+            }
+            Child::Expr(node) => match node.kind {
+                ExprKind::FunctionCall(ref call) => {
+                    res.push(Child::Expr(&call.lhs));
+                    res.extend(call.args.iter().map(Child::Expr));
+                }
+                ExprKind::Literal(ref lit) => res.push(Child::Lit(lit)),
+                ExprKind::Identifier(ref ident) => res.push(Child::Ident(ident)),
+                ExprKind::InfixFuncCall(ref call) => {
+                    res.push(Child::Expr(&call.lhs));
+                    res.push(Child::Ident(&call.ident));
+                    res.push(Child::Expr(&call.rhs));
+                }
+                ExprKind::InfixOpCall(ref call) => {
+                    res.push(Child::Expr(&call.lhs));
+                    res.push(Child::Op(&call.op));
+                    res.push(Child::Expr(&call.rhs));
+                }
+                ExprKind::PrefixOpCall(ref call) => {
+                    res.push(Child::Op(&call.op));
+                    res.push(Child::Expr(&call.expr));
+                }
+                ExprKind::Section(ref section) => {
+                    res.extend(section.lhs.iter().map(|lhs| Child::Expr(lhs)));
+                    res.push(Child::Op(&section.op));
+                    res.extend(section.rhs.iter().map(|rhs| Child::Expr(rhs)));
+                }
+                ExprKind::Parenthesed(ref paren) => res.push(Child::Expr(&paren.expr)),
+            },
+            Child::Type(node) => {
+                if let TypeKind::Function(ref func_t) = node.kind {
+                    res.extend(func_t.args.iter().map(Child::Type));
+                    res.push(Child::Type(&func_t.ret));
+                }
+            }
+            Child::Lit(_) | Child::Op(_) | Child::Ident(_) => (),
+        }
+        res
+    }
+
+    fn decl_children(decl: &'ast FuncDecl<T>, res: &mut Vec<Child<'ast, T>>) {
+        res.push(Child::Ident(&decl.ident));
+        for attr in &decl.attrs {
+            res.push(Child::Ident(&attr.ident));
+            res.extend(attr.args.iter().map(|arg| match arg {
+                AttrArg::Lit(lit) => Child::Lit(lit),
+                AttrArg::Ident(ident) => Child::Ident(ident),
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_lexer::{token, Lexer};
+    use ftl_session::Session;
+    use ftl_source::string;
+    use ftl_utility::RcRef;
+
+    use crate::Parser;
+
+    fn cst(src: &str) -> Cst<string::Pointer> {
+        let sess = RcRef::new(Session::new(string::String::from(src)));
+        let lexer = Lexer::lossless(sess.clone());
+        let mut parser = Parser::new(lexer, sess);
+        let ast = parser.parse();
+        parser.concrete_tree(&ast)
+    }
+
+    #[test]
+    fn source_is_reconstructed_exactly() {
+        let src = r#"
+# Comment before 😁
+decl add int int [lang_add   inline] : int # after
+infix 50 + a b: @add a b
+
+def  foo a b:	a+b   *(2+ 2)
+	# inside
+	`foo 3
+decl test (int)int (int)int: (int int) int
+
+   # last
+   "#;
+        assert_eq!(cst(src).text(), src);
+    }
+
+    #[test]
+    fn source_with_errors_is_reconstructed_exactly() {
+        let src = "def foo: 1 +\ndef bar: 2 )  3 @ # end";
+        assert_eq!(cst(src).text(), src);
+    }
+
+    #[test]
+    fn attribute_arguments_are_in_the_declaration() {
+        let src = "decl f [lang  \"add\" inline( always )]: int";
+        let tree = cst(src);
+        assert_eq!(tree.text(), src);
+        let decl = tree.root.nodes().next().unwrap();
+        let kinds: Vec<NodeKind> = decl.nodes().map(|n| n.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::Ident,
+                NodeKind::Ident,
+                NodeKind::Lit,
+                NodeKind::Ident,
+                NodeKind::Ident,
+                NodeKind::Type
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_source_is_reconstructed_exactly() {
+        for src in &["", "  ", "# just comment\n"] {
+            assert_eq!(cst(src).text(), *src);
+        }
+    }
+
+    #[test]
+    fn tokens_are_in_innermost_nodes() {
+        let tree = cst("def foo a [inline]: a + 1 # end");
+        let root = &tree.root;
+        assert_eq!(root.kind, NodeKind::Module);
+        assert_eq!(root.leaves().count(), 0);
+
+        let def = root.nodes().next().unwrap();
+        assert_eq!(def.kind, NodeKind::FuncDef);
+        let kinds: Vec<NodeKind> = def.nodes().map(|n| n.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::Ident,
+                NodeKind::FuncArg,
+                NodeKind::Ident,
+                NodeKind::Expr
+            ]
+        );
+        let leaves: Vec<&str> = def.leaves().map(|l| l.text.as_str()).collect();
+        assert_eq!(leaves, vec!["def", "[", "]", ":"]);
+
+        let body = def.nodes().last().unwrap();
+        assert_eq!(body.text(), " a + 1");
+        let op = body.nodes().nth(1).unwrap();
+        assert_eq!(op.kind, NodeKind::Op);
+        assert_eq!(op.text(), " +");
+
+        let trailing: Vec<&token::Kind> = tree.trailing.iter().map(|t| &t.kind).collect();
+        assert_eq!(
+            trailing,
+            vec![&token::Kind::Whitespace, &token::Kind::Comment]
+        );
+    }
+}
@@ -0,0 +1,570 @@
+//! Structural comparison of the syntax trees.
+//!
+//! Two trees are structurally equal if they describe the same
+//! program, regardless of the node ids and spans they have.
+//! Parentheses are compared as any other node, as they are the
+//! only precedence barrier the expression reassociation respects.
+//! Trees already reassociated can be compared ignoring them with
+//! [`diff_ignoring_parens`](fn.diff_ignoring_parens.html).
+//! This way the tree transformed by some pass can be compared
+//! with the one parsed from the expected source, even if
+//! they come from the different kinds of sources.
+//!
+//! # Examples
+//!
+//! ```
+//! use ftl_lexer::Lexer;
+//! use ftl_parser::{diff, Parser};
+//! use ftl_session::Session;
+//! use ftl_source::string;
+//! use ftl_utility::RcRef;
+//!
+//! let parse = |src: &str| {
+//!     let sess = RcRef::new(Session::new(string::String::from(src)));
+//!     Parser::new(Lexer::new(sess.clone()), sess).parse()
+//! };
+//! let ast = parse("def f a: @g a 1");
+//! assert!(diff::struct_eq(&ast, &parse("def   f a :\n  @g a 1")));
+//! assert!(!diff::struct_eq(&ast, &parse("def f a: @g (a) 1")));
+//!
+//! let diff = diff::diff(&ast, &parse("def f a: @g a 2")).unwrap();
+//! assert_eq!(diff.to_string(), "decl[0].body.args[1]: literal `1` != literal `2`");
+//! ```
+
+use std::fmt;
+
+use ftl_source::{Pointer, Source};
+
+use crate::ast::*;
+
+/// First difference found between two trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    /// Path to the differing node from the compared one,
+    /// for example `decl[1].body.lhs`.
+    pub path: String,
+    /// Description of the node in the left tree.
+    pub left: String,
+    /// Description of the node in the right tree.
+    pub right: String,
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "root"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {} != {}", path, self.left, self.right)
+    }
+}
+
+/// Returns first difference between the nodes,
+/// or None if they are structurally equal.
+pub fn diff<L: StructEq<R>, R>(left: &L, right: &R) -> Option<Diff> {
+    left.diff_at(right, &mut Path::default()).err()
+}
+
+/// Returns true if the nodes are structurally equal.
+pub fn struct_eq<L: StructEq<R>, R>(left: &L, right: &R) -> bool {
+    diff(left, right).is_none()
+}
+
+/// Returns first difference between the nodes, comparing the
+/// parenthesized expressions by the expression they hold.
+///
+/// Only valid for the trees the expression reassociation
+/// pass has been run on, as before that the parentheses
+/// decide how the infix calls are nested.
+pub fn diff_ignoring_parens<L: StructEq<R>, R>(left: &L, right: &R) -> Option<Diff> {
+    let mut path = Path {
+        ignore_parens: true,
+        ..Path::default()
+    };
+    left.diff_at(right, &mut path).err()
+}
+
+/// Returns true if the nodes are structurally equal, ignoring
+/// the parentheses. See
+/// [`diff_ignoring_parens`](fn.diff_ignoring_parens.html).
+pub fn struct_eq_ignoring_parens<L: StructEq<R>, R>(left: &L, right: &R) -> bool {
+    diff_ignoring_parens(left, right).is_none()
+}
+
+/// Path to the currently compared node.
+#[derive(Default)]
+pub struct Path {
+    nodes: Vec<String>,
+    ignore_parens: bool,
+}
+
+impl Path {
+    fn diff(&self, left: impl fmt::Display, right: impl fmt::Display) -> Diff {
+        Diff {
+            path: self.nodes.join("."),
+            left: left.to_string(),
+            right: right.to_string(),
+        }
+    }
+
+    fn field<L: StructEq<R>, R>(&mut self, name: &str, left: &L, right: &R) -> Result<(), Diff> {
+        self.nodes.push(name.to_owned());
+        let res = left.diff_at(right, self);
+        self.nodes.pop();
+        res
+    }
+
+    fn list<L, R>(&mut self, name: &str, left: &[L], right: &[R]) -> Result<(), Diff>
+    where
+        L: StructEq<R> + Describe,
+        R: Describe,
+    {
+        for (i, (l, r)) in left.iter().zip(right).enumerate() {
+            self.field(&format!("{}[{}]", name, i), l, r)?;
+        }
+        let common = left.len().min(right.len());
+        if left.len() == right.len() {
+            return Ok(());
+        }
+        self.nodes.push(format!("{}[{}]", name, common));
+        let diff = self.diff(describe_nth(left, common), describe_nth(right, common));
+        self.nodes.pop();
+        Err(diff)
+    }
+
+    fn value<T>(&mut self, name: &str, left: T, right: T) -> Result<(), Diff>
+    where
+        T: PartialEq + fmt::Display,
+    {
+        self.nodes.push(name.to_owned());
+        let res = self.eq(left, right);
+        self.nodes.pop();
+        res
+    }
+
+    fn eq<T: PartialEq + fmt::Display>(&self, left: T, right: T) -> Result<(), Diff> {
+        if left == right {
+            Ok(())
+        } else {
+            Err(self.diff(left, right))
+        }
+    }
+}
+
+/// Structural comparison of the nodes.
+///
+/// Implemented for the nodes with any pointer types,
+/// so that the trees from different sources can be compared.
+pub trait StructEq<Rhs = Self> {
+    /// Compares the nodes placed at the given path.
+    fn diff_at(&self, other: &Rhs, path: &mut Path) -> Result<(), Diff>;
+}
+
+/// Short description of the node used in the diff.
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+fn describe_nth<T: Describe>(nodes: &[T], n: usize) -> String {
+    match nodes.get(n) {
+        Some(node) => node.describe(),
+        None => "nothing".to_owned(),
+    }
+}
+
+impl<A: Source, B: Source> StructEq<AST<B>> for AST<A> {
+    fn diff_at(&self, other: &AST<B>, path: &mut Path) -> Result<(), Diff> {
+        self.root.diff_at(&other.root, path)
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<Module<B>> for Module<A> {
+    fn diff_at(&self, other: &Module<B>, path: &mut Path) -> Result<(), Diff> {
+        path.list("decl", &self.decl, &other.decl)
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<TopLevelDecl<B>> for TopLevelDecl<A> {
+    fn diff_at(&self, other: &TopLevelDecl<B>, path: &mut Path) -> Result<(), Diff> {
+        use TopLevelDeclKind::*;
+        match (&self.kind, &other.kind) {
+            (FunctionDecl(l), FunctionDecl(r)) => l.diff_at(r, path),
+            (FunctionDef(l), FunctionDef(r)) => l.diff_at(r, path),
+            (InfixDef(l), InfixDef(r)) => l.diff_at(r, path),
+            (PrefixDef(l), PrefixDef(r)) => l.diff_at(r, path),
+            _ => Err(path.diff(self.describe(), other.describe())),
+        }
+    }
+}
+
+impl<P: Pointer> Describe for TopLevelDecl<P> {
+    fn describe(&self) -> String {
+        match self.kind {
+            TopLevelDeclKind::FunctionDecl(ref decl) => {
+                format!("function declaration `{}`", decl.ident.symbol)
+            }
+            TopLevelDeclKind::FunctionDef(ref def) => {
+                format!("function definition `{}`", def.decl.ident.symbol)
+            }
+            TopLevelDeclKind::InfixDef(ref infix) => {
+                format!("infix definition `{}`", infix.op.symbol)
+            }
+            TopLevelDeclKind::PrefixDef(ref prefix) => {
+                format!("prefix definition `{}`", prefix.op.symbol)
+            }
+        }
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<FuncDecl<B>> for FuncDecl<A> {
+    fn diff_at(&self, other: &FuncDecl<B>, path: &mut Path) -> Result<(), Diff> {
+        path.field("ident", &self.ident, &other.ident)?;
+        path.field("ty", &self.ty, &other.ty)?;
+        path.list("attrs", &self.attrs, &other.attrs)?;
+        path.value("doc", doc(&self.doc), doc(&other.doc))
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<FuncDef<B>> for FuncDef<A> {
+    fn diff_at(&self, other: &FuncDef<B>, path: &mut Path) -> Result<(), Diff> {
+        path.field("decl", &self.decl, &other.decl)?;
+        path.list("args", &self.args, &other.args)?;
+        path.field("body", &self.body, &other.body)
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<InfixDef<B>> for InfixDef<A> {
+    fn diff_at(&self, other: &InfixDef<B>, path: &mut Path) -> Result<(), Diff> {
+        path.field("op", &self.op, &other.op)?;
+        path.value("precedence", self.precedence, other.precedence)?;
+        path.field("args[0]", &self.args.0, &other.args.0)?;
+        path.field("args[1]", &self.args.1, &other.args.1)?;
+        path.field("ty", &self.ty, &other.ty)?;
+        path.field("body", &self.body, &other.body)?;
+        path.value("doc", doc(&self.doc), doc(&other.doc))
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<PrefixDef<B>> for PrefixDef<A> {
+    fn diff_at(&self, other: &PrefixDef<B>, path: &mut Path) -> Result<(), Diff> {
+        let precedence = |prec: Option<usize>| match prec {
+            Some(prec) => prec.to_string(),
+            None => "none".to_owned(),
+        };
+        path.field("op", &self.op, &other.op)?;
+        path.value(
+            "precedence",
+            precedence(self.precedence),
+            precedence(other.precedence),
+        )?;
+        path.field("arg", &self.arg, &other.arg)?;
+        path.field("ty", &self.ty, &other.ty)?;
+        path.field("body", &self.body, &other.body)?;
+        path.value("doc", doc(&self.doc), doc(&other.doc))
+    }
+}
+
+fn doc(doc: &Option<String>) -> String {
+    match doc {
+        Some(doc) => format!("{:?}", doc),
+        None => "no doc".to_owned(),
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<FuncArg<B>> for FuncArg<A> {
+    fn diff_at(&self, other: &FuncArg<B>, path: &mut Path) -> Result<(), Diff> {
+        path.field("ident", &self.ident, &other.ident)?;
+        path.field("ty", &self.ty, &other.ty)
+    }
+}
+
+impl<P: Pointer> Describe for FuncArg<P> {
+    fn describe(&self) -> String {
+        format!("argument `{}`", self.ident.symbol)
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<FuncAttr<B>> for FuncAttr<A> {
+    fn diff_at(&self, other: &FuncAttr<B>, path: &mut Path) -> Result<(), Diff> {
+        self.ident.diff_at(&other.ident, path)?;
+        path.list("args", &self.args, &other.args)
+    }
+}
+
+impl<P: Pointer> Describe for FuncAttr<P> {
+    fn describe(&self) -> String {
+        format!("attribute `{}`", self.ident.symbol)
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<AttrArg<B>> for AttrArg<A> {
+    fn diff_at(&self, other: &AttrArg<B>, path: &mut Path) -> Result<(), Diff> {
+        match (self, other) {
+            (AttrArg::Lit(l), AttrArg::Lit(r)) if lit_eq(l, r) => Ok(()),
+            (AttrArg::Ident(l), AttrArg::Ident(r)) if l.symbol == r.symbol => Ok(()),
+            _ => Err(path.diff(self.describe(), other.describe())),
+        }
+    }
+}
+
+impl<P: Pointer> Describe for AttrArg<P> {
+    fn describe(&self) -> String {
+        match self {
+            AttrArg::Lit(lit) => describe_lit(lit),
+            AttrArg::Ident(ident) => format!("identifier `{}`", ident.symbol),
+        }
+    }
+}
+
+fn lit_eq<A: Pointer, B: Pointer>(left: &Lit<A>, right: &Lit<B>) -> bool {
+    match (&left.kind, &right.kind) {
+        (LitKind::Int(l_val), LitKind::Int(r_val)) => l_val == r_val,
+        (LitKind::Float(l_val), LitKind::Float(r_val)) => l_val == r_val,
+        (LitKind::Str(l_val), LitKind::Str(r_val)) => l_val == r_val,
+        (LitKind::Char(l_val), LitKind::Char(r_val)) => l_val == r_val,
+        _ => false,
+    }
+}
+
+fn describe_lit<P: Pointer>(lit: &Lit<P>) -> String {
+    match lit.kind {
+        LitKind::Int(val) => format!("literal `{}`", val),
+        LitKind::Float(val) => format!("literal `{:?}`", val),
+        LitKind::Str(ref val) => format!("literal `{:?}`", val),
+        LitKind::Char(val) => format!("literal `{:?}`", val),
+    }
+}
+
+/// Returns the expression without the parentheses around it.
+fn unparen<P: Pointer>(mut expr: &Expr<P>) -> &Expr<P> {
+    while let ExprKind::Parenthesed(ref paren) = expr.kind {
+        expr = &paren.expr;
+    }
+    expr
+}
+
+impl<A: Pointer, B: Pointer> StructEq<Expr<B>> for Expr<A> {
+    fn diff_at(&self, other: &Expr<B>, path: &mut Path) -> Result<(), Diff> {
+        use ExprKind::*;
+        let (left, right) = if path.ignore_parens {
+            (unparen(self), unparen(other))
+        } else {
+            (self, other)
+        };
+        match (&left.kind, &right.kind) {
+            (Parenthesed(l), Parenthesed(r)) => {
+                path.field("expr", l.expr.as_ref(), r.expr.as_ref())
+            }
+            (Literal(l), Literal(r)) if lit_eq(l, r) => Ok(()),
+            (Identifier(l), Identifier(r)) if l.symbol == r.symbol => Ok(()),
+            (FunctionCall(l), FunctionCall(r)) => {
+                path.field("lhs", l.lhs.as_ref(), r.lhs.as_ref())?;
+                path.list("args", &l.args, &r.args)
+            }
+            (InfixFuncCall(l), InfixFuncCall(r)) if l.ident.symbol == r.ident.symbol => {
+                path.field("lhs", l.lhs.as_ref(), r.lhs.as_ref())?;
+                path.field("rhs", l.rhs.as_ref(), r.rhs.as_ref())
+            }
+            (InfixOpCall(l), InfixOpCall(r)) if l.op.symbol == r.op.symbol => {
+                path.field("lhs", l.lhs.as_ref(), r.lhs.as_ref())?;
+                path.field("rhs", l.rhs.as_ref(), r.rhs.as_ref())
+            }
+            (PrefixOpCall(l), PrefixOpCall(r)) if l.op.symbol == r.op.symbol => {
+                path.field("expr", l.expr.as_ref(), r.expr.as_ref())
+            }
+            (Section(l), Section(r))
+                if l.op.symbol == r.op.symbol
+                    && l.lhs.is_some() == r.lhs.is_some()
+                    && l.rhs.is_some() == r.rhs.is_some() =>
+            {
+                if let (Some(l), Some(r)) = (&l.lhs, &r.lhs) {
+                    path.field("lhs", l.as_ref(), r.as_ref())?;
+                }
+                if let (Some(l), Some(r)) = (&l.rhs, &r.rhs) {
+                    path.field("rhs", l.as_ref(), r.as_ref())?;
+                }
+                Ok(())
+            }
+            _ => Err(path.diff(left.describe(), right.describe())),
+        }
+    }
+}
+
+impl<P: Pointer> Describe for Expr<P> {
+    fn describe(&self) -> String {
+        match self.kind {
+            ExprKind::Literal(ref lit) => describe_lit(lit),
+            ExprKind::Identifier(ref ident) => format!("identifier `{}`", ident.symbol),
+            ExprKind::FunctionCall(_) => "function call".to_owned(),
+            ExprKind::InfixFuncCall(ref call) => format!("infix call `{}`", call.ident.symbol),
+            ExprKind::InfixOpCall(ref call) => format!("operator call `{}`", call.op.symbol),
+            ExprKind::PrefixOpCall(ref call) => format!("prefix call `{}`", call.op.symbol),
+            ExprKind::Section(ref section) => match (&section.lhs, &section.rhs) {
+                (Some(_), _) => format!("left section `{}`", section.op.symbol),
+                (_, Some(_)) => format!("right section `{}`", section.op.symbol),
+                _ => format!("section `{}`", section.op.symbol),
+            },
+            ExprKind::Parenthesed(_) => "parentheses".to_owned(),
+        }
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<Ident<B>> for Ident<A> {
+    fn diff_at(&self, other: &Ident<B>, path: &mut Path) -> Result<(), Diff> {
+        path.eq(format!("`{}`", self.symbol), format!("`{}`", other.symbol))
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<Op<B>> for Op<A> {
+    fn diff_at(&self, other: &Op<B>, path: &mut Path) -> Result<(), Diff> {
+        path.eq(format!("`{}`", self.symbol), format!("`{}`", other.symbol))
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<Option<Type<B>>> for Option<Type<A>> {
+    fn diff_at(&self, other: &Option<Type<B>>, path: &mut Path) -> Result<(), Diff> {
+        match (self, other) {
+            (None, None) => Ok(()),
+            (Some(l), Some(r)) => l.diff_at(r, path),
+            _ => Err(path.diff(self.describe(), other.describe())),
+        }
+    }
+}
+
+impl<P: Pointer> Describe for Option<Type<P>> {
+    fn describe(&self) -> String {
+        match self {
+            Some(ty) => ty.describe(),
+            None => "no type".to_owned(),
+        }
+    }
+}
+
+impl<A: Pointer, B: Pointer> StructEq<Type<B>> for Type<A> {
+    fn diff_at(&self, other: &Type<B>, path: &mut Path) -> Result<(), Diff> {
+        match (&self.kind, &other.kind) {
+            (TypeKind::Literal(LitType::Int), TypeKind::Literal(LitType::Int))
+            | (TypeKind::Literal(LitType::Float), TypeKind::Literal(LitType::Float))
+            | (TypeKind::Literal(LitType::Void), TypeKind::Literal(LitType::Void))
+            | (TypeKind::Literal(LitType::Str), TypeKind::Literal(LitType::Str))
+            | (TypeKind::Literal(LitType::Char), TypeKind::Literal(LitType::Char)) => Ok(()),
+            (TypeKind::Function(l), TypeKind::Function(r)) => {
+                path.list("args", &l.args, &r.args)?;
+                path.field("ret", l.ret.as_ref(), r.ret.as_ref())
+            }
+            _ => Err(path.diff(self.describe(), other.describe())),
+        }
+    }
+}
+
+impl<P: Pointer> Describe for Type<P> {
+    fn describe(&self) -> String {
+        fn render<P: Pointer>(ty: &Type<P>) -> String {
+            match ty.kind {
+                TypeKind::Literal(LitType::Int) => "int".to_owned(),
+                TypeKind::Literal(LitType::Float) => "float".to_owned(),
+                TypeKind::Literal(LitType::Void) => "void".to_owned(),
+                TypeKind::Literal(LitType::Str) => "str".to_owned(),
+                TypeKind::Literal(LitType::Char) => "char".to_owned(),
+                TypeKind::Function(ref func_t) => format!(
+                    "({}) {}",
+                    func_t.args.iter().map(render).collect::<Vec<_>>().join(" "),
+                    render(&func_t.ret)
+                ),
+            }
+        }
+        format!("type `{}`", render(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_source::string;
+
+    use crate::test_utils::parse;
+
+    fn diff_str(left: &str, right: &str) -> Option<String> {
+        diff(&parse(left), &parse(right)).map(|d| d.to_string())
+    }
+
+    #[test]
+    fn ignoring_ids_and_spans() {
+        let src = "decl f int [a]: int\ninfix 5 + a b: a\ndef f x: x + 1 `g 2";
+        let mut shifted = parse(&format!("def h: 1\n\n{}", src));
+        shifted.root.decl.remove(0);
+        assert!(struct_eq(&parse(src), &shifted));
+    }
+
+    #[test]
+    fn comparing_parentheses() {
+        let (grouped, flat) = (
+            parse("def f a b c: (a + b) * c"),
+            parse("def f a b c: a + b * c"),
+        );
+        assert!(!struct_eq(&grouped, &flat));
+        assert!(!struct_eq(&parse("def f: ((1))"), &parse("def f: 1")));
+        assert!(struct_eq(&parse("def f: ((1))"), &parse("def f:((1) )")));
+        assert_eq!(
+            diff_str("def f: (1 + x)", "def f: (1 + y)"),
+            Some("decl[0].body.expr.rhs: identifier `x` != identifier `y`".to_owned())
+        );
+        assert_eq!(
+            diff_str("def f: (1)", "def f: 1"),
+            Some("decl[0].body: parentheses != literal `1`".to_owned())
+        );
+        assert!(struct_eq_ignoring_parens(
+            &parse("def f: ((1))"),
+            &parse("def f: 1")
+        ));
+    }
+
+    #[test]
+    fn reporting_first_difference() {
+        assert_eq!(diff_str("def f: 1 + 2", "def f: 1 + 2"), None);
+        assert_eq!(
+            diff_str("def f: 1 + 2", "def f: 1 - 2"),
+            Some("decl[0].body: operator call `+` != operator call `-`".to_owned())
+        );
+        assert_eq!(
+            diff_str("def f: (1 + x) + 2", "def f: (1 + y) + 3"),
+            Some("decl[0].body.lhs.expr.rhs: identifier `x` != identifier `y`".to_owned())
+        );
+        assert_eq!(
+            diff_str("infix 5 + a b: a", "infix 6 + a b: a"),
+            Some("decl[0].precedence: 5 != 6".to_owned())
+        );
+        assert_eq!(
+            diff_str("decl f int [a]: int", "decl f int [a b]: (int) void"),
+            Some("decl[0].ty.ret: type `int` != type `(int) void`".to_owned())
+        );
+        assert_eq!(
+            diff_str("decl f int [a]: int", "decl f int [a b]: int"),
+            Some("decl[0].attrs[1]: nothing != attribute `b`".to_owned())
+        );
+        assert_eq!(
+            diff_str("def f: 1", ""),
+            Some("decl[0]: function definition `f` != nothing".to_owned())
+        );
+    }
+
+    #[test]
+    fn comparing_nodes() {
+        let (left, right) = (parse("def f: @g 1"), parse("def f: @g 2"));
+        let body = |ast: &AST<string::String>| match ast.root.decl[0].kind {
+            TopLevelDeclKind::FunctionDef(ref def) => def.body.clone(),
+            _ => unreachable!(),
+        };
+        let d = diff(&body(&left), &body(&right)).unwrap();
+        assert_eq!(d.path, "args[0]");
+        assert_eq!(d.to_string(), "args[0]: literal `1` != literal `2`");
+        assert_eq!(
+            diff(&left.root.decl[0], &parse("decl f: int").root.decl[0])
+                .unwrap()
+                .to_string(),
+            "root: function definition `f` != function declaration `f`"
+        );
+    }
+}
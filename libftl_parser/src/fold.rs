@@ -0,0 +1,541 @@
+//! Owning traversal of the syntax tree.
+//!
+//! Unlike [`Pass`](../visitor/trait.Pass.html) and
+//! [`MutPass`](../visitor_mut/trait.MutPass.html) the
+//! [`Fold`](trait.Fold.html) takes every node by value and returns
+//! the node which should take its place, so a node can be replaced
+//! with one of a different kind, or with one of its own children,
+//! without swapping boxed expressions in place.
+//!
+//! Default implementation of every `fold_*` method calls the
+//! corresponding `noop_fold_*` function, which folds all the
+//! children of the node and rebuilds it with them.
+
+use crate::ast::*;
+use ftl_session::symbol::Symbol;
+use ftl_source::{Pointer, Source};
+
+/// # Example
+///
+/// Folder removing the parentheses.
+///
+/// ```
+/// use ftl_parser::ast::*;
+/// use ftl_parser::fold::{noop_fold_expr, Fold};
+/// use ftl_source::Pointer;
+///
+/// struct ParenRemoval;
+///
+/// impl<P: Pointer> Fold<P> for ParenRemoval {
+///     fn fold_expr(&mut self, node: Expr<P>) -> Expr<P> {
+///         match node.kind {
+///             ExprKind::Parenthesed(paren) => self.fold_expr(*paren.expr),
+///             kind => noop_fold_expr(self, Expr { kind, ..node }),
+///         }
+///     }
+/// }
+/// ```
+pub trait Fold<P: Pointer>: Sized {
+    fn fold_module(&mut self, node: Module<P>) -> Module<P> {
+        noop_fold_module(self, node)
+    }
+
+    fn fold_top_level_decl(&mut self, node: TopLevelDecl<P>) -> TopLevelDecl<P> {
+        noop_fold_top_level_decl(self, node)
+    }
+
+    fn fold_func_decl(&mut self, node: FuncDecl<P>) -> FuncDecl<P> {
+        noop_fold_func_decl(self, node)
+    }
+
+    fn fold_func_def(&mut self, node: FuncDef<P>) -> FuncDef<P> {
+        noop_fold_func_def(self, node)
+    }
+
+    fn fold_infix_def(&mut self, node: InfixDef<P>) -> InfixDef<P> {
+        noop_fold_infix_def(self, node)
+    }
+
+    fn fold_prefix_def(&mut self, node: PrefixDef<P>) -> PrefixDef<P> {
+        noop_fold_prefix_def(self, node)
+    }
+
+    fn fold_func_arg(&mut self, node: FuncArg<P>) -> FuncArg<P> {
+        noop_fold_func_arg(self, node)
+    }
+
+    fn fold_func_attr(&mut self, node: FuncAttr<P>) -> FuncAttr<P> {
+        noop_fold_func_attr(self, node)
+    }
+
+    fn fold_expr(&mut self, node: Expr<P>) -> Expr<P> {
+        noop_fold_expr(self, node)
+    }
+
+    fn fold_infix_func_call(&mut self, node: InfixFuncCall<P>) -> InfixFuncCall<P> {
+        noop_fold_infix_func_call(self, node)
+    }
+
+    fn fold_infix_op_call(&mut self, node: InfixOpCall<P>) -> InfixOpCall<P> {
+        noop_fold_infix_op_call(self, node)
+    }
+
+    fn fold_prefix_op_call(&mut self, node: PrefixOpCall<P>) -> PrefixOpCall<P> {
+        noop_fold_prefix_op_call(self, node)
+    }
+
+    fn fold_section(&mut self, node: Section<P>) -> Section<P> {
+        noop_fold_section(self, node)
+    }
+
+    fn fold_func_call(&mut self, node: FuncCall<P>) -> FuncCall<P> {
+        noop_fold_func_call(self, node)
+    }
+
+    fn fold_parenthesed(&mut self, node: Paren<P>) -> Paren<P> {
+        noop_fold_paren(self, node)
+    }
+
+    fn fold_lit(&mut self, node: Lit<P>) -> Lit<P> {
+        noop_fold_lit(self, node)
+    }
+
+    fn fold_int_lit(&mut self, val: i64) -> i64 {
+        val
+    }
+
+    fn fold_float_lit(&mut self, val: f64) -> f64 {
+        val
+    }
+
+    fn fold_str_lit(&mut self, val: Symbol) -> Symbol {
+        val
+    }
+
+    fn fold_char_lit(&mut self, val: char) -> char {
+        val
+    }
+
+    fn fold_ident(&mut self, node: Ident<P>) -> Ident<P> {
+        node
+    }
+
+    fn fold_op(&mut self, node: Op<P>) -> Op<P> {
+        node
+    }
+
+    fn fold_type(&mut self, node: Type<P>) -> Type<P> {
+        noop_fold_type(self, node)
+    }
+
+    fn fold_func_type(&mut self, node: FuncType<P>) -> FuncType<P> {
+        noop_fold_func_type(self, node)
+    }
+
+    fn fold_lit_type(&mut self, node: LitType) -> LitType {
+        node
+    }
+}
+
+pub fn fold_ast<S: Source, F: Fold<S::Pointer>>(f: &mut F, ast: AST<S>) -> AST<S> {
+    AST::new(f.fold_module(ast.root))
+}
+
+pub fn noop_fold_module<Ptr: Pointer, F: Fold<Ptr>>(f: &mut F, node: Module<Ptr>) -> Module<Ptr> {
+    Module {
+        id: node.id,
+        decl: node
+            .decl
+            .into_iter()
+            .map(|decl| f.fold_top_level_decl(decl))
+            .collect(),
+    }
+}
+
+pub fn noop_fold_top_level_decl<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: TopLevelDecl<Ptr>,
+) -> TopLevelDecl<Ptr> {
+    use TopLevelDeclKind::*;
+    TopLevelDecl {
+        id: node.id,
+        kind: match node.kind {
+            FunctionDef(func_def) => FunctionDef(f.fold_func_def(func_def)),
+            FunctionDecl(func_decl) => FunctionDecl(f.fold_func_decl(func_decl)),
+            InfixDef(infix_def) => InfixDef(f.fold_infix_def(infix_def)),
+            PrefixDef(prefix_def) => PrefixDef(f.fold_prefix_def(prefix_def)),
+        },
+        span: node.span,
+    }
+}
+
+pub fn noop_fold_func_decl<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: FuncDecl<Ptr>,
+) -> FuncDecl<Ptr> {
+    FuncDecl {
+        id: node.id,
+        ident: f.fold_ident(node.ident),
+        ty: node.ty.map(|ty| f.fold_type(ty)),
+        attrs: node
+            .attrs
+            .into_iter()
+            .map(|attr| f.fold_func_attr(attr))
+            .collect(),
+        doc: node.doc,
+    }
+}
+
+pub fn noop_fold_func_def<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: FuncDef<Ptr>,
+) -> FuncDef<Ptr> {
+    FuncDef {
+        id: node.id,
+        decl: f.fold_func_decl(node.decl),
+        args: node
+            .args
+            .into_iter()
+            .map(|arg| f.fold_func_arg(arg))
+            .collect(),
+        body: f.fold_expr(node.body),
+    }
+}
+
+pub fn noop_fold_infix_def<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: InfixDef<Ptr>,
+) -> InfixDef<Ptr> {
+    InfixDef {
+        id: node.id,
+        ty: node.ty.map(|ty| f.fold_type(ty)),
+        precedence: node.precedence,
+        op: f.fold_op(node.op),
+        args: (f.fold_func_arg(node.args.0), f.fold_func_arg(node.args.1)),
+        body: f.fold_expr(node.body),
+        doc: node.doc,
+    }
+}
+
+pub fn noop_fold_prefix_def<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: PrefixDef<Ptr>,
+) -> PrefixDef<Ptr> {
+    PrefixDef {
+        id: node.id,
+        ty: node.ty.map(|ty| f.fold_type(ty)),
+        precedence: node.precedence,
+        op: f.fold_op(node.op),
+        arg: f.fold_func_arg(node.arg),
+        body: f.fold_expr(node.body),
+        doc: node.doc,
+    }
+}
+
+pub fn noop_fold_func_arg<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: FuncArg<Ptr>,
+) -> FuncArg<Ptr> {
+    FuncArg {
+        id: node.id,
+        ident: f.fold_ident(node.ident),
+        ty: node.ty.map(|ty| f.fold_type(ty)),
+        span: node.span,
+    }
+}
+
+pub fn noop_fold_func_attr<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: FuncAttr<Ptr>,
+) -> FuncAttr<Ptr> {
+    FuncAttr {
+        id: node.id,
+        ident: f.fold_ident(node.ident),
+        args: node
+            .args
+            .into_iter()
+            .map(|arg| match arg {
+                AttrArg::Lit(lit) => AttrArg::Lit(f.fold_lit(lit)),
+                AttrArg::Ident(ident) => AttrArg::Ident(f.fold_ident(ident)),
+            })
+            .collect(),
+        span: node.span,
+    }
+}
+
+pub fn noop_fold_expr<Ptr: Pointer, F: Fold<Ptr>>(f: &mut F, node: Expr<Ptr>) -> Expr<Ptr> {
+    use ExprKind::*;
+    Expr {
+        id: node.id,
+        kind: match node.kind {
+            FunctionCall(call) => FunctionCall(f.fold_func_call(call)),
+            Literal(lit) => Literal(f.fold_lit(lit)),
+            Identifier(ident) => Identifier(f.fold_ident(ident)),
+            InfixFuncCall(call) => InfixFuncCall(f.fold_infix_func_call(call)),
+            InfixOpCall(call) => InfixOpCall(f.fold_infix_op_call(call)),
+            PrefixOpCall(call) => PrefixOpCall(f.fold_prefix_op_call(call)),
+            Section(section) => Section(f.fold_section(section)),
+            Parenthesed(paren) => Parenthesed(f.fold_parenthesed(paren)),
+        },
+        span: node.span,
+    }
+}
+
+pub fn noop_fold_func_call<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: FuncCall<Ptr>,
+) -> FuncCall<Ptr> {
+    FuncCall {
+        id: node.id,
+        lhs: Box::new(f.fold_expr(*node.lhs)),
+        args: node.args.into_iter().map(|arg| f.fold_expr(arg)).collect(),
+    }
+}
+
+pub fn noop_fold_infix_func_call<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: InfixFuncCall<Ptr>,
+) -> InfixFuncCall<Ptr> {
+    InfixFuncCall {
+        id: node.id,
+        ident: f.fold_ident(node.ident),
+        lhs: Box::new(f.fold_expr(*node.lhs)),
+        rhs: Box::new(f.fold_expr(*node.rhs)),
+    }
+}
+
+pub fn noop_fold_infix_op_call<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: InfixOpCall<Ptr>,
+) -> InfixOpCall<Ptr> {
+    InfixOpCall {
+        id: node.id,
+        op: f.fold_op(node.op),
+        lhs: Box::new(f.fold_expr(*node.lhs)),
+        rhs: Box::new(f.fold_expr(*node.rhs)),
+    }
+}
+
+pub fn noop_fold_prefix_op_call<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: PrefixOpCall<Ptr>,
+) -> PrefixOpCall<Ptr> {
+    PrefixOpCall {
+        id: node.id,
+        op: f.fold_op(node.op),
+        expr: Box::new(f.fold_expr(*node.expr)),
+    }
+}
+
+pub fn noop_fold_section<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: Section<Ptr>,
+) -> Section<Ptr> {
+    Section {
+        id: node.id,
+        lhs: node.lhs.map(|lhs| Box::new(f.fold_expr(*lhs))),
+        op: f.fold_op(node.op),
+        rhs: node.rhs.map(|rhs| Box::new(f.fold_expr(*rhs))),
+    }
+}
+
+pub fn noop_fold_paren<Ptr: Pointer, F: Fold<Ptr>>(f: &mut F, node: Paren<Ptr>) -> Paren<Ptr> {
+    Paren {
+        id: node.id,
+        expr: Box::new(f.fold_expr(*node.expr)),
+    }
+}
+
+pub fn noop_fold_lit<Ptr: Pointer, F: Fold<Ptr>>(f: &mut F, node: Lit<Ptr>) -> Lit<Ptr> {
+    Lit {
+        id: node.id,
+        kind: match node.kind {
+            LitKind::Int(val) => LitKind::Int(f.fold_int_lit(val)),
+            LitKind::Float(val) => LitKind::Float(f.fold_float_lit(val)),
+            LitKind::Str(val) => LitKind::Str(f.fold_str_lit(val)),
+            LitKind::Char(val) => LitKind::Char(f.fold_char_lit(val)),
+        },
+        span: node.span,
+    }
+}
+
+pub fn noop_fold_type<Ptr: Pointer, F: Fold<Ptr>>(f: &mut F, node: Type<Ptr>) -> Type<Ptr> {
+    use TypeKind::*;
+    Type {
+        id: node.id,
+        kind: match node.kind {
+            Function(func_t) => Function(f.fold_func_type(func_t)),
+            Literal(lit_t) => Literal(f.fold_lit_type(lit_t)),
+        },
+        span: node.span,
+    }
+}
+
+pub fn noop_fold_func_type<Ptr: Pointer, F: Fold<Ptr>>(
+    f: &mut F,
+    node: FuncType<Ptr>,
+) -> FuncType<Ptr> {
+    FuncType {
+        id: node.id,
+        args: node
+            .args
+            .into_iter()
+            .map(|arg_t| f.fold_type(arg_t))
+            .collect(),
+        ret: Box::new(f.fold_type(*node.ret)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_session::symbol::Symbol;
+
+    use crate::test_utils::parse;
+    use crate::visitor::{self, Pass};
+
+    /// Describes the expressions in the visited tree.
+    #[derive(Default)]
+    struct Exprs(Vec<String>);
+
+    impl<P: Pointer> Pass<'_, P> for Exprs {
+        fn visit_expr(&mut self, node: &Expr<P>) {
+            self.0.push(match node.kind {
+                ExprKind::FunctionCall(ref call) => format!("call/{}", call.args.len()),
+                ExprKind::Literal(_) => "lit".to_owned(),
+                ExprKind::Identifier(ref ident) => ident.symbol.to_string(),
+                ExprKind::InfixFuncCall(ref call) => format!("`{}", call.ident.symbol),
+                ExprKind::InfixOpCall(ref call) => call.op.symbol.to_string(),
+                ExprKind::PrefixOpCall(ref call) => format!("{}_", call.op.symbol),
+                ExprKind::Section(ref section) => format!("({})", section.op.symbol),
+                ExprKind::Parenthesed(_) => "()".to_owned(),
+            });
+            visitor::walk_expr(self, node);
+        }
+    }
+
+    fn exprs<S: Source>(ast: &AST<S>) -> Vec<String> {
+        let mut exprs = Exprs::default();
+        visitor::visit_ast(&mut exprs, ast);
+        exprs.0
+    }
+
+    struct Identity;
+
+    impl<P: Pointer> Fold<P> for Identity {}
+
+    struct ParenRemoval;
+
+    impl<P: Pointer> Fold<P> for ParenRemoval {
+        fn fold_expr(&mut self, node: Expr<P>) -> Expr<P> {
+            match node.kind {
+                ExprKind::Parenthesed(paren) => self.fold_expr(*paren.expr),
+                kind => noop_fold_expr(self, Expr { kind, ..node }),
+            }
+        }
+    }
+
+    /// Replaces infix operator calls with the calls
+    /// of the functions named after the operators.
+    struct InfixToCall;
+
+    impl<P: Pointer> Fold<P> for InfixToCall {
+        fn fold_expr(&mut self, node: Expr<P>) -> Expr<P> {
+            match node.kind {
+                ExprKind::InfixOpCall(call) => Expr {
+                    kind: ExprKind::FunctionCall(FuncCall {
+                        id: call.id,
+                        lhs: Box::new(Expr {
+                            id: call.op.id,
+                            span: call.op.span.clone(),
+                            kind: ExprKind::Identifier(Ident {
+                                id: call.op.id,
+                                symbol: call.op.symbol,
+                                span: call.op.span,
+                            }),
+                        }),
+                        args: vec![self.fold_expr(*call.lhs), self.fold_expr(*call.rhs)],
+                    }),
+                    ..node
+                },
+                kind => noop_fold_expr(self, Expr { kind, ..node }),
+            }
+        }
+    }
+
+    const SOURCE: &str = r#"
+decl add int int [lang_add] : int
+infix 5 + a b: @add a b
+def foo a b: (a + (b)) `foo ((2 + 3) + a)
+"#;
+
+    #[test]
+    fn identity_fold_keeps_the_tree() {
+        let ast = parse(SOURCE);
+        let before = exprs(&ast);
+        let ast = fold_ast(&mut Identity, ast);
+        assert_eq!(exprs(&ast), before);
+    }
+
+    #[test]
+    fn removing_parentheses() {
+        let ast = fold_ast(&mut ParenRemoval, parse(SOURCE));
+        assert_eq!(
+            exprs(&ast),
+            vec!["call/2", "add", "a", "b", "`foo", "+", "a", "b", "+", "+", "lit", "lit", "a"]
+        );
+    }
+
+    #[test]
+    fn desugaring_infix_calls() {
+        let ast = fold_ast(&mut InfixToCall, fold_ast(&mut ParenRemoval, parse(SOURCE)));
+        assert_eq!(
+            exprs(&ast),
+            vec![
+                "call/2", "add", "a", "b", "`foo", "call/2", "+", "a", "b", "call/2", "+",
+                "call/2", "+", "lit", "lit", "a"
+            ]
+        );
+    }
+
+    #[test]
+    fn folding_leaves() {
+        struct Rename;
+
+        impl<P: Pointer> Fold<P> for Rename {
+            fn fold_ident(&mut self, node: Ident<P>) -> Ident<P> {
+                Ident {
+                    symbol: Symbol::new(&node.symbol.to_uppercase()),
+                    ..node
+                }
+            }
+
+            fn fold_int_lit(&mut self, val: i64) -> i64 {
+                val * 10
+            }
+        }
+
+        let ast = fold_ast(&mut Rename, parse("def foo a [inline]: a `foo 4"));
+        let def = match ast.root.decl[0].kind {
+            TopLevelDeclKind::FunctionDef(ref def) => def,
+            _ => unreachable!(),
+        };
+        assert_eq!(def.decl.ident.symbol, "FOO");
+        assert_eq!(def.decl.attrs[0].ident.symbol, "INLINE");
+        assert_eq!(def.args[0].ident.symbol, "A");
+        match def.body.kind {
+            ExprKind::InfixFuncCall(ref call) => {
+                assert_eq!(call.ident.symbol, "FOO");
+                match call.rhs.kind {
+                    ExprKind::Literal(Lit {
+                        kind: LitKind::Int(val),
+                        ..
+                    }) => assert_eq!(val, 40),
+                    _ => panic!("expected literal"),
+                }
+            }
+            _ => panic!("expected infix call"),
+        }
+    }
+}
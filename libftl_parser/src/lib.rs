@@ -1,13 +1,19 @@
-use log::debug;
-
 use ftl_error::LangError;
 use ftl_lexer::{token, Lexer};
+use ftl_session::symbol::Symbol;
 use ftl_session::Session;
 use ftl_source::{Pointer, Source, Span};
 use ftl_utility::RcRef;
 
 pub mod ast;
+pub mod cst;
+pub mod diff;
 pub mod errors;
+pub mod fold;
+#[cfg(feature = "serde")]
+pub mod serialize;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 pub mod utility;
 pub mod visitor;
 pub mod visitor_mut;
@@ -23,8 +29,9 @@ pub struct Parser<S: Source> {
     sess: RcRef<Session<S>>,
     lexer: Lexer<S>,
 
-    node_id: ast::NodeId,
-    saved_ptrs: Vec<S::Pointer>,
+    node_ids: ast::NodeIds,
+    /// End of the last consumed token.
+    prev_end: Option<S::Pointer>,
 }
 
 impl<S, P> Parser<S>
@@ -38,8 +45,8 @@ where
         Self {
             lexer,
             sess,
-            node_id: 0,
-            saved_ptrs: Vec::new(),
+            node_ids: ast::NodeIds::new(),
+            prev_end: None,
         }
     }
 
@@ -50,6 +57,29 @@ where
         }
     }
 
+    /// Returns concrete syntax tree of the source
+    /// the `ast` has been parsed from.
+    /// Panics if the lexer isn't lossless.
+    pub fn concrete_tree(&mut self, ast: &ast::AST<S>) -> cst::Cst<P> {
+        if !self.lexer.is_lossless() {
+            panic!("Concrete syntax tree can only be built with the lossless lexer.")
+        }
+        while self.lexer.advance().is_some() {}
+        let src = self.sess.borrow().src.clone();
+        let leaves = self
+            .lexer
+            .take_tokens()
+            .into_iter()
+            .map(|token| cst::Leaf {
+                text: src
+                    .borrow()
+                    .source_between(&token.span.beg, &token.span.end),
+                token,
+            })
+            .collect();
+        cst::build(&ast.root, leaves, self.lexer.trailing_trivia().to_vec())
+    }
+
     // Parsing methods
 
     fn parse_module(&mut self) -> PRes<ast::Module<P>, P> {
@@ -71,7 +101,7 @@ where
     }
 
     fn parse_eof(&mut self) -> PRes<(), P> {
-        match self.lexer.next() {
+        match self.lexer.advance() {
             None => Ok(()),
             Some(tok) => Err(ParseErr::NotThisItem(tok)),
         }
@@ -80,42 +110,71 @@ where
     // Top level decl
 
     fn parse_top_level_decl(&mut self) -> PRes<ast::TopLevelDecl<P>, P> {
-        self.push_ptr();
-        let kind = if let Ok(func_decl) = self.parse_func_decl() {
+        let beg = self.beg_ptr();
+        let doc = self.doc_comment();
+        let mut kind = if let Ok(func_decl) = self.attempt(Self::parse_func_decl) {
             ast::TopLevelDeclKind::FunctionDecl(func_decl)
-        } else if let Ok(func_def) = self.parse_func_def() {
+        } else if let Ok(func_def) = self.attempt(Self::parse_func_def) {
             ast::TopLevelDeclKind::FunctionDef(func_def)
-        } else if let Ok(infix_def) = self.parse_infix_decl() {
+        } else if let Ok(infix_def) = self.attempt(Self::parse_infix_decl) {
             ast::TopLevelDeclKind::InfixDef(infix_def)
+        } else if let Ok(prefix_def) = self.attempt(Self::parse_prefix_decl) {
+            ast::TopLevelDeclKind::PrefixDef(prefix_def)
         } else {
             return Err(match self.lexer.curr() {
                 Some(tok) => ParseErr::NotThisItem(tok),
                 None => ParseErr::EOF,
             });
         };
+        match kind {
+            ast::TopLevelDeclKind::FunctionDecl(ref mut decl) => decl.doc = doc,
+            ast::TopLevelDeclKind::FunctionDef(ref mut def) => def.decl.doc = doc,
+            ast::TopLevelDeclKind::InfixDef(ref mut def) => def.doc = doc,
+            ast::TopLevelDeclKind::PrefixDef(ref mut def) => def.doc = doc,
+        }
         Ok(ast::TopLevelDecl {
             id: self.next_node_id(),
             kind,
             span: Span {
-                beg: self.pop_ptr(),
-                end: self.curr_ptr(),
+                beg,
+                end: self.prev_ptr(),
             },
         })
     }
 
+    /// Returns the text of the doc comments preceding the current token,
+    /// one line for each comment, without the leading `##`
+    /// and the space following it.
+    fn doc_comment(&mut self) -> Option<String> {
+        let tok = self.lexer.curr()?;
+        let lines: Vec<&str> = tok
+            .trivia
+            .iter()
+            .filter(|trivia| trivia.kind == token::Kind::DocComment)
+            .map(|trivia| {
+                let text = trivia.text["##".len()..].trim_end();
+                text.strip_prefix(' ').unwrap_or(text)
+            })
+            .collect();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+
     // Function
 
     fn parse_infix_decl(&mut self) -> PRes<ast::InfixDef<P>, P> {
-        self.parse_token(token::Kind::InfixDef)?;
+        let beg = self.parse_token(token::Kind::InfixDef)?.span.end;
         let precedence = Comb(self)
             .r#try(Self::parse_int_lit)
-            .map(pres_lift_fn(
-                |ast::Lit {
-                     kind: ast::LitKind::Int(val),
-                     ..
-                 }| val as usize,
-            ))
+            .map(pres_lift_fn(|lit: ast::Lit<P>| match lit.kind {
+                ast::LitKind::Int(val) => val as usize,
+                _ => unreachable!(),
+            }))
             .fail_unex_tok(
+                beg.clone(),
                 token::Kind::IntLiteral,
                 token::Value::None,
                 "Infix declaration needs to have its precendence.".to_owned(),
@@ -124,20 +183,22 @@ where
         let op = Comb(self)
             .r#try(Self::parse_op)
             .fail_unex_tok(
+                beg.clone(),
                 token::Kind::Operator,
                 token::Value::None,
                 "An infix needs an operator as its name.".to_owned(),
             )
             .run();
-        let (arg_1, arg_2) = self.parse_infix_decl_args();
+        let (arg_1, arg_2) = self.parse_infix_decl_args(beg.clone());
         self.try_parse_token_rec(
+            beg.clone(),
             token::Kind::Colon,
             "Colon expected".to_owned(),
-            token::Value::String(";".to_owned()),
+            token::Value::Symbol(Symbol::new(";")),
         );
         let body = Comb(self)
             .r#try(Self::parse_expr)
-            .fail_msg("Infix needs a body definition".to_owned())
+            .fail_msg(beg.clone(), "Infix needs a body definition".to_owned())
             .run();
         Ok(ast::InfixDef {
             id: self.next_node_id(),
@@ -146,31 +207,75 @@ where
             body,
             args: (arg_1, arg_2),
             precedence,
+            doc: None,
         })
     }
 
-    fn parse_infix_decl_args(&mut self) -> (ast::FuncArg<P>, ast::FuncArg<P>) {
+    fn parse_prefix_decl(&mut self) -> PRes<ast::PrefixDef<P>, P> {
+        let beg = self.parse_token(token::Kind::PrefixDef)?.span.end;
+        let precedence = self.parse_int_lit().ok().map(|lit| match lit.kind {
+            ast::LitKind::Int(val) => val as usize,
+            _ => unreachable!(),
+        });
+        let op = Comb(self)
+            .r#try(Self::parse_op)
+            .fail_unex_tok(
+                beg.clone(),
+                token::Kind::Operator,
+                token::Value::None,
+                "A prefix needs an operator as its name.".to_owned(),
+            )
+            .run();
+        let arg = Comb(self)
+            .r#try(Self::parse_func_arg)
+            .fail_msg(beg.clone(), "Prefix needs 1 argument".to_owned())
+            .run();
+        self.try_parse_token_rec(
+            beg.clone(),
+            token::Kind::Colon,
+            "Colon expected".to_owned(),
+            token::Value::Symbol(Symbol::new(";")),
+        );
+        let body = Comb(self)
+            .r#try(Self::parse_expr)
+            .fail_msg(beg.clone(), "Prefix needs a body definition".to_owned())
+            .run();
+        Ok(ast::PrefixDef {
+            id: self.next_node_id(),
+            ty: None,
+            op,
+            body,
+            arg,
+            precedence,
+            doc: None,
+        })
+    }
+
+    fn parse_infix_decl_args(&mut self, beg: P) -> (ast::FuncArg<P>, ast::FuncArg<P>) {
         let arg_1 = Comb(self)
             .r#try(Self::parse_func_arg)
-            .fail_msg("Infix needs 2 arguments".to_owned())
+            .fail_msg(beg.clone(), "Infix needs 2 arguments".to_owned())
             .run();
         let arg_2 = Comb(self)
             .r#try(Self::parse_func_arg)
-            .fail_msg("Infix needs 2 arguments".to_owned())
+            .fail_msg(beg, "Infix needs 2 arguments".to_owned())
             .run();
         (arg_1, arg_2)
     }
 
     fn parse_func_decl(&mut self) -> PRes<ast::FuncDecl<P>, P> {
-        self.parse_token(token::Kind::FuncDecl)?;
-        let ident =
-            self.try_parse_ident_fail("A function needs an identifier as its name.".to_owned());
+        let beg = self.parse_token(token::Kind::FuncDecl)?.span.end;
+        let ident = self.try_parse_ident_fail(
+            beg.clone(),
+            "A function needs an identifier as its name.".to_owned(),
+        );
         let args_t = self.parse_func_args_types().unwrap_or_default();
         let attrs = self.parse_func_attrs().unwrap_or_default();
         self.try_parse_token_rec(
+            beg.clone(),
             token::Kind::Colon,
             "Colon expected".to_owned(),
-            token::Value::String(")".to_owned()),
+            token::Value::Symbol(Symbol::new(")")),
         );
         let ret_t = self.parse_type()?;
         Ok(ast::FuncDecl {
@@ -193,12 +298,13 @@ where
             }),
             attrs,
             ident,
+            doc: None,
         })
     }
 
     fn parse_func_args_types(&mut self) -> PRes<Vec<ast::Type<P>>, P> {
         let mut args = Vec::new();
-        let beg = self.peek_ptr().clone();
+        let beg = self.beg_ptr();
         while let Ok(t) = self.parse_type() {
             if let ast::TypeKind::Literal(ast::LitType::Void) = t.kind {
                 self.err(Self::msg_err(
@@ -237,21 +343,22 @@ where
     }
 
     fn parse_func_type(&mut self) -> PRes<ast::Type<P>, P> {
-        let beg = self.curr_ptr();
+        let beg = self.beg_ptr();
         self.parse_token(token::Kind::LeftParenthesis)?;
         let mut args = Vec::new();
         while let Ok(t) = self.parse_type() {
             args.push(t);
         }
         self.try_parse_token_rec(
+            beg.clone(),
             token::Kind::RightParenthesis,
             "Unclosed parenthesis for function type".to_owned(),
-            token::Value::String(")".to_owned()),
+            token::Value::Symbol(Symbol::new(")")),
         );
         let ret = Comb(self)
             .r#try(Self::parse_type)
             .map(pres_lift_fn(Box::new))
-            .fail_msg("Missing function type return type".to_owned())
+            .fail_msg(beg.clone(), "Missing function type return type".to_owned())
             .run();
         Ok(ast::Type {
             id: self.next_node_id(),
@@ -262,42 +369,83 @@ where
             }),
             span: Span {
                 beg,
-                end: self.curr_ptr(),
+                end: self.prev_ptr(),
             },
         })
     }
 
     fn parse_func_attrs(&mut self) -> PRes<Vec<ast::FuncAttr<P>>, P> {
+        let beg = self.beg_ptr();
         self.parse_token(token::Kind::LeftBracket)?;
         let mut attrs = Vec::new();
-        while let Ok(attr) = self.parse_ident() {
-            attrs.push(ast::FuncAttr {
-                id: self.next_node_id(),
-                ident: attr,
-            });
+        while let Ok(attr) = self.parse_func_attr() {
+            attrs.push(attr);
         }
         self.try_parse_token_rec(
+            beg.clone(),
             token::Kind::RightBracket,
             "Unclosed attributes parenthesis".to_owned(),
-            token::Value::String("]".to_owned()),
+            token::Value::Symbol(Symbol::new("]")),
         );
         Ok(attrs)
     }
 
+    /// Parses the attribute with its arguments. Identifiers and literals
+    /// can be given in the parenthesis after the name, like `inline(always)`,
+    /// literals can also follow the name directly, like `lang "add"`.
+    fn parse_func_attr(&mut self) -> PRes<ast::FuncAttr<P>, P> {
+        let beg = self.beg_ptr();
+        let ident = self.parse_ident()?;
+        let mut args = Vec::new();
+        if self.parse_token(token::Kind::LeftParenthesis).is_ok() {
+            while let Ok(arg) = self.parse_attr_arg() {
+                args.push(arg);
+            }
+            self.try_parse_token_rec(
+                beg.clone(),
+                token::Kind::RightParenthesis,
+                "Unclosed attribute arguments parenthesis".to_owned(),
+                token::Value::Symbol(Symbol::new(")")),
+            );
+        }
+        while let Ok(lit) = self.parse_lit() {
+            args.push(ast::AttrArg::Lit(lit));
+        }
+        Ok(ast::FuncAttr {
+            id: self.next_node_id(),
+            ident,
+            args,
+            span: Span {
+                beg,
+                end: self.prev_ptr(),
+            },
+        })
+    }
+
+    fn parse_attr_arg(&mut self) -> PRes<ast::AttrArg<P>, P> {
+        match self.parse_ident() {
+            Ok(ident) => Ok(ast::AttrArg::Ident(ident)),
+            Err(_) => self.parse_lit().map(ast::AttrArg::Lit),
+        }
+    }
+
     fn parse_func_def(&mut self) -> PRes<ast::FuncDef<P>, P> {
-        self.parse_token(token::Kind::FuncDef)?;
-        let ident =
-            self.try_parse_ident_fail("A function needs an identifier as its name.".to_owned());
+        let beg = self.parse_token(token::Kind::FuncDef)?.span.end;
+        let ident = self.try_parse_ident_fail(
+            beg.clone(),
+            "A function needs an identifier as its name.".to_owned(),
+        );
         let args = self.parse_func_args();
         let attrs = self.parse_func_attrs().unwrap_or_default();
         self.try_parse_token_rec(
+            beg.clone(),
             token::Kind::Colon,
             "Colon expected".to_owned(),
-            token::Value::String(";".to_owned()),
+            token::Value::Symbol(Symbol::new(";")),
         );
         let body = Comb(self)
             .r#try(Self::parse_expr)
-            .fail_msg("Function needs a body definition".to_owned())
+            .fail_msg(beg.clone(), "Function needs a body definition".to_owned())
             .run();
         Ok(ast::FuncDef {
             id: self.next_node_id(),
@@ -306,6 +454,7 @@ where
                 ident,
                 attrs,
                 ty: None,
+                doc: None,
             },
             args,
             body,
@@ -340,10 +489,14 @@ where
     }
 
     fn parse_infix_expr(&mut self) -> PRes<ast::Expr<P>, P> {
-        let mut lhs = self.parse_func_call()?;
-        while let Ok(op) = self.one_of_tok(vec![token::Kind::InfixIdent, token::Kind::Operator]) {
-            let beg = self.curr_ptr();
-            let rhs = match self.parse_func_call() {
+        let mut lhs = self.parse_prefix_expr()?;
+        while !self.at_left_section_end() {
+            let op = match self.one_of_tok(vec![token::Kind::InfixIdent, token::Kind::Operator]) {
+                Ok(op) => op,
+                Err(_) => break,
+            };
+            let beg = self.beg_ptr();
+            let rhs = match self.parse_prefix_expr() {
                 Ok(expr) => expr,
                 _ => {
                     self.err(Self::msg_err(
@@ -355,14 +508,14 @@ where
                 }
             };
             let sym = match op.value {
-                token::Value::String(s) => s,
+                token::Value::Symbol(s) => s,
                 _ => unreachable!(),
             };
             lhs = ast::Expr {
                 id: self.next_node_id(),
                 span: Span {
                     beg: lhs.span.clone().beg,
-                    end: self.curr_ptr(),
+                    end: self.prev_ptr(),
                 },
                 kind: match op.kind {
                     token::Kind::InfixIdent => ast::ExprKind::InfixFuncCall(ast::InfixFuncCall {
@@ -392,14 +545,89 @@ where
         Ok(lhs)
     }
 
+    /// Operator standing where an operand is expected is a prefix
+    /// operator call. So the `-` in `a - b` is a binary minus,
+    /// while the second one in `a - -b` is an unary one.
+    fn parse_prefix_expr(&mut self) -> PRes<ast::Expr<P>, P> {
+        if let Ok(expr) = self.attempt(Self::parse_negative_lit_expr) {
+            return Ok(expr);
+        }
+        let beg = self.beg_ptr();
+        let op = match self.parse_op() {
+            Ok(op) => op,
+            Err(_) => return self.parse_func_call(),
+        };
+        let expr = Comb(self)
+            .r#try(Self::parse_prefix_expr)
+            .fail_msg(
+                beg.clone(),
+                "Expected expression after prefix operator".to_owned(),
+            )
+            .run();
+        Ok(ast::Expr {
+            id: self.next_node_id(),
+            span: Span {
+                beg,
+                end: self.prev_ptr(),
+            },
+            kind: ast::ExprKind::PrefixOpCall(ast::PrefixOpCall {
+                id: self.next_node_id(),
+                op,
+                expr: Box::new(expr),
+            }),
+        })
+    }
+
+    /// Parses the number written right after the `-` standing
+    /// where an operand is expected, like in `a - -1` or `(-1)`,
+    /// as the negative literal. Separated from the number,
+    /// like in `(- 1)`, the `-` stays the prefix operator.
+    fn parse_negative_lit_expr(&mut self) -> PRes<ast::Expr<P>, P> {
+        let minus = self.parse_token(token::Kind::Operator)?;
+        if !matches!(minus.value, token::Value::Symbol(ref s) if *s == "-") {
+            return Err(ParseErr::NotThisItem(minus));
+        }
+        let tok = match self.lexer.curr() {
+            Some(tok) if tok.span.beg.offset() == minus.span.end.offset() => tok,
+            Some(tok) => return Err(ParseErr::NotThisItem(tok)),
+            None => return Err(ParseErr::EOF),
+        };
+        let span = Span {
+            beg: minus.span.beg,
+            end: tok.span.end.clone(),
+        };
+        let kind = match tok.value {
+            token::Value::Integer(v) if tok.kind == token::Kind::IntLiteral => {
+                ast::LitKind::Int(self.int_value(v, true, &span))
+            }
+            token::Value::Float(v) if tok.kind == token::Kind::FloatLiteral => {
+                ast::LitKind::Float(-v)
+            }
+            _ => return Err(ParseErr::NotThisItem(tok)),
+        };
+        self.parse_token(tok.kind)?;
+        Ok(ast::Expr {
+            id: self.next_node_id(),
+            span: span.clone(),
+            kind: ast::ExprKind::Literal(ast::Lit {
+                id: self.next_node_id(),
+                kind,
+                span,
+            }),
+        })
+    }
+
     fn parse_func_call(&mut self) -> PRes<ast::Expr<P>, P> {
-        let beg = self.curr_ptr();
+        let beg = self.beg_ptr();
         if self.parse_token(token::Kind::At).is_err() {
             return self.parse_primary_expr();
         }
         let lhs = Comb(self)
             .r#try(Self::parse_primary_expr)
-            .fail_msg("Expected expression after call operator".to_owned())
+            .fail_msg(
+                beg.clone(),
+                "Expected expression after call operator".to_owned(),
+            )
             .run();
         let mut args = Vec::new();
         while let Ok(arg) = self.parse_primary_expr() {
@@ -409,7 +637,7 @@ where
             id: self.next_node_id(),
             span: Span {
                 beg,
-                end: self.curr_ptr(),
+                end: self.prev_ptr(),
             },
             kind: ast::ExprKind::FunctionCall(ast::FuncCall {
                 id: self.next_node_id(),
@@ -437,59 +665,101 @@ where
     }
 
     fn parse_ident_expr(&mut self) -> PRes<ast::Expr<P>, P> {
-        self.parse_ident().and_then(|ident: ast::Ident<P>| {
-            Ok(ast::Expr {
-                id: self.next_node_id(),
-                span: Span {
-                    beg: ident.span.clone().beg,
-                    end: ident.span.clone().end,
-                },
-                kind: ast::ExprKind::Identifier(ident),
-            })
+        self.parse_ident().map(|ident: ast::Ident<P>| ast::Expr {
+            id: self.next_node_id(),
+            span: Span {
+                beg: ident.span.clone().beg,
+                end: ident.span.clone().end,
+            },
+            kind: ast::ExprKind::Identifier(ident),
         })
     }
 
     fn parse_lit_expr(&mut self) -> PRes<ast::Expr<P>, P> {
-        self.parse_lit().and_then(|lit: ast::Lit<P>| {
-            Ok(ast::Expr {
-                id: self.next_node_id(),
-                span: Span {
-                    beg: lit.span.clone().beg,
-                    end: lit.span.clone().end,
-                },
-                kind: ast::ExprKind::Literal(lit),
-            })
+        self.parse_lit().map(|lit: ast::Lit<P>| ast::Expr {
+            id: self.next_node_id(),
+            span: Span {
+                beg: lit.span.clone().beg,
+                end: lit.span.clone().end,
+            },
+            kind: ast::ExprKind::Literal(lit),
         })
     }
 
+    /// Parses the parenthesed expression or the operator section.
+    ///
+    /// Right section like `(+ 1)` is parsed as the prefix call
+    /// in the parenthesis, as only the later passes know
+    /// if there is the prefix operator declared for it.
     fn parse_parenthesis_expr(&mut self) -> PRes<ast::Expr<P>, P> {
-        let beg = self.curr_ptr();
+        let beg = self.beg_ptr();
         self.parse_token(token::Kind::LeftParenthesis)?;
-        let expr = Comb(self)
-            .r#try(Self::parse_expr)
-            .fail_msg("Expression expected after opening parenthesis '('".to_owned())
-            .run();
+        let kind = if let Ok(op) = self.attempt(Self::parse_section_op) {
+            ast::ExprKind::Section(ast::Section {
+                id: self.next_node_id(),
+                op,
+                lhs: None,
+                rhs: None,
+            })
+        } else {
+            let expr = Comb(self)
+                .r#try(Self::parse_expr)
+                .fail_msg(
+                    beg.clone(),
+                    "Expression expected after opening parenthesis '('".to_owned(),
+                )
+                .run();
+            match self.parse_op() {
+                Ok(op) => ast::ExprKind::Section(ast::Section {
+                    id: self.next_node_id(),
+                    op,
+                    lhs: Some(Box::new(expr)),
+                    rhs: None,
+                }),
+                Err(_) => ast::ExprKind::Parenthesed(ast::Paren {
+                    id: self.next_node_id(),
+                    expr: Box::new(expr),
+                }),
+            }
+        };
         self.try_parse_token_rec(
+            beg.clone(),
             token::Kind::RightParenthesis,
             "Expected closing parenthesis".to_owned(),
-            token::Value::String(String::from(")")),
+            token::Value::Symbol(Symbol::new(")")),
         );
         Ok(ast::Expr {
             id: self.next_node_id(),
             span: Span {
                 beg,
-                end: self.curr_ptr(),
+                end: self.prev_ptr(),
             },
-            kind: ast::ExprKind::Parenthesed(ast::Paren {
-                id: self.next_node_id(),
-                expr: Box::new(expr),
-            }),
+            kind,
         })
     }
 
+    /// Parses the operator of the section without
+    /// any operands, leaving the closing parenthesis.
+    fn parse_section_op(&mut self) -> PRes<ast::Op<P>, P> {
+        let op = self.parse_op()?;
+        match self.lexer.curr() {
+            Some(tok) if tok.kind == token::Kind::RightParenthesis => Ok(op),
+            Some(tok) => Err(ParseErr::NotThisItem(tok)),
+            None => Err(ParseErr::EOF),
+        }
+    }
+
+    /// Checks if the current token is the operator
+    /// closing the left section, like the `*` in `(2 *)`.
+    fn at_left_section_end(&mut self) -> bool {
+        let is = |tok: Option<token::Token<P>>, kind| matches!(tok, Some(tok) if tok.kind == kind);
+        is(self.lexer.peek_nth(0), token::Kind::Operator)
+            && is(self.lexer.peek_nth(1), token::Kind::RightParenthesis)
+    }
+
     fn parse_ident(&mut self) -> PRes<ast::Ident<P>, P> {
         let tok = self.parse_token(token::Kind::Identifier)?;
-        if let token::Value::String(s) = tok.value {
+        if let token::Value::Symbol(s) = tok.value {
             Ok(ast::Ident {
                 id: self.next_node_id(),
                 symbol: s,
@@ -502,7 +772,7 @@ where
 
     fn parse_op(&mut self) -> PRes<ast::Op<P>, P> {
         let tok = self.parse_token(token::Kind::Operator)?;
-        if let token::Value::String(s) = tok.value {
+        if let token::Value::Symbol(s) = tok.value {
             Ok(ast::Op {
                 id: self.next_node_id(),
                 symbol: s,
@@ -516,20 +786,78 @@ where
     // Literals
 
     fn parse_lit(&mut self) -> PRes<ast::Lit<P>, P> {
-        self.parse_int_lit()
+        Comb(self)
+            .r#try(Self::parse_int_lit)
+            .or(Self::parse_float_lit)
+            .or(Self::parse_str_lit)
+            .or(Self::parse_char_lit)
+            .run()
     }
 
     fn parse_int_lit(&mut self) -> PRes<ast::Lit<P>, P> {
-        let beg = self.curr_ptr();
         let tok = self.parse_token(token::Kind::IntLiteral)?;
         if let token::Value::Integer(v) = tok.value {
             Ok(ast::Lit {
                 id: self.next_node_id(),
-                kind: ast::LitKind::Int(v),
-                span: Span {
-                    beg,
-                    end: self.curr_ptr(),
-                },
+                kind: ast::LitKind::Int(self.int_value(v, false, &tok.span)),
+                span: tok.span,
+            })
+        } else {
+            unreachable!();
+        }
+    }
+
+    /// Returns value of the integer literal with the given magnitude,
+    /// reporting the ones which don't fit in `int`.
+    fn int_value(&mut self, magnitude: u64, negative: bool, span: &Span<P>) -> i64 {
+        let value = if negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            0i64.checked_add_unsigned(magnitude)
+        };
+        value.unwrap_or_else(|| {
+            self.err(Self::msg_err(
+                "Integer literal is too large for `int`".to_owned(),
+                span.beg.clone(),
+                span.end.clone(),
+            ));
+            0
+        })
+    }
+
+    fn parse_float_lit(&mut self) -> PRes<ast::Lit<P>, P> {
+        let tok = self.parse_token(token::Kind::FloatLiteral)?;
+        if let token::Value::Float(v) = tok.value {
+            Ok(ast::Lit {
+                id: self.next_node_id(),
+                kind: ast::LitKind::Float(v),
+                span: tok.span,
+            })
+        } else {
+            unreachable!();
+        }
+    }
+
+    fn parse_str_lit(&mut self) -> PRes<ast::Lit<P>, P> {
+        let tok = self.parse_token(token::Kind::StrLiteral)?;
+        if let token::Value::Symbol(s) = tok.value {
+            Ok(ast::Lit {
+                id: self.next_node_id(),
+                kind: ast::LitKind::Str(s),
+                span: tok.span,
+            })
+        } else {
+            unreachable!();
+        }
+    }
+
+    fn parse_char_lit(&mut self) -> PRes<ast::Lit<P>, P> {
+        let tok = self.parse_token(token::Kind::CharLiteral)?;
+        if let token::Value::Char(c) = tok.value {
+            Ok(ast::Lit {
+                id: self.next_node_id(),
+                kind: ast::LitKind::Char(c),
+                span: tok.span,
             })
         } else {
             unreachable!();
@@ -542,7 +870,8 @@ where
         match self.lexer.curr() {
             Some(tok) => {
                 if tok.kind == kind {
-                    self.lexer.next();
+                    self.lexer.advance();
+                    self.prev_end = Some(tok.span.end.clone());
                     Ok(tok)
                 } else {
                     Err(ParseErr::NotThisItem(tok))
@@ -554,11 +883,11 @@ where
 
     fn try_parse_token_rec(
         &mut self,
+        beg: P,
         kind: token::Kind,
         error_msg: String,
         val: token::Value,
     ) -> token::Token<P> {
-        let beg = self.peek_ptr().clone();
         self.parse_token(kind.clone())
             .unwrap_or_else(|err| match err {
                 ParseErr::EOF => self.eof_reached_fatal(beg, self.curr_ptr()),
@@ -573,6 +902,7 @@ where
                         span: tok.span,
                         kind,
                         value: val,
+                        trivia: Vec::new(),
                     }
                 }
             })
@@ -581,6 +911,7 @@ where
     #[allow(dead_code)]
     fn try_parse_token_fail(
         &mut self,
+        beg: P,
         kind: token::Kind,
         error_msg: String,
         val: token::Value,
@@ -588,21 +919,19 @@ where
         let kind_ = kind.clone();
         Comb(self)
             .r#try(move |self_: &mut Self| self_.parse_token(kind_.clone()))
-            .fail_unex_tok(kind, val, error_msg)
+            .fail_unex_tok(beg, kind, val, error_msg)
             .run()
     }
 
-    fn try_parse_ident_fail(&mut self, error_msg: String) -> ast::Ident<P> {
+    fn try_parse_ident_fail(&mut self, beg: P, error_msg: String) -> ast::Ident<P> {
         Comb(self)
             .r#try(Self::parse_ident)
-            .fail_unex_tok(token::Kind::Identifier, token::Value::None, error_msg)
+            .fail_unex_tok(beg, token::Kind::Identifier, token::Value::None, error_msg)
             .run()
     }
 
     pub fn next_node_id(&mut self) -> ast::NodeId {
-        let tmp = self.node_id;
-        self.node_id += 1;
-        tmp
+        self.node_ids.next_id()
     }
 
     fn one_of_tok(&mut self, kinds: Vec<token::Kind>) -> PRes<token::Token<P>, P> {
@@ -619,31 +948,44 @@ where
 
     // Utility Helpers
 
-    fn push_ptr(&mut self) {
-        debug!("Pushing ptr onto the ptr stack");
-        self.saved_ptrs.push(self.curr_ptr())
-    }
-
-    fn pop_ptr(&mut self) -> P {
-        debug!("Poping value from the ptr stack");
-        self.saved_ptrs
-            .pop()
-            .expect("Poping from empty pointer stack")
-    }
-
-    fn peek_ptr(&mut self) -> &P {
-        debug!("Peeping value on the ptr stack");
-        self.saved_ptrs
-            .last()
-            .expect("Peeking from empty pointer stack")
+    /// Runs the parsing method moving back to the token it
+    /// started at if it fails, so that the alternatives
+    /// can be tried without lexing the source again.
+    fn attempt<R>(&mut self, meth: impl FnOnce(&mut Self) -> PRes<R, P>) -> PRes<R, P> {
+        let checkpoint = self.lexer.checkpoint();
+        let prev_end = self.prev_end.clone();
+        let res = meth(self);
+        if res.is_err() {
+            self.lexer.rewind(checkpoint);
+            self.prev_end = prev_end;
+        } else {
+            self.lexer.release(checkpoint);
+        }
+        res
     }
 
     // Delegations
 
+    /// Returns pointer to the end of the current token.
     fn curr_ptr(&self) -> P {
         self.lexer.curr_ptr()
     }
 
+    /// Returns pointer to the beginning of the current token,
+    /// or to the end of the source if there are no more tokens.
+    fn beg_ptr(&self) -> P {
+        match self.lexer.curr() {
+            Some(tok) => tok.span.beg,
+            None => self.curr_ptr(),
+        }
+    }
+
+    /// Returns pointer to the end of the last consumed token.
+    /// Used as the end of the parsed nodes span.
+    fn prev_ptr(&self) -> P {
+        self.prev_end.clone().unwrap_or_else(|| self.beg_ptr())
+    }
+
     fn err(&mut self, err: Box<dyn LangError<Ptr = P>>) {
         self.sess.borrow_mut().err(err)
     }
@@ -670,6 +1012,7 @@ where
             kind,
             value,
             span: Span { beg, end },
+            trivia: Vec::new(),
         };
         let err: Box<errors::ParserError<P>> = Box::new(errors::ParserError {
             msg: format!("Expected token {}. {}", tok, msg),
@@ -688,6 +1031,7 @@ where
             kind,
             value,
             span: actual.span.clone(),
+            trivia: Vec::new(),
         };
         let err: Box<errors::ParserError<P>> = Box::new(errors::ParserError {
             msg: format!("Expected token {}, got {}. {}", expected, actual, msg),
@@ -710,3 +1054,40 @@ pub enum ParseErr<P: ftl_source::Pointer> {
     EOF,
     NotThisItem(token::Token<P>),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::panic::{self, AssertUnwindSafe};
+
+    use ftl_source::string;
+
+    /// Returns position of the fatal error reported
+    /// when the source ends in the middle of a declaration.
+    fn eof_err_pos(src: &str) -> (usize, usize) {
+        let sess = RcRef::new(Session::new(string::String::from(src)));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            Parser::new(Lexer::new(sess.clone()), sess.clone()).parse()
+        }));
+        assert!(res.is_err(), "{}", src);
+        let sess = sess.borrow();
+        let err = sess.handler.errors().last().unwrap();
+        assert_eq!(err.desc(), "End of file reached");
+        (err.begin().line(), err.begin().position())
+    }
+
+    #[test]
+    fn eof_error_starts_after_declaration_keyword() {
+        assert_eq!(eof_err_pos("decl f int int"), (1, 5));
+        assert_eq!(eof_err_pos("def f a a"), (1, 4));
+        assert_eq!(eof_err_pos("infix 5 + a b"), (1, 6));
+        assert_eq!(eof_err_pos("\n  decl f"), (2, 7));
+    }
+
+    #[test]
+    fn eof_error_in_parentheses_starts_at_the_opening_one() {
+        assert_eq!(eof_err_pos("def f: (1"), (1, 8));
+        assert_eq!(eof_err_pos("decl f: (int"), (1, 9));
+    }
+}
@@ -0,0 +1,148 @@
+//! Serde support for the syntax tree, enabled with the `serde` feature.
+//!
+//! Every node is serialized with its fields, spans as pairs
+//! of [`Position`](struct.Position.html)s and symbols as strings.
+//! Syntax tree can be deserialized with any pointer implementing
+//! [`FromPosition`](trait.FromPosition.html), usually the
+//! [`synthetic::Pointer`](../../ftl_source/synthetic/struct.Pointer.html),
+//! and processed by the passes like the parsed one.
+//!
+//! # Examples
+//!
+//! ```
+//! use ftl_parser::ast::AST;
+//! use ftl_source::synthetic::Synthetic;
+//!
+//! let json = r#"{"root": {"id": 0, "decl": []}}"#;
+//! let ast: AST<Synthetic> = serde_json::from_str(json).unwrap();
+//! assert!(ast.root.decl.is_empty());
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use ftl_source::{synthetic, Pointer, Span};
+
+/// Place in the source the pointer points to, as it is serialized.
+/// Columns are counted from one, see
+/// [`Pointer`](../../ftl_source/trait.Pointer.html) for their meaning.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub utf16_column: usize,
+    pub byte_column: usize,
+    pub offset: usize,
+}
+
+impl Position {
+    pub fn of<P: Pointer>(ptr: &P) -> Self {
+        Self {
+            line: ptr.line(),
+            column: ptr.position(),
+            utf16_column: ptr.utf16_position(),
+            byte_column: ptr.byte_position(),
+            offset: ptr.offset(),
+        }
+    }
+}
+
+/// Pointer which can be created from the deserialized position.
+pub trait FromPosition: Pointer {
+    fn from_position(pos: Position) -> Self;
+}
+
+impl FromPosition for synthetic::Pointer {
+    fn from_position(pos: Position) -> Self {
+        Self {
+            line: pos.line,
+            position: pos.column,
+            utf16_position: pos.utf16_column,
+            byte_position: pos.byte_column,
+            offset: pos.offset,
+        }
+    }
+}
+
+/// Used with `#[serde(with)]` on the span fields.
+pub(crate) mod span {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct SpanRepr {
+        beg: Position,
+        end: Position,
+    }
+
+    pub fn serialize<P: Pointer, S: Serializer>(span: &Span<P>, s: S) -> Result<S::Ok, S::Error> {
+        SpanRepr {
+            beg: Position::of(&span.beg),
+            end: Position::of(&span.end),
+        }
+        .serialize(s)
+    }
+
+    pub fn deserialize<'de, P: FromPosition, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Span<P>, D::Error> {
+        let repr = SpanRepr::deserialize(d)?;
+        Ok(Span {
+            beg: P::from_position(repr.beg),
+            end: P::from_position(repr.end),
+        })
+    }
+}
+
+/// Used with `#[serde(with)]` on the symbol fields.
+pub(crate) mod symbol {
+    use super::*;
+
+    use ftl_session::symbol::Symbol;
+
+    pub fn serialize<S: Serializer>(symbol: &Symbol, s: S) -> Result<S::Ok, S::Error> {
+        symbol.as_str().serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Symbol, D::Error> {
+        Ok(Symbol::new(&String::deserialize(d)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use ftl_source::synthetic::Synthetic;
+
+    use crate::ast::*;
+    use crate::test_utils::parse;
+
+    #[test]
+    fn deserialized_tree_serializes_to_the_same_json() {
+        let ast = parse(
+            r#"
+decl add int int [lang_add] : int
+decl test (int)int (int)int: (int int) int
+infix 50 + a b: @add a b
+def fooß a b: (a + b) `fooß 2
+"#,
+        );
+        let json = serde_json::to_value(&ast).unwrap();
+        let loaded: AST<Synthetic> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), json);
+    }
+
+    #[test]
+    fn spans_are_serialized_as_positions() {
+        let ast = parse("def ßß: ab\n");
+        let json = serde_json::to_value(&ast).unwrap();
+        let body = &json["root"]["decl"][0]["kind"]["FunctionDef"]["body"];
+        assert_eq!(
+            body["span"],
+            json!({
+                "beg": {"line": 1, "column": 9, "utf16_column": 9, "byte_column": 11, "offset": 10},
+                "end": {"line": 1, "column": 11, "utf16_column": 11, "byte_column": 13, "offset": 12},
+            })
+        );
+        assert_eq!(body["kind"]["Identifier"]["symbol"], Value::from("ab"));
+    }
+}
@@ -0,0 +1,228 @@
+//! Fixtures shared by the tests of the parser and the passes.
+//!
+//! Built for the tests of this crate, other crates
+//! get them with the `test-utils` feature.
+
+use ftl_lexer::Lexer;
+use ftl_session::Session;
+use ftl_source::{string, Pointer};
+use ftl_utility::RcRef;
+
+use crate::ast::*;
+use crate::visitor::{self, Pass};
+use crate::visitor_mut::{self, MutPass};
+use crate::Parser;
+
+/// Parses the source which has to be free of errors.
+pub fn parse(src: &str) -> AST<string::String> {
+    let sess = RcRef::new(Session::new(string::String::from(src)));
+    let lexer = Lexer::new(sess.clone());
+    let mut parser = Parser::new(lexer, sess.clone());
+    let ast = parser.parse();
+    assert_eq!(sess.borrow().handler.error_msg(), None, "{}", src);
+    ast
+}
+
+/// Collects ids of the visited nodes in the order they are visited.
+#[derive(Default)]
+pub struct Ids(pub Vec<NodeId>);
+
+impl<P: Pointer> Pass<'_, P> for Ids {
+    fn visit_module(&mut self, node: &Module<P>) {
+        self.0.push(node.id);
+        visitor::walk_module(self, node);
+    }
+
+    fn visit_top_level_decl(&mut self, node: &TopLevelDecl<P>) {
+        self.0.push(node.id);
+        visitor::walk_top_level_decl(self, node);
+    }
+
+    fn visit_func_decl(&mut self, node: &FuncDecl<P>) {
+        self.0.push(node.id);
+        visitor::walk_func_decl(self, node);
+    }
+
+    fn visit_func_def(&mut self, node: &FuncDef<P>) {
+        self.0.push(node.id);
+        visitor::walk_func_def(self, node);
+    }
+
+    fn visit_infix_def(&mut self, node: &InfixDef<P>) {
+        self.0.push(node.id);
+        visitor::walk_infix_def(self, node);
+    }
+
+    fn visit_prefix_def(&mut self, node: &PrefixDef<P>) {
+        self.0.push(node.id);
+        visitor::walk_prefix_def(self, node);
+    }
+
+    fn visit_func_arg(&mut self, node: &FuncArg<P>) {
+        self.0.push(node.id);
+        visitor::walk_func_arg(self, node);
+    }
+
+    fn visit_func_attr(&mut self, node: &FuncAttr<P>) {
+        self.0.push(node.id);
+        visitor::walk_func_attr(self, node);
+    }
+
+    fn visit_expr(&mut self, node: &Expr<P>) {
+        self.0.push(node.id);
+        visitor::walk_expr(self, node);
+    }
+
+    fn visit_infix_func_call(&mut self, node: &InfixFuncCall<P>) {
+        self.0.push(node.id);
+        visitor::walk_infix_func_call(self, node);
+    }
+
+    fn visit_infix_op_call(&mut self, node: &InfixOpCall<P>) {
+        self.0.push(node.id);
+        visitor::walk_infix_op_call(self, node);
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &PrefixOpCall<P>) {
+        self.0.push(node.id);
+        visitor::walk_prefix_op_call(self, node);
+    }
+
+    fn visit_section(&mut self, node: &Section<P>) {
+        self.0.push(node.id);
+        visitor::walk_section(self, node);
+    }
+
+    fn visit_func_call(&mut self, node: &FuncCall<P>) {
+        self.0.push(node.id);
+        visitor::walk_func_call(self, node);
+    }
+
+    fn visit_parenthesed(&mut self, node: &Paren<P>) {
+        self.0.push(node.id);
+        visitor::walk_paren_expr(self, node);
+    }
+
+    fn visit_lit(&mut self, node: &Lit<P>) {
+        self.0.push(node.id);
+        visitor::walk_lit(self, node);
+    }
+
+    fn visit_ident(&mut self, node: &Ident<P>) {
+        self.0.push(node.id);
+    }
+
+    fn visit_op(&mut self, node: &Op<P>) {
+        self.0.push(node.id);
+    }
+
+    fn visit_type(&mut self, node: &Type<P>) {
+        self.0.push(node.id);
+        visitor::walk_type(self, node);
+    }
+
+    fn visit_func_type(&mut self, node: &FuncType<P>) {
+        self.0.push(node.id);
+        visitor::walk_func_type(self, node);
+    }
+}
+
+impl<P: Pointer> MutPass<'_, P> for Ids {
+    fn visit_module(&mut self, node: &mut Module<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_module(self, node);
+    }
+
+    fn visit_top_level_decl(&mut self, node: &mut TopLevelDecl<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_top_level_decl(self, node);
+    }
+
+    fn visit_func_decl(&mut self, node: &mut FuncDecl<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_func_decl(self, node);
+    }
+
+    fn visit_func_def(&mut self, node: &mut FuncDef<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_func_def(self, node);
+    }
+
+    fn visit_infix_def(&mut self, node: &mut InfixDef<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_infix_def(self, node);
+    }
+
+    fn visit_prefix_def(&mut self, node: &mut PrefixDef<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_prefix_def(self, node);
+    }
+
+    fn visit_func_arg(&mut self, node: &mut FuncArg<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_func_arg(self, node);
+    }
+
+    fn visit_func_attr(&mut self, node: &mut FuncAttr<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_func_attr(self, node);
+    }
+
+    fn visit_expr(&mut self, node: &mut Expr<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_expr(self, node);
+    }
+
+    fn visit_infix_func_call(&mut self, node: &mut InfixFuncCall<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_infix_func_call(self, node);
+    }
+
+    fn visit_infix_op_call(&mut self, node: &mut InfixOpCall<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_infix_op_call(self, node);
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &mut PrefixOpCall<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_prefix_op_call(self, node);
+    }
+
+    fn visit_section(&mut self, node: &mut Section<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_section(self, node);
+    }
+
+    fn visit_func_call(&mut self, node: &mut FuncCall<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_func_call(self, node);
+    }
+
+    fn visit_parenthesed(&mut self, node: &mut Paren<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_paren(self, node);
+    }
+
+    fn visit_lit(&mut self, node: &mut Lit<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_lit(self, node);
+    }
+
+    fn visit_ident(&mut self, node: &mut Ident<P>) {
+        self.0.push(node.id);
+    }
+
+    fn visit_op(&mut self, node: &mut Op<P>) {
+        self.0.push(node.id);
+    }
+
+    fn visit_type(&mut self, node: &mut Type<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_type(self, node);
+    }
+
+    fn visit_func_type(&mut self, node: &mut FuncType<P>) {
+        self.0.push(node.id);
+        visitor_mut::noop_func_type(self, node);
+    }
+}
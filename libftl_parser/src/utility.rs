@@ -1,4 +1,851 @@
-// FIXME: WIP
+//! Builders constructing syntax trees without parsing them.
+//!
+//! Built trees come from the
+//! [`Synthetic`](../../ftl_source/synthetic/struct.Synthetic.html)
+//! source, so all of their spans are empty, and their node ids are
+//! allocated one per node, the same way the parser allocates them.
+//!
+//! # Examples
+//!
+//! ```
+//! use ftl_parser::utility::*;
+//!
+//! let ast = ASTBuilder::new()
+//!     .infix(infix(5, "+", "a", "b").body(id("a")))
+//!     .decl(decl("foo").arg(int_t()).ret(int_t()))
+//!     .def(def("foo").arg("a").body(op("+", id("a"), int(1))))
+//!     .build();
+//! assert_eq!(ast.root.decl.len(), 3);
+//! ```
 
-// FIXME: WIP
-pub struct ASTBuilder;
+use ftl_session::symbol::Symbol;
+use ftl_source::synthetic::{Pointer, Synthetic};
+use ftl_source::Span;
+
+use crate::ast::*;
+
+/// Builds the node once its ids can be allocated.
+type Build<T> = Box<dyn FnOnce(&mut NodeIds) -> T>;
+
+fn span() -> Span<Pointer> {
+    Span {
+        beg: Pointer::default(),
+        end: Pointer::default(),
+    }
+}
+
+fn ident(ids: &mut NodeIds, name: &str) -> Ident<Pointer> {
+    Ident {
+        id: ids.next_id(),
+        symbol: Symbol::new(name),
+        span: span(),
+    }
+}
+
+fn lit(ids: &mut NodeIds, kind: LitKind) -> Lit<Pointer> {
+    Lit {
+        id: ids.next_id(),
+        kind,
+        span: span(),
+    }
+}
+
+/// Builds the module out of the top level declarations
+/// in the order they have been added.
+#[derive(Default)]
+pub struct ASTBuilder {
+    decls: Vec<Build<TopLevelDeclKind<Pointer>>>,
+}
+
+impl ASTBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn decl(mut self, decl: FuncDeclBuilder) -> Self {
+        self.decls.push(Box::new(|ids| {
+            TopLevelDeclKind::FunctionDecl(decl.build(ids))
+        }));
+        self
+    }
+
+    pub fn def(mut self, def: FuncDefBuilder) -> Self {
+        self.decls.push(Box::new(|ids| {
+            TopLevelDeclKind::FunctionDef(def.build(ids))
+        }));
+        self
+    }
+
+    pub fn infix(mut self, infix: InfixDefBuilder) -> Self {
+        self.decls
+            .push(Box::new(|ids| TopLevelDeclKind::InfixDef(infix.build(ids))));
+        self
+    }
+
+    pub fn prefix(mut self, prefix: PrefixDefBuilder) -> Self {
+        self.decls.push(Box::new(|ids| {
+            TopLevelDeclKind::PrefixDef(prefix.build(ids))
+        }));
+        self
+    }
+
+    pub fn build(self) -> AST<Synthetic> {
+        self.build_with(&mut NodeIds::new())
+    }
+
+    /// Builds the tree allocating its ids with the given allocator.
+    pub fn build_with(self, ids: &mut NodeIds) -> AST<Synthetic> {
+        let id = ids.next_id();
+        let decl = self
+            .decls
+            .into_iter()
+            .map(|kind| TopLevelDecl {
+                id: ids.next_id(),
+                kind: kind(ids),
+                span: span(),
+            })
+            .collect();
+        AST::new(Module { id, decl })
+    }
+}
+
+/// Builds the function declaration.
+///
+/// Declaration can only be built, or added to the
+/// [`ASTBuilder`](struct.ASTBuilder.html), once its
+/// return type has been given.
+pub struct FuncDeclBuilder<R = TypeBuilder> {
+    name: String,
+    args: Vec<TypeBuilder>,
+    attrs: Vec<(String, Vec<AttrArgBuilder>)>,
+    ret: R,
+    doc: Option<String>,
+}
+
+pub fn decl(name: &str) -> FuncDeclBuilder<()> {
+    FuncDeclBuilder {
+        name: name.to_owned(),
+        args: Vec::new(),
+        attrs: Vec::new(),
+        ret: (),
+        doc: None,
+    }
+}
+
+impl<R> FuncDeclBuilder<R> {
+    /// Adds type of the next argument.
+    pub fn arg(mut self, ty: TypeBuilder) -> Self {
+        self.args.push(ty);
+        self
+    }
+
+    pub fn attr(mut self, name: &str) -> Self {
+        self.attrs.push((name.to_owned(), Vec::new()));
+        self
+    }
+
+    pub fn attr_args(mut self, name: &str, args: Vec<AttrArgBuilder>) -> Self {
+        self.attrs.push((name.to_owned(), args));
+        self
+    }
+
+    pub fn ret(self, ty: TypeBuilder) -> FuncDeclBuilder {
+        FuncDeclBuilder {
+            name: self.name,
+            args: self.args,
+            attrs: self.attrs,
+            ret: ty,
+            doc: self.doc,
+        }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_owned());
+        self
+    }
+}
+
+impl FuncDeclBuilder {
+    pub fn build(self, ids: &mut NodeIds) -> FuncDecl<Pointer> {
+        FuncDecl {
+            id: ids.next_id(),
+            ident: ident(ids, &self.name),
+            ty: Some(func_t(self.args, self.ret).build(ids)),
+            attrs: build_attrs(ids, self.attrs),
+            doc: self.doc,
+        }
+    }
+}
+
+fn build_attrs(
+    ids: &mut NodeIds,
+    attrs: Vec<(String, Vec<AttrArgBuilder>)>,
+) -> Vec<FuncAttr<Pointer>> {
+    attrs
+        .into_iter()
+        .map(|(name, args)| FuncAttr {
+            id: ids.next_id(),
+            ident: ident(ids, &name),
+            args: args.into_iter().map(|arg| (arg.0)(ids)).collect(),
+            span: span(),
+        })
+        .collect()
+}
+
+fn build_arg(ids: &mut NodeIds, name: &str, ty: Option<TypeBuilder>) -> FuncArg<Pointer> {
+    FuncArg {
+        id: ids.next_id(),
+        ident: ident(ids, name),
+        ty: ty.map(|ty| ty.build(ids)),
+        span: span(),
+    }
+}
+
+/// Builds the function definition.
+///
+/// Definition can only be built, or added to the
+/// [`ASTBuilder`](struct.ASTBuilder.html), once its
+/// body has been given.
+///
+/// ```compile_fail
+/// use ftl_parser::utility::*;
+///
+/// ASTBuilder::new().def(def("f").arg("a")).build();
+/// ```
+pub struct FuncDefBuilder<B = ExprBuilder> {
+    name: String,
+    args: Vec<(String, Option<TypeBuilder>)>,
+    attrs: Vec<(String, Vec<AttrArgBuilder>)>,
+    body: B,
+    doc: Option<String>,
+}
+
+pub fn def(name: &str) -> FuncDefBuilder<()> {
+    FuncDefBuilder {
+        name: name.to_owned(),
+        args: Vec::new(),
+        attrs: Vec::new(),
+        body: (),
+        doc: None,
+    }
+}
+
+impl<B> FuncDefBuilder<B> {
+    pub fn arg(mut self, name: &str) -> Self {
+        self.args.push((name.to_owned(), None));
+        self
+    }
+
+    pub fn typed_arg(mut self, name: &str, ty: TypeBuilder) -> Self {
+        self.args.push((name.to_owned(), Some(ty)));
+        self
+    }
+
+    pub fn attr(mut self, name: &str) -> Self {
+        self.attrs.push((name.to_owned(), Vec::new()));
+        self
+    }
+
+    pub fn attr_args(mut self, name: &str, args: Vec<AttrArgBuilder>) -> Self {
+        self.attrs.push((name.to_owned(), args));
+        self
+    }
+
+    pub fn body(self, body: ExprBuilder) -> FuncDefBuilder {
+        FuncDefBuilder {
+            name: self.name,
+            args: self.args,
+            attrs: self.attrs,
+            body,
+            doc: self.doc,
+        }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_owned());
+        self
+    }
+}
+
+impl FuncDefBuilder {
+    pub fn build(self, ids: &mut NodeIds) -> FuncDef<Pointer> {
+        FuncDef {
+            id: ids.next_id(),
+            decl: FuncDecl {
+                id: ids.next_id(),
+                ident: ident(ids, &self.name),
+                ty: None,
+                attrs: build_attrs(ids, self.attrs),
+                doc: self.doc,
+            },
+            args: self
+                .args
+                .into_iter()
+                .map(|(name, ty)| build_arg(ids, &name, ty))
+                .collect(),
+            body: self.body.build(ids),
+        }
+    }
+}
+
+/// Builds the infix operator definition.
+///
+/// Definition can only be built, or added to the
+/// [`ASTBuilder`](struct.ASTBuilder.html), once its
+/// body has been given.
+pub struct InfixDefBuilder<B = ExprBuilder> {
+    precedence: usize,
+    op: String,
+    args: (String, String),
+    ty: Option<TypeBuilder>,
+    body: B,
+    doc: Option<String>,
+}
+
+pub fn infix(precedence: usize, op: &str, lhs: &str, rhs: &str) -> InfixDefBuilder<()> {
+    InfixDefBuilder {
+        precedence,
+        op: op.to_owned(),
+        args: (lhs.to_owned(), rhs.to_owned()),
+        ty: None,
+        body: (),
+        doc: None,
+    }
+}
+
+impl<B> InfixDefBuilder<B> {
+    pub fn ty(mut self, ty: TypeBuilder) -> Self {
+        self.ty = Some(ty);
+        self
+    }
+
+    pub fn body(self, body: ExprBuilder) -> InfixDefBuilder {
+        InfixDefBuilder {
+            precedence: self.precedence,
+            op: self.op,
+            args: self.args,
+            ty: self.ty,
+            body,
+            doc: self.doc,
+        }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_owned());
+        self
+    }
+}
+
+impl InfixDefBuilder {
+    pub fn build(self, ids: &mut NodeIds) -> InfixDef<Pointer> {
+        InfixDef {
+            id: ids.next_id(),
+            precedence: self.precedence,
+            op: Op {
+                id: ids.next_id(),
+                symbol: Symbol::new(&self.op),
+                span: span(),
+            },
+            args: (
+                build_arg(ids, &self.args.0, None),
+                build_arg(ids, &self.args.1, None),
+            ),
+            ty: self.ty.map(|ty| ty.build(ids)),
+            body: self.body.build(ids),
+            doc: self.doc,
+        }
+    }
+}
+
+/// Builds the prefix operator definition.
+///
+/// Definition can only be built, or added to the
+/// [`ASTBuilder`](struct.ASTBuilder.html), once its
+/// body has been given.
+pub struct PrefixDefBuilder<B = ExprBuilder> {
+    precedence: Option<usize>,
+    op: String,
+    arg: String,
+    ty: Option<TypeBuilder>,
+    body: B,
+    doc: Option<String>,
+}
+
+pub fn prefix(op: &str, arg: &str) -> PrefixDefBuilder<()> {
+    PrefixDefBuilder {
+        precedence: None,
+        op: op.to_owned(),
+        arg: arg.to_owned(),
+        ty: None,
+        body: (),
+        doc: None,
+    }
+}
+
+impl<B> PrefixDefBuilder<B> {
+    pub fn precedence(mut self, precedence: usize) -> Self {
+        self.precedence = Some(precedence);
+        self
+    }
+
+    pub fn ty(mut self, ty: TypeBuilder) -> Self {
+        self.ty = Some(ty);
+        self
+    }
+
+    pub fn body(self, body: ExprBuilder) -> PrefixDefBuilder {
+        PrefixDefBuilder {
+            precedence: self.precedence,
+            op: self.op,
+            arg: self.arg,
+            ty: self.ty,
+            body,
+            doc: self.doc,
+        }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_owned());
+        self
+    }
+}
+
+impl PrefixDefBuilder {
+    pub fn build(self, ids: &mut NodeIds) -> PrefixDef<Pointer> {
+        PrefixDef {
+            id: ids.next_id(),
+            precedence: self.precedence,
+            op: Op {
+                id: ids.next_id(),
+                symbol: Symbol::new(&self.op),
+                span: span(),
+            },
+            arg: build_arg(ids, &self.arg, None),
+            ty: self.ty.map(|ty| ty.build(ids)),
+            body: self.body.build(ids),
+            doc: self.doc,
+        }
+    }
+}
+
+/// Builds the expression once its ids can be allocated.
+pub struct ExprBuilder(Build<ExprKind<Pointer>>);
+
+impl ExprBuilder {
+    fn new(kind: impl FnOnce(&mut NodeIds) -> ExprKind<Pointer> + 'static) -> Self {
+        Self(Box::new(kind))
+    }
+
+    pub fn build(self, ids: &mut NodeIds) -> Expr<Pointer> {
+        Expr {
+            id: ids.next_id(),
+            kind: (self.0)(ids),
+            span: span(),
+        }
+    }
+}
+
+pub fn int(val: i64) -> ExprBuilder {
+    ExprBuilder::new(move |ids| ExprKind::Literal(lit(ids, LitKind::Int(val))))
+}
+
+pub fn float(val: f64) -> ExprBuilder {
+    ExprBuilder::new(move |ids| ExprKind::Literal(lit(ids, LitKind::Float(val))))
+}
+
+pub fn string(val: &str) -> ExprBuilder {
+    let val = Symbol::new(val);
+    ExprBuilder::new(move |ids| ExprKind::Literal(lit(ids, LitKind::Str(val))))
+}
+
+pub fn character(val: char) -> ExprBuilder {
+    ExprBuilder::new(move |ids| ExprKind::Literal(lit(ids, LitKind::Char(val))))
+}
+
+pub fn id(name: &str) -> ExprBuilder {
+    let name = name.to_owned();
+    ExprBuilder::new(move |ids| ExprKind::Identifier(ident(ids, &name)))
+}
+
+/// Builds the attribute argument once its ids can be allocated.
+pub struct AttrArgBuilder(Build<AttrArg<Pointer>>);
+
+pub fn int_arg(val: i64) -> AttrArgBuilder {
+    AttrArgBuilder(Box::new(move |ids| {
+        AttrArg::Lit(lit(ids, LitKind::Int(val)))
+    }))
+}
+
+pub fn float_arg(val: f64) -> AttrArgBuilder {
+    AttrArgBuilder(Box::new(move |ids| {
+        AttrArg::Lit(lit(ids, LitKind::Float(val)))
+    }))
+}
+
+pub fn string_arg(val: &str) -> AttrArgBuilder {
+    let val = Symbol::new(val);
+    AttrArgBuilder(Box::new(move |ids| {
+        AttrArg::Lit(lit(ids, LitKind::Str(val)))
+    }))
+}
+
+pub fn character_arg(val: char) -> AttrArgBuilder {
+    AttrArgBuilder(Box::new(move |ids| {
+        AttrArg::Lit(lit(ids, LitKind::Char(val)))
+    }))
+}
+
+pub fn id_arg(name: &str) -> AttrArgBuilder {
+    let name = name.to_owned();
+    AttrArgBuilder(Box::new(move |ids| AttrArg::Ident(ident(ids, &name))))
+}
+
+/// Function call `@lhs args...`.
+pub fn call(lhs: ExprBuilder, args: Vec<ExprBuilder>) -> ExprBuilder {
+    ExprBuilder::new(move |ids| {
+        ExprKind::FunctionCall(FuncCall {
+            id: ids.next_id(),
+            lhs: Box::new(lhs.build(ids)),
+            args: args.into_iter().map(|arg| arg.build(ids)).collect(),
+        })
+    })
+}
+
+/// Operator call `lhs op rhs`.
+pub fn op(op: &str, lhs: ExprBuilder, rhs: ExprBuilder) -> ExprBuilder {
+    let op = op.to_owned();
+    ExprBuilder::new(move |ids| {
+        ExprKind::InfixOpCall(InfixOpCall {
+            id: ids.next_id(),
+            op: Op {
+                id: ids.next_id(),
+                symbol: Symbol::new(&op),
+                span: span(),
+            },
+            lhs: Box::new(lhs.build(ids)),
+            rhs: Box::new(rhs.build(ids)),
+        })
+    })
+}
+
+/// Prefix operator call `op expr`.
+pub fn prefix_op(op: &str, expr: ExprBuilder) -> ExprBuilder {
+    let op = op.to_owned();
+    ExprBuilder::new(move |ids| {
+        ExprKind::PrefixOpCall(PrefixOpCall {
+            id: ids.next_id(),
+            op: Op {
+                id: ids.next_id(),
+                symbol: Symbol::new(&op),
+                span: span(),
+            },
+            expr: Box::new(expr.build(ids)),
+        })
+    })
+}
+
+/// Infix function call ``lhs `name rhs``.
+pub fn infix_call(name: &str, lhs: ExprBuilder, rhs: ExprBuilder) -> ExprBuilder {
+    let name = name.to_owned();
+    ExprBuilder::new(move |ids| {
+        ExprKind::InfixFuncCall(InfixFuncCall {
+            id: ids.next_id(),
+            ident: ident(ids, &name),
+            lhs: Box::new(lhs.build(ids)),
+            rhs: Box::new(rhs.build(ids)),
+        })
+    })
+}
+
+/// Operator section `(op)`.
+pub fn section(op: &str) -> ExprBuilder {
+    build_section(op, None, None)
+}
+
+/// Left operator section `(lhs op)`.
+pub fn left_section(lhs: ExprBuilder, op: &str) -> ExprBuilder {
+    build_section(op, Some(lhs), None)
+}
+
+/// Right operator section `(op rhs)`.
+pub fn right_section(op: &str, rhs: ExprBuilder) -> ExprBuilder {
+    build_section(op, None, Some(rhs))
+}
+
+fn build_section(op: &str, lhs: Option<ExprBuilder>, rhs: Option<ExprBuilder>) -> ExprBuilder {
+    let op = op.to_owned();
+    ExprBuilder::new(move |ids| {
+        ExprKind::Section(Section {
+            id: ids.next_id(),
+            lhs: lhs.map(|lhs| Box::new(lhs.build(ids))),
+            op: Op {
+                id: ids.next_id(),
+                symbol: Symbol::new(&op),
+                span: span(),
+            },
+            rhs: rhs.map(|rhs| Box::new(rhs.build(ids))),
+        })
+    })
+}
+
+pub fn paren(expr: ExprBuilder) -> ExprBuilder {
+    ExprBuilder::new(move |ids| {
+        ExprKind::Parenthesed(Paren {
+            id: ids.next_id(),
+            expr: Box::new(expr.build(ids)),
+        })
+    })
+}
+
+/// Builds the type once its ids can be allocated.
+pub struct TypeBuilder(Build<TypeKind<Pointer>>);
+
+impl TypeBuilder {
+    pub fn build(self, ids: &mut NodeIds) -> Type<Pointer> {
+        Type {
+            id: ids.next_id(),
+            kind: (self.0)(ids),
+            span: span(),
+        }
+    }
+}
+
+pub fn int_t() -> TypeBuilder {
+    TypeBuilder(Box::new(|_| TypeKind::Literal(LitType::Int)))
+}
+
+pub fn float_t() -> TypeBuilder {
+    TypeBuilder(Box::new(|_| TypeKind::Literal(LitType::Float)))
+}
+
+pub fn void_t() -> TypeBuilder {
+    TypeBuilder(Box::new(|_| TypeKind::Literal(LitType::Void)))
+}
+
+pub fn str_t() -> TypeBuilder {
+    TypeBuilder(Box::new(|_| TypeKind::Literal(LitType::Str)))
+}
+
+pub fn char_t() -> TypeBuilder {
+    TypeBuilder(Box::new(|_| TypeKind::Literal(LitType::Char)))
+}
+
+pub fn func_t(args: Vec<TypeBuilder>, ret: TypeBuilder) -> TypeBuilder {
+    TypeBuilder(Box::new(move |ids| {
+        TypeKind::Function(FuncType {
+            id: ids.next_id(),
+            args: args.into_iter().map(|arg| arg.build(ids)).collect(),
+            ret: Box::new(ret.build(ids)),
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use ftl_lexer::Lexer;
+    use ftl_session::Session;
+    use ftl_source::string;
+    use ftl_utility::RcRef;
+
+    use crate::diff::diff;
+    use crate::test_utils::{parse, Ids};
+    use crate::visitor;
+    use crate::Parser;
+
+    #[test]
+    fn building_the_same_tree_as_parsed() {
+        let ast = ASTBuilder::new()
+            .infix(infix(5, "+", "a", "b").body(id("a")))
+            .decl(
+                decl("foo")
+                    .arg(int_t())
+                    .arg(func_t(vec![int_t()], void_t()))
+                    .attr("x")
+                    .ret(int_t()),
+            )
+            .def(def("foo").arg("a").arg("g").attr("y").body(op(
+                "+",
+                paren(id("a")),
+                call(id("g"), vec![int(1)]),
+            )))
+            .def(def("bar").body(infix_call("foo", int(1), id("bar"))))
+            .build();
+        let parsed = parse(
+            "infix 5 + a b: a\n\
+             decl foo int (int) void [x]: int\n\
+             def foo a g [y]: (a) + @g 1\n\
+             def bar: 1 `foo bar",
+        );
+        assert_eq!(diff(&ast, &parsed), None);
+    }
+
+    #[test]
+    fn parsing_attribute_arguments() {
+        let ast = ASTBuilder::new()
+            .decl(
+                decl("add")
+                    .attr_args("lang", vec![string_arg("add")])
+                    .attr_args("inline", vec![id_arg("always")])
+                    .attr_args("x", vec![id_arg("a"), int_arg(1), int_arg(2)])
+                    .ret(int_t()),
+            )
+            .def(
+                def("f")
+                    .attr("y")
+                    .attr_args("z", vec![character_arg('c')])
+                    .body(int(1)),
+            )
+            .build();
+        let parsed = parse(
+            "decl add [lang \"add\" inline(always) x(a 1) 2]: int\n\
+             def f [y z 'c']: 1",
+        );
+        assert_eq!(diff(&ast, &parsed), None);
+    }
+
+    #[test]
+    fn attaching_doc_comments() {
+        let ast = ASTBuilder::new()
+            .infix(infix(5, "+", "a", "b").doc("Adds.").body(id("a")))
+            .prefix(prefix("-", "a").doc("Negates.\n\nTwice.").body(id("a")))
+            .decl(decl("foo").doc("Foo.").ret(int_t()))
+            .def(def("foo").body(int(1)))
+            .def(def("bar").doc("Bar.").body(int(2)))
+            .build();
+        let parsed = parse(
+            "## Adds.\n\
+             infix 5 + a b: a\n\
+             ##Negates.\n\
+             ##\n\
+             # not a doc\n\
+             ## Twice.\n\
+             prefix - a: a\n\
+             ## Foo.\n\
+             decl foo: int\n\
+             def foo: 1\n\
+             ## Bar.\n\
+             def bar: 2",
+        );
+        assert_eq!(diff(&ast, &parsed), None);
+    }
+
+    #[test]
+    fn building_prefix_calls_as_parsed() {
+        let ast = ASTBuilder::new()
+            .prefix(prefix("-", "a").body(id("a")))
+            .prefix(prefix("!", "x").precedence(3).body(id("x")))
+            .def(def("f").arg("a").arg("b").body(op(
+                "-",
+                prefix_op("-", id("a")),
+                prefix_op("-", id("b")),
+            )))
+            .def(def("g").arg("h").body(op(
+                "-",
+                prefix_op("!", call(id("h"), vec![int(1)])),
+                int(2),
+            )))
+            .build();
+        let parsed = parse(
+            "prefix - a: a\n\
+             prefix 3 ! x: x\n\
+             def f a b: -a - -b\n\
+             def g h: ! @h 1 - 2",
+        );
+        assert_eq!(diff(&ast, &parsed), None);
+    }
+
+    #[test]
+    fn building_sections_as_parsed() {
+        let ast = ASTBuilder::new()
+            .def(def("f").arg("a").body(call(
+                id("g"),
+                vec![
+                    section("+"),
+                    left_section(int(2), "*"),
+                    left_section(op("+", id("a"), int(1)), "-"),
+                    paren(prefix_op("-", int(1))),
+                ],
+            )))
+            .build();
+        let parsed = parse("def f a: @g (+) (2 *) (a + 1 -) (- 1)");
+        assert_eq!(diff(&ast, &parsed), None);
+    }
+
+    #[test]
+    fn parsing_negative_literals() {
+        let ast = ASTBuilder::new()
+            .def(def("f").arg("a").body(call(
+                id("g"),
+                vec![
+                    paren(int(-1)),
+                    paren(prefix_op("-", int(1))),
+                    paren(op("-", id("a"), int(1))),
+                    paren(op("-", id("a"), int(i64::MIN))),
+                ],
+            )))
+            .def(def("h").body(op("*", float(-2.5), int(-0x10))))
+            .build();
+        let parsed = parse(
+            "def f a: @g (-1) (- 1) (a -1) (a - -9_223_372_036_854_775_808)
+             def h: -2.5 * -0x10",
+        );
+        assert_eq!(diff(&ast, &parsed), None);
+    }
+
+    #[test]
+    fn reporting_integers_too_large() {
+        let sess = RcRef::new(Session::new(string::String::from(
+            "def f: -9223372036854775809
+def g: 9223372036854775808",
+        )));
+        let lexer = Lexer::new(sess.clone());
+        Parser::new(lexer, sess.clone()).parse();
+        let errors: Vec<String> = sess
+            .borrow()
+            .handler
+            .errors()
+            .iter()
+            .map(|e| e.desc())
+            .collect();
+        assert_eq!(errors, vec!["Integer literal is too large for `int`"; 2]);
+    }
+
+    #[test]
+    fn allocating_unique_ids() {
+        let mut ids = NodeIds::new();
+        let first = ASTBuilder::new()
+            .def(def("f").arg("a").body(op("+", id("a"), id("a"))))
+            .build_with(&mut ids);
+        let second = ASTBuilder::new()
+            .def(def("g").body(call(id("f"), vec![int(1), int(2)])))
+            .build_with(&mut ids);
+        let mut visited = Ids::default();
+        visitor::visit_ast(&mut visited, &first);
+        let first_max = *visited.0.iter().max().unwrap();
+        visitor::visit_ast(&mut visited, &second);
+        let unique: HashSet<_> = visited.0.iter().collect();
+        assert_eq!(visited.0.len(), 27);
+        assert_eq!(unique.len(), visited.0.len());
+        assert_eq!(first.root.id, 0);
+        assert!(second.root.id > first_max);
+    }
+
+    #[test]
+    fn allocating_one_id_per_attribute_argument() {
+        let mut ids = NodeIds::new();
+        ASTBuilder::new()
+            .def(
+                def("f")
+                    .attr_args("x", vec![id_arg("a"), int_arg(1)])
+                    .body(id("f")),
+            )
+            .build_with(&mut ids);
+        // module, declaration, definition, its declaration and name,
+        // attribute, its name and arguments, body and its identifier
+        assert_eq!(ids.next_id(), 11);
+    }
+}
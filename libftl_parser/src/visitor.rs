@@ -1,6 +1,7 @@
 use crate::ast::*;
 // use crate::visitor_mut::MutPass;
 
+use ftl_session::symbol::Symbol;
 use ftl_source::{Pointer, Source};
 
 pub trait Pass<'ast, P: Pointer>: Sized {
@@ -24,14 +25,16 @@ pub trait Pass<'ast, P: Pointer>: Sized {
         walk_infix_def(self, node);
     }
 
-    fn visit_func_arg(&mut self, _node: &'ast FuncArg<P>) {
-        // todo walk, when its more than just an identifier
-        self.nop()
+    fn visit_prefix_def(&mut self, node: &'ast PrefixDef<P>) {
+        walk_prefix_def(self, node);
     }
 
-    fn visit_func_attr(&mut self, _node: &'ast FuncAttr<P>) {
-        // todo walk, when its more than just an identifier
-        self.nop()
+    fn visit_func_arg(&mut self, node: &'ast FuncArg<P>) {
+        walk_func_arg(self, node);
+    }
+
+    fn visit_func_attr(&mut self, node: &'ast FuncAttr<P>) {
+        walk_func_attr(self, node);
     }
 
     fn visit_expr(&mut self, node: &'ast Expr<P>) {
@@ -46,6 +49,14 @@ pub trait Pass<'ast, P: Pointer>: Sized {
         walk_infix_op_call(self, node);
     }
 
+    fn visit_prefix_op_call(&mut self, node: &'ast PrefixOpCall<P>) {
+        walk_prefix_op_call(self, node);
+    }
+
+    fn visit_section(&mut self, node: &'ast Section<P>) {
+        walk_section(self, node);
+    }
+
     fn visit_func_call(&mut self, node: &'ast FuncCall<P>) {
         walk_func_call(self, node);
     }
@@ -58,7 +69,19 @@ pub trait Pass<'ast, P: Pointer>: Sized {
         walk_lit(self, node);
     }
 
-    fn visit_int_lit(&mut self, _val: u64) {
+    fn visit_int_lit(&mut self, _val: i64) {
+        self.nop()
+    }
+
+    fn visit_float_lit(&mut self, _val: f64) {
+        self.nop()
+    }
+
+    fn visit_str_lit(&mut self, _val: Symbol) {
+        self.nop()
+    }
+
+    fn visit_char_lit(&mut self, _val: char) {
         self.nop()
     }
 
@@ -111,6 +134,9 @@ pub fn walk_top_level_decl<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
         TopLevelDeclKind::InfixDef(ref infix_def) => {
             v.visit_infix_def(infix_def);
         }
+        TopLevelDeclKind::PrefixDef(ref prefix_def) => {
+            v.visit_prefix_def(prefix_def);
+        }
         TopLevelDeclKind::FunctionDecl(ref func_decl) => {
             v.visit_func_decl(func_decl);
         }
@@ -152,14 +178,48 @@ pub fn walk_infix_def<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
     v: &mut P,
     node: &'ast InfixDef<Ptr>,
 ) {
-    // todo - for now skipping type
     // todo - what to do with the precedence
     v.visit_op(&node.op);
     v.visit_func_arg(&node.args.0);
     v.visit_func_arg(&node.args.1);
+    if let Some(ref ty) = node.ty {
+        v.visit_type(ty);
+    }
+    v.visit_expr(&node.body);
+}
+
+pub fn walk_prefix_def<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
+    v: &mut P,
+    node: &'ast PrefixDef<Ptr>,
+) {
+    v.visit_op(&node.op);
+    v.visit_func_arg(&node.arg);
+    if let Some(ref ty) = node.ty {
+        v.visit_type(ty);
+    }
     v.visit_expr(&node.body);
 }
 
+pub fn walk_func_arg<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(v: &mut P, node: &'ast FuncArg<Ptr>) {
+    v.visit_ident(&node.ident);
+    if let Some(ref ty) = node.ty {
+        v.visit_type(ty);
+    }
+}
+
+pub fn walk_func_attr<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
+    v: &mut P,
+    node: &'ast FuncAttr<Ptr>,
+) {
+    v.visit_ident(&node.ident);
+    for arg in &node.args {
+        match arg {
+            AttrArg::Lit(lit) => v.visit_lit(lit),
+            AttrArg::Ident(ident) => v.visit_ident(ident),
+        }
+    }
+}
+
 pub fn walk_expr<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(v: &mut P, node: &'ast Expr<Ptr>) {
     match node.kind {
         ExprKind::FunctionCall(ref call) => {
@@ -177,6 +237,12 @@ pub fn walk_expr<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(v: &mut P, node: &'ast
         ExprKind::InfixFuncCall(ref infix_call) => {
             v.visit_infix_func_call(infix_call);
         }
+        ExprKind::PrefixOpCall(ref prefix_op_call) => {
+            v.visit_prefix_op_call(prefix_op_call);
+        }
+        ExprKind::Section(ref section) => {
+            v.visit_section(section);
+        }
         ExprKind::Parenthesed(ref paren) => {
             v.visit_parenthesed(paren);
         }
@@ -205,6 +271,24 @@ pub fn walk_infix_op_call<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
     v.visit_expr(&node.rhs);
 }
 
+pub fn walk_prefix_op_call<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
+    v: &mut P,
+    node: &'ast PrefixOpCall<Ptr>,
+) {
+    v.visit_op(&node.op);
+    v.visit_expr(&node.expr);
+}
+
+pub fn walk_section<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(v: &mut P, node: &'ast Section<Ptr>) {
+    if let Some(ref lhs) = node.lhs {
+        v.visit_expr(lhs);
+    }
+    v.visit_op(&node.op);
+    if let Some(ref rhs) = node.rhs {
+        v.visit_expr(rhs);
+    }
+}
+
 pub fn walk_func_call<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
     v: &mut P,
     node: &'ast FuncCall<Ptr>,
@@ -218,6 +302,9 @@ pub fn walk_func_call<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
 pub fn walk_lit<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(v: &mut P, node: &'ast Lit<Ptr>) {
     match node.kind {
         LitKind::Int(val) => v.visit_int_lit(val),
+        LitKind::Float(val) => v.visit_float_lit(val),
+        LitKind::Str(ref val) => v.visit_str_lit(val.clone()),
+        LitKind::Char(val) => v.visit_char_lit(val),
     }
 }
 
@@ -300,7 +387,7 @@ pub fn walk_func_type<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
 //         <Self as Pass<P>>::visit_lit(self, node);
 //     }
 
-//     fn visit_int_lit(&mut self, val: &mut u64) {
+//     fn visit_int_lit(&mut self, val: &mut i64) {
 //         <Self as Pass<P>>::visit_int_lit(self, *val);
 //     }
 
@@ -324,3 +411,85 @@ pub fn walk_func_type<'ast, Ptr: Pointer, P: Pass<'ast, Ptr>>(
 //         <Self as Pass<P>>::visit_lit_type(self, node);
 //     }
 // }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use ftl_source::string;
+
+    use crate::test_utils::{parse, Ids};
+
+    /// Parses the source and gives the type to every
+    /// argument and infix definition, as the parser can't yet.
+    pub(crate) fn parse_typed(src: &str) -> AST<string::String> {
+        let mut ast = parse(src);
+        let mut id = 1000;
+        let mut ty = |span: &ftl_source::Span<string::Pointer>| {
+            id += 1;
+            Some(Type {
+                id,
+                kind: TypeKind::Literal(LitType::Int),
+                span: span.clone(),
+            })
+        };
+        for decl in &mut ast.root.decl {
+            match decl.kind {
+                TopLevelDeclKind::FunctionDef(ref mut def) => {
+                    for arg in &mut def.args {
+                        arg.ty = ty(&arg.span);
+                    }
+                }
+                TopLevelDeclKind::InfixDef(ref mut infix) => {
+                    infix.ty = ty(&infix.op.span);
+                    infix.args.0.ty = ty(&infix.args.0.span);
+                    infix.args.1.ty = ty(&infix.args.1.span);
+                }
+                TopLevelDeclKind::PrefixDef(ref mut prefix) => {
+                    prefix.ty = ty(&prefix.op.span);
+                    prefix.arg.ty = ty(&prefix.arg.span);
+                }
+                TopLevelDeclKind::FunctionDecl(_) => (),
+            }
+        }
+        ast
+    }
+
+    // Module
+    // decl: TopLevelDecl, FuncDecl, Ident, Type, FuncType,
+    //   Type, Type, FuncAttr, Ident
+    // infix: TopLevelDecl, InfixDef, Op, Type, FuncArg, Ident, Type,
+    //   FuncArg, Ident, Type, Expr, Ident
+    // def: TopLevelDecl, FuncDef, FuncDecl, Ident, FuncAttr, Ident,
+    //   FuncArg, Ident, Type, Expr, InfixOpCall, Op, Expr, Ident, Expr, Lit
+    // prefix: TopLevelDecl, PrefixDef, Op, Type, FuncArg, Ident, Type,
+    //   Expr, PrefixOpCall, Op, Expr, Ident
+    pub(crate) const SOURCE: &str = r#"
+decl f int [x]: int
+infix 5 + a b: a
+def g a [y]: a + 1
+prefix - a: -a
+"#;
+    pub(crate) const NODES: usize = 50;
+
+    #[test]
+    fn every_node_is_visited_once() {
+        let ast = parse_typed(SOURCE);
+        let mut ids = Ids::default();
+        visit_ast(&mut ids, &ast);
+        assert_eq!(ids.0.len(), NODES);
+        let unique: HashSet<NodeId> = ids.0.iter().cloned().collect();
+        assert_eq!(unique.len(), NODES);
+    }
+
+    #[test]
+    fn types_of_arguments_and_infixes_are_visited() {
+        let ast = parse_typed(SOURCE);
+        let mut ids = Ids::default();
+        visit_ast(&mut ids, &ast);
+        // one for each argument, infix and prefix definition
+        assert_eq!(ids.0.iter().filter(|id| **id > 1000).count(), 6);
+    }
+}
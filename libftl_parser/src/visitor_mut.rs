@@ -1,4 +1,5 @@
 use crate::ast::*;
+use ftl_session::symbol::Symbol;
 use ftl_source::{Pointer, Source};
 
 pub fn visit_ast_mut<'ast, S: Source, P: MutPass<'ast, S::Pointer>>(
@@ -29,14 +30,16 @@ pub trait MutPass<'ast, P: Pointer>: Sized {
         noop_infix_def(self, node);
     }
 
-    fn visit_func_arg(&mut self, _node: &'ast mut FuncArg<P>) {
-        // todo walk, when its more than just an identifier
-        self.nop()
+    fn visit_prefix_def(&mut self, node: &'ast mut PrefixDef<P>) {
+        noop_prefix_def(self, node);
     }
 
-    fn visit_func_attr(&mut self, _node: &'ast mut FuncAttr<P>) {
-        // todo walk, when its more than just an identifier
-        self.nop()
+    fn visit_func_arg(&mut self, node: &'ast mut FuncArg<P>) {
+        noop_func_arg(self, node);
+    }
+
+    fn visit_func_attr(&mut self, node: &'ast mut FuncAttr<P>) {
+        noop_func_attr(self, node);
     }
 
     fn visit_expr(&mut self, node: &'ast mut Expr<P>) {
@@ -51,6 +54,14 @@ pub trait MutPass<'ast, P: Pointer>: Sized {
         noop_infix_op_call(self, node);
     }
 
+    fn visit_prefix_op_call(&mut self, node: &'ast mut PrefixOpCall<P>) {
+        noop_prefix_op_call(self, node);
+    }
+
+    fn visit_section(&mut self, node: &'ast mut Section<P>) {
+        noop_section(self, node);
+    }
+
     fn visit_func_call(&mut self, node: &'ast mut FuncCall<P>) {
         noop_func_call(self, node);
     }
@@ -63,7 +74,19 @@ pub trait MutPass<'ast, P: Pointer>: Sized {
         noop_lit(self, node);
     }
 
-    fn visit_int_lit(&mut self, _val: &'ast mut u64) {
+    fn visit_int_lit(&mut self, _val: &'ast mut i64) {
+        self.nop()
+    }
+
+    fn visit_float_lit(&mut self, _val: &'ast mut f64) {
+        self.nop()
+    }
+
+    fn visit_str_lit(&mut self, _val: &'ast mut Symbol) {
+        self.nop()
+    }
+
+    fn visit_char_lit(&mut self, _val: &'ast mut char) {
         self.nop()
     }
 
@@ -114,6 +137,9 @@ pub fn noop_top_level_decl<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
         TopLevelDeclKind::InfixDef(ref mut infix_def) => {
             v.visit_infix_def(infix_def);
         }
+        TopLevelDeclKind::PrefixDef(ref mut prefix_def) => {
+            v.visit_prefix_def(prefix_def);
+        }
         TopLevelDeclKind::FunctionDecl(ref mut func_decl) => {
             v.visit_func_decl(func_decl);
         }
@@ -158,14 +184,51 @@ pub fn noop_infix_def<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
     v: &mut P,
     node: &'ast mut InfixDef<Ptr>,
 ) {
-    // todo - for now skipping type
     // todo - what to do with the precedence
     v.visit_op(&mut node.op);
     v.visit_func_arg(&mut node.args.0);
     v.visit_func_arg(&mut node.args.1);
+    if let Some(ref mut ty) = node.ty {
+        v.visit_type(ty);
+    }
+    v.visit_expr(&mut node.body);
+}
+
+pub fn noop_prefix_def<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
+    v: &mut P,
+    node: &'ast mut PrefixDef<Ptr>,
+) {
+    v.visit_op(&mut node.op);
+    v.visit_func_arg(&mut node.arg);
+    if let Some(ref mut ty) = node.ty {
+        v.visit_type(ty);
+    }
     v.visit_expr(&mut node.body);
 }
 
+pub fn noop_func_arg<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
+    v: &mut P,
+    node: &'ast mut FuncArg<Ptr>,
+) {
+    v.visit_ident(&mut node.ident);
+    if let Some(ref mut ty) = node.ty {
+        v.visit_type(ty);
+    }
+}
+
+pub fn noop_func_attr<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
+    v: &mut P,
+    node: &'ast mut FuncAttr<Ptr>,
+) {
+    v.visit_ident(&mut node.ident);
+    for arg in &mut node.args {
+        match arg {
+            AttrArg::Lit(lit) => v.visit_lit(lit),
+            AttrArg::Ident(ident) => v.visit_ident(ident),
+        }
+    }
+}
+
 pub fn noop_expr<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(v: &mut P, node: &'ast mut Expr<Ptr>) {
     match node.kind {
         ExprKind::FunctionCall(ref mut call) => {
@@ -181,6 +244,12 @@ pub fn noop_expr<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(v: &mut P, node: &'a
         ExprKind::InfixFuncCall(ref mut infix_call) => {
             v.visit_infix_func_call(infix_call);
         }
+        ExprKind::PrefixOpCall(ref mut prefix_op_call) => {
+            v.visit_prefix_op_call(prefix_op_call);
+        }
+        ExprKind::Section(ref mut section) => {
+            v.visit_section(section);
+        }
         ExprKind::Parenthesed(ref mut paren) => {
             v.visit_parenthesed(paren);
         }
@@ -215,6 +284,27 @@ pub fn noop_infix_op_call<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
     v.visit_expr(&mut node.rhs);
 }
 
+pub fn noop_prefix_op_call<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
+    v: &mut P,
+    node: &'ast mut PrefixOpCall<Ptr>,
+) {
+    v.visit_op(&mut node.op);
+    v.visit_expr(&mut node.expr);
+}
+
+pub fn noop_section<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
+    v: &mut P,
+    node: &'ast mut Section<Ptr>,
+) {
+    if let Some(ref mut lhs) = node.lhs {
+        v.visit_expr(lhs);
+    }
+    v.visit_op(&mut node.op);
+    if let Some(ref mut rhs) = node.rhs {
+        v.visit_expr(rhs);
+    }
+}
+
 pub fn noop_paren<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
     v: &mut P,
     node: &'ast mut Paren<Ptr>,
@@ -225,6 +315,9 @@ pub fn noop_paren<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
 pub fn noop_lit<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(v: &mut P, node: &'ast mut Lit<Ptr>) {
     match node.kind {
         LitKind::Int(ref mut val) => v.visit_int_lit(val),
+        LitKind::Float(ref mut val) => v.visit_float_lit(val),
+        LitKind::Str(ref mut val) => v.visit_str_lit(val),
+        LitKind::Char(ref mut val) => v.visit_char_lit(val),
     }
 }
 
@@ -245,3 +338,64 @@ pub fn noop_func_type<'ast, Ptr: Pointer, P: MutPass<'ast, Ptr>>(
     }
     v.visit_type(&mut node.ret);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use crate::test_utils::Ids;
+    use crate::visitor::tests::{parse_typed, NODES, SOURCE};
+
+    #[test]
+    fn every_node_is_visited_once() {
+        let mut ast = parse_typed(SOURCE);
+        let mut ids = Ids::default();
+        visit_ast_mut(&mut ids, &mut ast);
+        assert_eq!(ids.0.len(), NODES);
+        let unique: HashSet<NodeId> = ids.0.iter().cloned().collect();
+        assert_eq!(unique.len(), NODES);
+        assert_eq!(ids.0.iter().filter(|id| **id > 1000).count(), 6);
+    }
+
+    #[test]
+    fn types_are_visited_mutably() {
+        struct Voids;
+
+        impl<P: Pointer> MutPass<'_, P> for Voids {
+            fn visit_lit_type(&mut self, node: &mut LitType) {
+                *node = LitType::Void;
+            }
+        }
+
+        let mut ast = parse_typed(SOURCE);
+        visit_ast_mut(&mut Voids, &mut ast);
+        for decl in &ast.root.decl {
+            let types: Vec<&Type<_>> = match decl.kind {
+                TopLevelDeclKind::FunctionDef(ref def) => {
+                    def.args.iter().filter_map(|arg| arg.ty.as_ref()).collect()
+                }
+                TopLevelDeclKind::InfixDef(ref infix) => vec![
+                    infix.ty.as_ref().unwrap(),
+                    infix.args.0.ty.as_ref().unwrap(),
+                    infix.args.1.ty.as_ref().unwrap(),
+                ],
+                TopLevelDeclKind::PrefixDef(ref prefix) => {
+                    vec![prefix.ty.as_ref().unwrap(), prefix.arg.ty.as_ref().unwrap()]
+                }
+                TopLevelDeclKind::FunctionDecl(ref decl) => match decl.ty {
+                    Some(Type {
+                        kind: TypeKind::Function(ref func_t),
+                        ..
+                    }) => func_t.args.iter().chain(Some(&*func_t.ret)).collect(),
+                    _ => unreachable!(),
+                },
+            };
+            assert!(!types.is_empty());
+            for ty in types {
+                assert!(matches!(ty.kind, TypeKind::Literal(LitType::Void)));
+            }
+        }
+    }
+}
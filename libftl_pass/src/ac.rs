@@ -0,0 +1,212 @@
+use log::debug;
+
+use std::collections::HashMap;
+
+use ftl_error::LangError;
+use ftl_session::symbol::Symbol;
+use ftl_session::Session;
+
+use ftl_parser::ast::*;
+use ftl_parser::visitor::*;
+
+use ftl_source::Pointer;
+use ftl_source::Source;
+
+/// Error for the call given more arguments
+/// than the called function can take.
+pub struct TooManyArguments<P: Pointer> {
+    pub e_beg: P,
+    pub e_end: P,
+    pub func: Symbol,
+    pub takes: usize,
+    pub given: usize,
+}
+
+impl<P: Pointer> LangError for TooManyArguments<P> {
+    type Ptr = P;
+
+    fn desc(&self) -> String {
+        format!(
+            "Function `{}` takes {} arguments but {} were given",
+            self.func, self.takes, self.given
+        )
+    }
+
+    fn begin(&self) -> &Self::Ptr {
+        &self.e_beg
+    }
+
+    fn end(&self) -> &Self::Ptr {
+        &self.e_end
+    }
+}
+
+/// Checks the number of arguments the top level functions are called with.
+///
+/// Function called with fewer arguments than it has parameters is
+/// partially applied and results in the function taking the rest of them.
+/// Called with more it has to return a function taking the remaining ones,
+/// so only the calls of the functions with declared types can be
+/// reported, as the return type of the others is not known here.
+/// Reported errors are not fatal.
+pub struct ArityCheck<'a, S: Source> {
+    sess: &'a mut Session<S>,
+
+    /// Maps function names to the most arguments they can be called with,
+    /// counting the ones taken by the functions they return.
+    arity: HashMap<Symbol, usize>,
+
+    /// Arguments of the definition being visited,
+    /// which shadow the top level functions.
+    args: Vec<Symbol>,
+}
+
+impl<'a, S: Source> ArityCheck<'a, S> {
+    /// Returns new ArityCheck pass ready to be run on the syntax tree.
+    pub fn new(sess: &'a mut Session<S>) -> Self {
+        debug!("Running AC Pass");
+        Self {
+            sess,
+            arity: HashMap::new(),
+            args: Vec::new(),
+        }
+    }
+
+    fn max_args<P: Pointer>(ty: &Type<P>) -> usize {
+        match ty.kind {
+            TypeKind::Function(ref func_t) => func_t.args.len() + Self::max_args(&func_t.ret),
+            TypeKind::Literal(_) => 0,
+        }
+    }
+
+    fn check_call<P>(&mut self, ident: &Ident<P>, args: &[&Expr<P>])
+    where
+        P: 'static + Pointer,
+        S: Source<Pointer = P>,
+    {
+        if self.args.contains(&ident.symbol) {
+            return;
+        }
+        let takes = match self.arity.get(&ident.symbol) {
+            Some(takes) => *takes,
+            None => return,
+        };
+        if args.len() > takes {
+            self.sess.err(Box::new(TooManyArguments {
+                e_beg: args[takes].span.beg.clone(),
+                e_end: args[args.len() - 1].span.end.clone(),
+                func: ident.symbol.clone(),
+                takes,
+                given: args.len(),
+            }));
+        }
+    }
+}
+
+impl<'a, 'ast, S, P> Pass<'ast, P> for ArityCheck<'a, S>
+where
+    P: 'static + Pointer,
+    S: Source<Pointer = P>,
+{
+    fn visit_module(&mut self, node: &'ast Module<P>) {
+        self.arity.clear();
+        for decl in &node.decl {
+            let decl = match decl.kind {
+                TopLevelDeclKind::FunctionDecl(ref decl) => decl,
+                TopLevelDeclKind::FunctionDef(ref def) => &def.decl,
+                _ => continue,
+            };
+            if let Some(ref ty) = decl.ty {
+                self.arity
+                    .insert(decl.ident.symbol.clone(), Self::max_args(ty));
+            }
+        }
+        walk_module(self, node);
+    }
+
+    fn visit_func_def(&mut self, node: &'ast FuncDef<P>) {
+        self.args = node
+            .args
+            .iter()
+            .map(|arg| arg.ident.symbol.clone())
+            .collect();
+        self.visit_expr(&node.body);
+        self.args.clear();
+    }
+
+    fn visit_infix_def(&mut self, node: &'ast InfixDef<P>) {
+        self.args = vec![
+            node.args.0.ident.symbol.clone(),
+            node.args.1.ident.symbol.clone(),
+        ];
+        self.visit_expr(&node.body);
+        self.args.clear();
+    }
+
+    fn visit_prefix_def(&mut self, node: &'ast PrefixDef<P>) {
+        self.args = vec![node.arg.ident.symbol.clone()];
+        self.visit_expr(&node.body);
+        self.args.clear();
+    }
+
+    fn visit_func_call(&mut self, node: &'ast FuncCall<P>) {
+        if let ExprKind::Identifier(ref ident) = node.lhs.kind {
+            self.check_call(ident, &node.args.iter().collect::<Vec<_>>());
+        }
+        walk_func_call(self, node);
+    }
+
+    fn visit_infix_func_call(&mut self, node: &'ast InfixFuncCall<P>) {
+        self.check_call(&node.ident, &[&node.lhs, &node.rhs]);
+        walk_infix_func_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_lexer::Lexer;
+    use ftl_parser::Parser;
+    use ftl_source::string;
+    use ftl_utility::RcRef;
+
+    fn errors(src: &str) -> Vec<String> {
+        let sess = RcRef::new(Session::new(string::String::from(src)));
+        let mut parser = Parser::new(Lexer::new(sess.clone()), sess.clone());
+        let ast = parser.parse();
+        let mut sess = sess.borrow_mut();
+        visit_ast(&mut ArityCheck::new(&mut sess), &ast);
+        sess.handler.errors().iter().map(|e| e.desc()).collect()
+    }
+
+    #[test]
+    fn partial_application_is_allowed() {
+        assert!(errors("decl add int int: int\ndef inc: @add 1\ndef zero: @add").is_empty());
+        // Return type of the functions without declarations is not known.
+        assert!(errors("def first a b: a\ndef f: @first 1 2 3").is_empty());
+    }
+
+    #[test]
+    fn calls_with_too_many_arguments() {
+        assert_eq!(
+            errors("decl add int int: int\ndef f: @add 1 2 3 4"),
+            vec!["Function `add` takes 2 arguments but 4 were given"]
+        );
+        assert_eq!(
+            errors("decl neg int: int\ndef f: 1 `neg 2"),
+            vec!["Function `neg` takes 1 arguments but 2 were given"]
+        );
+        // Returned function takes the rest of the arguments.
+        assert!(errors("decl adder int: (int) int\ndef f: @adder 1 2").is_empty());
+    }
+
+    #[test]
+    fn arguments_shadow_functions() {
+        assert!(errors("decl g: int\ndef f g: @g 1 2").is_empty());
+        assert_eq!(
+            errors("decl g: int\ndef f x: @g 1"),
+            vec!["Function `g` takes 0 arguments but 1 were given"]
+        );
+    }
+}
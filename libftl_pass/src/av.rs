@@ -0,0 +1,268 @@
+use log::debug;
+
+use ftl_error::LangError;
+use ftl_session::symbol::Symbol;
+use ftl_session::Session;
+
+use ftl_parser::ast::*;
+use ftl_parser::visitor::*;
+
+use ftl_source::Pointer;
+use ftl_source::Source;
+
+/// Declaration the attribute is written on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Target {
+    FuncDecl,
+    FuncDef,
+}
+
+impl Target {
+    fn describe(self) -> &'static str {
+        match self {
+            Target::FuncDecl => "a function declaration",
+            Target::FuncDef => "a function definition",
+        }
+    }
+}
+
+/// Arguments the attribute takes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Args {
+    None,
+    /// At most one identifier, being one of the given.
+    OptionalIdent(&'static [&'static str]),
+    /// Exactly one string literal.
+    Str,
+}
+
+impl Args {
+    fn accepts<P: Pointer>(self, args: &[AttrArg<P>]) -> bool {
+        match (self, args) {
+            (Args::None, []) => true,
+            (Args::OptionalIdent(_), []) => true,
+            (Args::OptionalIdent(values), [AttrArg::Ident(ident)]) => {
+                values.iter().any(|value| ident.symbol == *value)
+            }
+            (Args::Str, [AttrArg::Lit(lit)]) => matches!(lit.kind, LitKind::Str(_)),
+            _ => false,
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Args::None => String::from("no arguments"),
+            Args::OptionalIdent(values) => {
+                let values: Vec<_> = values.iter().map(|v| format!("`{}`", v)).collect();
+                format!("an optional argument, one of {}", values.join(", "))
+            }
+            Args::Str => String::from("a single string argument"),
+        }
+    }
+}
+
+/// Attribute the compiler knows about.
+#[derive(Debug)]
+pub struct AttrSpec {
+    pub name: &'static str,
+    pub targets: &'static [Target],
+    pub args: Args,
+}
+
+const FUNCTIONS: &[Target] = &[Target::FuncDecl, Target::FuncDef];
+
+/// Every known attribute, except for the
+/// short `lang_<item>` form of the `lang` attribute.
+pub const KNOWN_ATTRS: &[AttrSpec] = &[
+    AttrSpec {
+        name: "inline",
+        targets: FUNCTIONS,
+        args: Args::OptionalIdent(&["always", "never"]),
+    },
+    AttrSpec {
+        name: "lang",
+        targets: FUNCTIONS,
+        args: Args::Str,
+    },
+];
+
+/// Short form of the lang attribute, `lang_add` meaning `lang "add"`.
+const LANG_SHORT: AttrSpec = AttrSpec {
+    name: "lang_",
+    targets: FUNCTIONS,
+    args: Args::None,
+};
+
+/// Returns the known attribute with the given name.
+pub fn lookup(name: &str) -> Option<&'static AttrSpec> {
+    KNOWN_ATTRS
+        .iter()
+        .find(|spec| spec.name == name)
+        .or_else(|| match name.strip_prefix(LANG_SHORT.name) {
+            Some(item) if !item.is_empty() => Some(&LANG_SHORT),
+            _ => None,
+        })
+}
+
+pub enum AttrErrorKind {
+    Unknown,
+    WrongTarget(Target),
+    WrongArgs(Args),
+}
+
+/// Error for the attribute which is not known
+/// or is used in the way it cannot be.
+pub struct AttrError<P: Pointer> {
+    pub kind: AttrErrorKind,
+    pub attr: Symbol,
+    pub beg: P,
+    pub end: P,
+}
+
+impl<P: Pointer> LangError for AttrError<P> {
+    type Ptr = P;
+
+    fn desc(&self) -> String {
+        match self.kind {
+            AttrErrorKind::Unknown => format!("Unknown attribute `{}`", self.attr),
+            AttrErrorKind::WrongTarget(target) => format!(
+                "Attribute `{}` cannot be used on {}",
+                self.attr,
+                target.describe()
+            ),
+            AttrErrorKind::WrongArgs(args) => {
+                format!("Attribute `{}` takes {}", self.attr, args.describe())
+            }
+        }
+    }
+
+    fn begin(&self) -> &Self::Ptr {
+        &self.beg
+    }
+
+    fn end(&self) -> &Self::Ptr {
+        &self.end
+    }
+}
+
+/// Checks that every attribute is one of the
+/// [`KNOWN_ATTRS`](constant.KNOWN_ATTRS.html), is used on
+/// the declaration it can be and has the arguments it takes.
+/// Reported errors are not fatal.
+pub struct AttrValidation<'a, S: Source> {
+    sess: &'a mut Session<S>,
+}
+
+impl<'a, S: Source> AttrValidation<'a, S> {
+    /// Returns new AttrValidation pass ready to be run on the syntax tree.
+    pub fn new(sess: &'a mut Session<S>) -> Self {
+        debug!("Running AV Pass");
+        Self { sess }
+    }
+
+    fn check<P>(&mut self, attrs: &[FuncAttr<P>], target: Target)
+    where
+        P: 'static + Pointer,
+        S: Source<Pointer = P>,
+    {
+        for attr in attrs {
+            let kind = match lookup(&attr.ident.symbol) {
+                None => AttrErrorKind::Unknown,
+                Some(spec) if !spec.targets.contains(&target) => AttrErrorKind::WrongTarget(target),
+                Some(spec) if !spec.args.accepts(&attr.args) => AttrErrorKind::WrongArgs(spec.args),
+                Some(_) => continue,
+            };
+            self.sess.err(Box::new(AttrError {
+                kind,
+                attr: attr.ident.symbol.clone(),
+                beg: attr.span.beg.clone(),
+                end: attr.span.end.clone(),
+            }));
+        }
+    }
+}
+
+impl<'a, 'ast, S, P> Pass<'ast, P> for AttrValidation<'a, S>
+where
+    P: 'static + Pointer,
+    S: Source<Pointer = P>,
+{
+    fn visit_func_decl(&mut self, node: &'ast FuncDecl<P>) {
+        self.check(&node.attrs, Target::FuncDecl);
+    }
+
+    fn visit_func_def(&mut self, node: &'ast FuncDef<P>) {
+        self.check(&node.decl.attrs, Target::FuncDef);
+    }
+
+    fn visit_infix_def(&mut self, _node: &'ast InfixDef<P>) {}
+
+    fn visit_prefix_def(&mut self, _node: &'ast PrefixDef<P>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_lexer::Lexer;
+    use ftl_parser::Parser;
+    use ftl_source::string;
+    use ftl_utility::RcRef;
+
+    fn errors(src: &str) -> Vec<String> {
+        let sess = RcRef::new(Session::new(string::String::from(src)));
+        let mut parser = Parser::new(Lexer::new(sess.clone()), sess.clone());
+        let ast = parser.parse();
+        assert_eq!(sess.borrow().handler.error_msg(), None);
+        let mut sess = sess.borrow_mut();
+        visit_ast(&mut AttrValidation::new(&mut sess), &ast);
+        sess.handler.errors().iter().map(|e| e.desc()).collect()
+    }
+
+    #[test]
+    fn known_attributes_are_accepted() {
+        assert!(errors(
+            "decl add int int [lang_add inline] : int\n\
+             def f [inline(always)]: 1\n\
+             decl g [lang \"sub\" inline(never)]: int"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn unknown_attributes() {
+        assert_eq!(
+            errors("def f [inlined lang_]: 1\ndecl g [remove]: int"),
+            vec![
+                "Unknown attribute `inlined`",
+                "Unknown attribute `lang_`",
+                "Unknown attribute `remove`",
+            ]
+        );
+    }
+
+    #[test]
+    fn misused_attributes() {
+        assert_eq!(
+            errors(
+                "def f [inline(sometimes) lang]: 1\n\
+                 decl g [lang(add) lang_add 1 inline \"x\"]: int"
+            ),
+            vec![
+                "Attribute `inline` takes an optional argument, one of `always`, `never`",
+                "Attribute `lang` takes a single string argument",
+                "Attribute `lang` takes a single string argument",
+                "Attribute `lang_add` takes no arguments",
+                "Attribute `inline` takes an optional argument, one of `always`, `never`",
+            ]
+        );
+    }
+
+    #[test]
+    fn registry_lookup() {
+        assert_eq!(lookup("inline").unwrap().name, "inline");
+        assert_eq!(lookup("lang_fadd").unwrap().args, Args::None);
+        assert!(lookup("lang_").is_none());
+        assert!(lookup("debug").is_none());
+    }
+}
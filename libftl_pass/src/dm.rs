@@ -2,6 +2,8 @@ use log::debug;
 
 use std::collections::HashMap;
 
+use ftl_session::symbol::Symbol;
+
 use ftl_parser::ast::*;
 use ftl_parser::visitor::*;
 use ftl_parser::visitor_mut::*;
@@ -21,37 +23,40 @@ impl<'a, P: Pointer> MutPass<'a, P> for DeclarationMerge {
     fn visit_module(&mut self, node: &'a mut Module<P>) {
         let mut merger = DefMerger::new();
         merger.visit_module(node);
-        let decls_to_rem = merger.get();
-        let mut remover = DeclRemover::new(decls_to_rem.values().map(|x| x.id).collect());
+        let mut remover = DeclRemover::new(merger.get());
         remover.visit_module(node);
     }
 }
 
 struct DefMerger<P: Pointer> {
-    decls: HashMap<String, FuncDecl<P>>,
+    decls: HashMap<Symbol, FuncDecl<P>>,
+    /// Declarations merged into the definitions, to be removed.
+    merged: Vec<NodeId>,
 }
 
 impl<P: Pointer> DefMerger<P> {
     pub fn new() -> Self {
         Self {
             decls: HashMap::new(),
+            merged: Vec::new(),
         }
     }
 
     fn merge(decl: &FuncDecl<P>, def: &mut FuncDef<P>) {
         def.decl.attrs.extend_from_slice(&decl.attrs);
-        def.decl
-            .attrs
-            .sort_by(|a, b| a.ident.symbol.cmp(&b.ident.symbol));
+        def.decl.attrs.sort_by_key(|attr| attr.ident.symbol.clone());
         def.decl
             .attrs
             .dedup_by(|a, b| a.ident.symbol == b.ident.symbol);
 
         def.decl.ty = decl.ty.clone();
+        if def.decl.doc.is_none() {
+            def.decl.doc = decl.doc.clone();
+        }
     }
 
-    fn get(self) -> HashMap<String, FuncDecl<P>> {
-        self.decls
+    fn get(self) -> Vec<NodeId> {
+        self.merged
     }
 }
 
@@ -61,27 +66,12 @@ impl<'a, P: Pointer> MutPass<'a, P> for DefMerger<P> {
         decls.visit_module(node);
         self.decls = decls.get();
         noop_module(self, node);
-        self.decls.retain(|_, v| {
-            for attr in &v.attrs {
-                if attr.ident.symbol == "remove" {
-                    return true;
-                }
-            }
-            false
-        });
     }
 
     fn visit_func_def(&mut self, node: &'a mut FuncDef<P>) {
-        if let Some(ref mut decl) = self.decls.get_mut(&node.decl.ident.symbol) {
-            Self::merge(&decl, node);
-            decl.attrs.push(FuncAttr {
-                id: 0,
-                ident: Ident {
-                    id: 0,
-                    symbol: "remove".to_owned(),
-                    span: decl.ident.span.clone(),
-                },
-            })
+        if let Some(decl) = self.decls.get(&node.decl.ident.symbol) {
+            Self::merge(decl, node);
+            self.merged.push(decl.id);
         }
     }
 }
@@ -90,7 +80,7 @@ impl<'a, P: Pointer> MutPass<'a, P> for DefMerger<P> {
 /// map of top level function declaration identifier symbols to their
 /// respective nodes copies.
 struct DeclCollector<P: Pointer> {
-    decls: HashMap<String, FuncDecl<P>>,
+    decls: HashMap<Symbol, FuncDecl<P>>,
     run_already: bool,
 }
 
@@ -102,7 +92,7 @@ impl<P: Pointer> DeclCollector<P> {
         }
     }
 
-    pub fn get(self) -> HashMap<String, FuncDecl<P>> {
+    pub fn get(self) -> HashMap<Symbol, FuncDecl<P>> {
         if !self.run_already {
             panic!(
                 "DeclCollector needs to visit a syntax tree before 
@@ -0,0 +1,566 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::io::Write;
+
+use ftl_session::symbol::Symbol;
+
+use ftl_parser::ast::*;
+use ftl_parser::visitor::*;
+
+use ftl_source::Pointer;
+
+use crate::fmt::{attr_repr, type_repr};
+
+/// Output format of the generated documentation.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+/// Top level item links can point to.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Link {
+    Func(Symbol),
+    Infix(Symbol),
+    Prefix(Symbol),
+}
+
+impl Link {
+    fn name(&self) -> &Symbol {
+        match self {
+            Link::Func(name) | Link::Infix(name) | Link::Prefix(name) => name,
+        }
+    }
+
+    /// Returns the anchor of the item's documentation.
+    /// Operator characters are written as their hex codes
+    /// so the anchor is valid both in HTML and in Markdown links.
+    fn anchor(&self) -> String {
+        let op = |kind: &str, op: &Symbol| {
+            let codes: Vec<_> = op.chars().map(|ch| format!("{:x}", ch as u32)).collect();
+            format!("{}.{}", kind, codes.join("-"))
+        };
+        match self {
+            Link::Func(name) => format!("fn.{}", name),
+            Link::Infix(name) => op("infix", name),
+            Link::Prefix(name) => op("prefix", name),
+        }
+    }
+}
+
+/// Documentation of the function merged from all of its declarations.
+#[derive(Default)]
+struct Function {
+    ty: Option<String>,
+    /// Arguments of the definition, if there is one.
+    args: Option<Vec<Symbol>>,
+    attrs: BTreeSet<String>,
+    doc: Option<String>,
+    uses: BTreeSet<Link>,
+}
+
+/// Documentation of the infix or prefix operator.
+struct Operator {
+    precedence: Option<usize>,
+    args: Vec<Symbol>,
+    ty: Option<String>,
+    doc: Option<String>,
+    uses: BTreeSet<Link>,
+}
+
+impl Operator {
+    fn new<P: Pointer>(
+        precedence: Option<usize>,
+        args: Vec<Symbol>,
+        ty: &Option<Type<P>>,
+        doc: &Option<String>,
+    ) -> Self {
+        Self {
+            precedence,
+            args,
+            ty: ty.as_ref().map(type_repr),
+            doc: doc.clone(),
+            uses: BTreeSet::new(),
+        }
+    }
+}
+
+/// Generates the documentation of the visited module.
+///
+/// Every function, infix and prefix operator is listed with its
+/// signature, attributes, precedence and the text of its doc comments.
+/// Names written in backticks in the doc text and the top level
+/// items used in the definition's body link to their documentation.
+/// Declarations and the definition of the same function are
+/// documented together, so the declaration merge pass is not needed.
+pub struct DocGen {
+    format: Format,
+    title: String,
+    res: String,
+
+    funcs: BTreeMap<Symbol, Function>,
+    infixes: BTreeMap<Symbol, Operator>,
+    prefixes: BTreeMap<Symbol, Operator>,
+
+    /// Item which definition is visited with its arguments.
+    scope: Option<(Link, Vec<Symbol>)>,
+
+    run_already: bool,
+}
+
+impl DocGen {
+    /// Creates new pass generating documentation
+    /// in the given format ready to be run on a syntax tree.
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            title: String::from("Module"),
+            res: String::new(),
+            funcs: BTreeMap::new(),
+            infixes: BTreeMap::new(),
+            prefixes: BTreeMap::new(),
+            scope: None,
+            run_already: false,
+        }
+    }
+
+    /// Sets the title of the documentation, `Module` by default.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_owned();
+        self
+    }
+
+    /// Returns the documentation.
+    /// If the pass hasn't visited any syntax tree yet it panics.
+    pub fn get(&self) -> &str {
+        if !self.run_already {
+            panic!("DocGen needs to visit a syntax tree before returning its output.")
+        }
+        &self.res
+    }
+
+    /// Writes the documentation to the writer.
+    /// If the pass hasn't visited any syntax tree yet it panics.
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        write!(w, "{}", self.get())
+    }
+
+    fn collect_items<P: Pointer>(&mut self, node: &Module<P>) {
+        for decl in &node.decl {
+            match decl.kind {
+                TopLevelDeclKind::FunctionDecl(ref decl) => self.add_func(decl, None),
+                TopLevelDeclKind::FunctionDef(ref def) => {
+                    let args = def
+                        .args
+                        .iter()
+                        .map(|arg| arg.ident.symbol.clone())
+                        .collect();
+                    self.add_func(&def.decl, Some(args));
+                }
+                TopLevelDeclKind::InfixDef(ref infix) => {
+                    let args = vec![
+                        infix.args.0.ident.symbol.clone(),
+                        infix.args.1.ident.symbol.clone(),
+                    ];
+                    let op = Operator::new(Some(infix.precedence), args, &infix.ty, &infix.doc);
+                    self.infixes.insert(infix.op.symbol.clone(), op);
+                }
+                TopLevelDeclKind::PrefixDef(ref prefix) => {
+                    let args = vec![prefix.arg.ident.symbol.clone()];
+                    let op = Operator::new(prefix.precedence, args, &prefix.ty, &prefix.doc);
+                    self.prefixes.insert(prefix.op.symbol.clone(), op);
+                }
+            }
+        }
+    }
+
+    fn add_func<P: Pointer>(&mut self, decl: &FuncDecl<P>, args: Option<Vec<Symbol>>) {
+        let func = self.funcs.entry(decl.ident.symbol.clone()).or_default();
+        if func.ty.is_none() {
+            func.ty = decl.ty.as_ref().map(type_repr);
+        }
+        if args.is_some() {
+            func.args = args;
+        }
+        func.attrs.extend(decl.attrs.iter().map(attr_repr));
+        if func.doc.is_none() {
+            func.doc = decl.doc.clone();
+        }
+    }
+
+    /// Returns the link to the item with the given name, functions
+    /// first as the operator names cannot be valid identifiers.
+    fn resolve(&self, name: &str) -> Option<Link> {
+        let name = Symbol::new(name);
+        if self.funcs.contains_key(&name) {
+            Some(Link::Func(name))
+        } else if self.infixes.contains_key(&name) {
+            Some(Link::Infix(name))
+        } else if self.prefixes.contains_key(&name) {
+            Some(Link::Prefix(name))
+        } else {
+            None
+        }
+    }
+
+    fn add_use(&mut self, link: Link) {
+        let item = match &self.scope {
+            Some((item, _)) => item,
+            None => return,
+        };
+        let uses = match item {
+            Link::Func(name) => self.funcs.get_mut(name).map(|func| &mut func.uses),
+            Link::Infix(name) => self.infixes.get_mut(name).map(|op| &mut op.uses),
+            Link::Prefix(name) => self.prefixes.get_mut(name).map(|op| &mut op.uses),
+        };
+        if let Some(uses) = uses {
+            uses.insert(link);
+        }
+    }
+
+    fn render(&mut self) {
+        let mut res = self.heading(1, &escape(self.format, &self.title), None);
+        if !self.funcs.is_empty() {
+            res += &self.heading(2, "Functions", None);
+        }
+        for (name, func) in &self.funcs {
+            let mut sig = Vec::new();
+            if let Some(ref ty) = func.ty {
+                sig.push(format!("decl {}: {}", name, ty));
+            }
+            if let Some(ref args) = func.args {
+                sig.push(format!("def {}{}", name, strfy_args(args)));
+            }
+            res += &self.item(Link::Func(name.clone()), &sig.join("\n"));
+            if !func.attrs.is_empty() {
+                let attrs: Vec<_> = func.attrs.iter().map(|attr| self.code(attr)).collect();
+                res += &self.paragraph(&format!("Attributes: {}", attrs.join(", ")));
+            }
+            res += &self.item_doc(&func.doc, &func.uses);
+        }
+        let ops = [
+            ("Infix operators", "infix", &self.infixes),
+            ("Prefix operators", "prefix", &self.prefixes),
+        ];
+        for (title, kind, ops) in ops.iter() {
+            if !ops.is_empty() {
+                res += &self.heading(2, title, None);
+            }
+            for (name, op) in ops.iter() {
+                let mut sig = kind.to_string();
+                if let Some(prec) = op.precedence {
+                    sig += &format!(" {}", prec);
+                }
+                sig += &format!(" {}{}", name, strfy_args(&op.args));
+                if let Some(ref ty) = op.ty {
+                    sig += &format!(": {}", ty);
+                }
+                let link = if *kind == "infix" {
+                    Link::Infix(name.clone())
+                } else {
+                    Link::Prefix(name.clone())
+                };
+                res += &self.item(link, &sig);
+                res += &self.paragraph(&match op.precedence {
+                    Some(prec) => format!("Precedence: {}", prec),
+                    None => String::from("Precedence: binds tighter than any infix operator"),
+                });
+                res += &self.item_doc(&op.doc, &op.uses);
+            }
+        }
+        self.res = match self.format {
+            Format::Markdown => res,
+            Format::Html => format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                 <title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+                escape_html(&self.title),
+                res
+            ),
+        };
+    }
+
+    /// Returns the heading with the item's name and its signature.
+    fn item(&self, link: Link, sig: &str) -> String {
+        let anchor = link.anchor();
+        let mut res = self.heading(3, &self.code(link.name()), Some(&anchor));
+        res += &match self.format {
+            Format::Markdown => format!("```ftl\n{}\n```\n\n", sig),
+            Format::Html => format!("<pre><code>{}</code></pre>\n", escape_html(sig)),
+        };
+        res
+    }
+
+    /// Returns the doc text and the links to the used items.
+    fn item_doc(&self, doc: &Option<String>, uses: &BTreeSet<Link>) -> String {
+        let mut res = String::new();
+        if let Some(ref doc) = doc {
+            for par in doc.split("\n\n") {
+                res += &self.paragraph(&self.link_names(par));
+            }
+        }
+        if !uses.is_empty() {
+            let links: Vec<_> = uses.iter().map(|link| self.link(link)).collect();
+            res += &self.paragraph(&format!("Uses: {}", links.join(", ")));
+        }
+        res
+    }
+
+    /// Replaces code spans naming the documented items with
+    /// the links to them, escaping the rest of the text for HTML.
+    fn link_names(&self, text: &str) -> String {
+        let mut res = String::new();
+        for (i, part) in text.split('`').enumerate() {
+            if i % 2 == 0 {
+                res += &escape(self.format, part);
+            } else {
+                res += &match self.resolve(part) {
+                    Some(link) => self.link(&link),
+                    None => self.code(part),
+                };
+            }
+        }
+        res
+    }
+
+    fn link(&self, link: &Link) -> String {
+        let name = self.code(link.name());
+        match self.format {
+            Format::Markdown => format!("[{}](#{})", name, link.anchor()),
+            Format::Html => format!("<a href=\"#{}\">{}</a>", link.anchor(), name),
+        }
+    }
+
+    fn code(&self, text: &str) -> String {
+        match self.format {
+            Format::Markdown => format!("`{}`", text),
+            Format::Html => format!("<code>{}</code>", escape_html(text)),
+        }
+    }
+
+    /// Returns the heading, its text is expected to be already escaped.
+    fn heading(&self, level: usize, text: &str, anchor: Option<&str>) -> String {
+        match self.format {
+            Format::Markdown => {
+                let anchor = anchor.map_or(String::new(), |a| format!("<a id=\"{}\"></a>\n", a));
+                format!("{}{} {}\n\n", anchor, "#".repeat(level), text)
+            }
+            Format::Html => {
+                let id = anchor.map_or(String::new(), |a| format!(" id=\"{}\"", a));
+                format!("<h{0}{1}>{2}</h{0}>\n", level, id, text)
+            }
+        }
+    }
+
+    /// Returns the paragraph, its text is expected to be already escaped.
+    fn paragraph(&self, text: &str) -> String {
+        match self.format {
+            Format::Markdown => format!("{}\n\n", text),
+            Format::Html => format!("<p>{}</p>\n", text),
+        }
+    }
+}
+
+fn strfy_args(args: &[Symbol]) -> String {
+    args.iter().map(|arg| format!(" {}", arg)).collect()
+}
+
+/// Escapes the text to be written in the given format,
+/// Markdown text is left as it is.
+fn escape(format: Format, s: &str) -> String {
+    match format {
+        Format::Markdown => s.to_owned(),
+        Format::Html => escape_html(s),
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl<P: Pointer> Pass<'_, P> for DocGen {
+    fn visit_module(&mut self, node: &Module<P>) {
+        self.run_already = true;
+        self.funcs.clear();
+        self.infixes.clear();
+        self.prefixes.clear();
+        self.collect_items(node);
+        walk_module(self, node);
+        self.render();
+    }
+
+    fn visit_func_decl(&mut self, _node: &FuncDecl<P>) {}
+
+    fn visit_func_def(&mut self, node: &FuncDef<P>) {
+        let args = node
+            .args
+            .iter()
+            .map(|arg| arg.ident.symbol.clone())
+            .collect();
+        self.scope = Some((Link::Func(node.decl.ident.symbol.clone()), args));
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_infix_def(&mut self, node: &InfixDef<P>) {
+        let args = vec![
+            node.args.0.ident.symbol.clone(),
+            node.args.1.ident.symbol.clone(),
+        ];
+        self.scope = Some((Link::Infix(node.op.symbol.clone()), args));
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_prefix_def(&mut self, node: &PrefixDef<P>) {
+        let args = vec![node.arg.ident.symbol.clone()];
+        self.scope = Some((Link::Prefix(node.op.symbol.clone()), args));
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_ident(&mut self, node: &Ident<P>) {
+        let shadowed = self
+            .scope
+            .as_ref()
+            .is_some_and(|(_, args)| args.contains(&node.symbol));
+        if !shadowed && self.funcs.contains_key(&node.symbol) {
+            self.add_use(Link::Func(node.symbol.clone()));
+        }
+    }
+
+    fn visit_infix_op_call(&mut self, node: &InfixOpCall<P>) {
+        if self.infixes.contains_key(&node.op.symbol) {
+            self.add_use(Link::Infix(node.op.symbol.clone()));
+        }
+        walk_infix_op_call(self, node);
+    }
+
+    fn visit_section(&mut self, node: &Section<P>) {
+        if self.infixes.contains_key(&node.op.symbol) {
+            self.add_use(Link::Infix(node.op.symbol.clone()));
+        }
+        walk_section(self, node);
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &PrefixOpCall<P>) {
+        // Without the reassociation pass right sections
+        // are parsed as the prefix calls.
+        if self.prefixes.contains_key(&node.op.symbol) {
+            self.add_use(Link::Prefix(node.op.symbol.clone()));
+        } else if self.infixes.contains_key(&node.op.symbol) {
+            self.add_use(Link::Infix(node.op.symbol.clone()));
+        }
+        walk_prefix_op_call(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_parser::test_utils::parse;
+
+    const SOURCE: &str = "## Adds two numbers, see `+`.
+decl add int int [lang_add] : int
+def add a b [inline]: a
+## Adds with `add`.
+##
+## Works on `int` only.
+infix 5 + a b: @add a b
+prefix <- x: x
+def inc x: x + 1
+def shadow add: @add 1
+";
+
+    fn generate(src: &str, format: Format) -> String {
+        let ast = parse(src);
+        let mut gen = DocGen::new(format).title("test");
+        visit_ast(&mut gen, &ast);
+        gen.get().to_owned()
+    }
+
+    #[test]
+    fn markdown_documentation() {
+        assert_eq!(
+            generate(SOURCE, Format::Markdown),
+            "# test
+
+## Functions
+
+<a id=\"fn.add\"></a>
+### `add`
+
+```ftl
+decl add: (int int) int
+def add a b
+```
+
+Attributes: `inline`, `lang_add`
+
+Adds two numbers, see [`+`](#infix.2b).
+
+<a id=\"fn.inc\"></a>
+### `inc`
+
+```ftl
+def inc x
+```
+
+Uses: [`+`](#infix.2b)
+
+<a id=\"fn.shadow\"></a>
+### `shadow`
+
+```ftl
+def shadow add
+```
+
+## Infix operators
+
+<a id=\"infix.2b\"></a>
+### `+`
+
+```ftl
+infix 5 + a b
+```
+
+Precedence: 5
+
+Adds with [`add`](#fn.add).
+
+Works on `int` only.
+
+Uses: [`add`](#fn.add)
+
+## Prefix operators
+
+<a id=\"prefix.3c-2d\"></a>
+### `<-`
+
+```ftl
+prefix <- x
+```
+
+Precedence: binds tighter than any infix operator
+
+"
+        );
+    }
+
+    #[test]
+    fn html_documentation_is_escaped() {
+        let html = generate(SOURCE, Format::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>test</title>"));
+        assert!(html.contains("<h3 id=\"prefix.3c-2d\"><code>&lt;-</code></h3>"));
+        assert!(
+            html.contains("<p>Adds two numbers, see <a href=\"#infix.2b\"><code>+</code></a>.</p>")
+        );
+        assert!(html.contains("<pre><code>prefix &lt;- x</code></pre>"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+    }
+}
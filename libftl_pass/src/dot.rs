@@ -0,0 +1,500 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::io::Write;
+
+use ftl_session::symbol::Symbol;
+
+use ftl_parser::ast::*;
+use ftl_parser::visitor::*;
+
+use ftl_source::{Pointer, Span};
+
+/// Escapes string to be used inside of the quoted DOT identifier.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn strfy_span<P: Pointer>(span: &Span<P>) -> String {
+    format!(
+        "{}:{}-{}:{}",
+        span.beg.line(),
+        span.beg.position(),
+        span.end.line(),
+        span.end.position()
+    )
+}
+
+/// Writes visited syntax tree as the Graphviz DOT graph.
+///
+/// Every node with its own `NodeId` becomes a graph node
+/// labelled with its kind, symbol or value if it has one,
+/// and span if it has one. Edges lead from the nodes to their children.
+pub struct Dot {
+    res: String,
+    /// Ids of the nodes being visited, the last one is the parent
+    /// of the next added node.
+    parents: Vec<NodeId>,
+
+    run_already: bool,
+}
+
+impl Default for Dot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dot {
+    /// Creates new pass ready to be run on
+    /// a syntax tree.
+    pub fn new() -> Self {
+        Self {
+            res: String::new(),
+            parents: Vec::new(),
+            run_already: false,
+        }
+    }
+
+    /// Returns the graph.
+    /// If the pass hasn't visited any syntax tree yet it panics.
+    pub fn get(&self) -> &str {
+        if !self.run_already {
+            panic!("Dot needs to visit a syntax tree before returning its output.")
+        }
+        &self.res
+    }
+
+    /// Writes the graph to the writer.
+    /// If the pass hasn't visited any syntax tree yet it panics.
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        write!(w, "{}", self.get())
+    }
+
+    /// Adds node with the edge from its parent.
+    fn add<P: Pointer>(
+        &mut self,
+        id: NodeId,
+        kind: &str,
+        symbol: Option<&str>,
+        span: Option<&Span<P>>,
+    ) {
+        let mut label = String::from(kind);
+        if let Some(symbol) = symbol {
+            label += &format!(" {}", escape(symbol));
+        }
+        if let Some(span) = span {
+            label += &format!("\\n{}", strfy_span(span));
+        }
+        self.res += &format!("    n{} [label=\"{}\"];\n", id, label);
+        if let Some(parent) = self.parents.last() {
+            self.res += &format!("    n{} -> n{};\n", parent, id);
+        }
+    }
+
+    /// Adds node and visits its children with `f`.
+    fn node<P: Pointer>(
+        &mut self,
+        id: NodeId,
+        kind: &str,
+        symbol: Option<&str>,
+        span: Option<&Span<P>>,
+        f: impl FnOnce(&mut Self),
+    ) {
+        self.add(id, kind, symbol, span);
+        self.parents.push(id);
+        f(self);
+        self.parents.pop();
+    }
+}
+
+impl<P: Pointer> Pass<'_, P> for Dot {
+    fn visit_module(&mut self, node: &Module<P>) {
+        self.run_already = true;
+        self.res = String::from("digraph ast {\n    node [shape=box];\n");
+        self.parents.clear();
+        self.node::<P>(node.id, "Module", None, None, |s| walk_module(s, node));
+        self.res += "}\n";
+    }
+
+    fn visit_top_level_decl(&mut self, node: &TopLevelDecl<P>) {
+        self.node(node.id, "TopLevelDecl", None, Some(&node.span), |s| {
+            walk_top_level_decl(s, node)
+        });
+    }
+
+    fn visit_func_decl(&mut self, node: &FuncDecl<P>) {
+        self.node::<P>(node.id, "FuncDecl", None, None, |s| walk_func_decl(s, node));
+    }
+
+    fn visit_func_def(&mut self, node: &FuncDef<P>) {
+        self.node::<P>(node.id, "FuncDef", None, None, |s| walk_func_def(s, node));
+    }
+
+    fn visit_infix_def(&mut self, node: &InfixDef<P>) {
+        let prec = node.precedence.to_string();
+        self.node::<P>(node.id, "InfixDef", Some(&prec), None, |s| {
+            walk_infix_def(s, node)
+        });
+    }
+
+    fn visit_prefix_def(&mut self, node: &PrefixDef<P>) {
+        let prec = node.precedence.map(|prec| prec.to_string());
+        self.node::<P>(node.id, "PrefixDef", prec.as_deref(), None, |s| {
+            walk_prefix_def(s, node)
+        });
+    }
+
+    fn visit_func_arg(&mut self, node: &FuncArg<P>) {
+        self.node(node.id, "FuncArg", None, Some(&node.span), |s| {
+            walk_func_arg(s, node)
+        });
+    }
+
+    fn visit_func_attr(&mut self, node: &FuncAttr<P>) {
+        self.node::<P>(node.id, "FuncAttr", None, None, |s| walk_func_attr(s, node));
+    }
+
+    fn visit_expr(&mut self, node: &Expr<P>) {
+        self.node(node.id, "Expr", None, Some(&node.span), |s| {
+            walk_expr(s, node)
+        });
+    }
+
+    fn visit_infix_func_call(&mut self, node: &InfixFuncCall<P>) {
+        self.node::<P>(node.id, "InfixFuncCall", None, None, |s| {
+            walk_infix_func_call(s, node)
+        });
+    }
+
+    fn visit_infix_op_call(&mut self, node: &InfixOpCall<P>) {
+        self.node::<P>(node.id, "InfixOpCall", None, None, |s| {
+            walk_infix_op_call(s, node)
+        });
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &PrefixOpCall<P>) {
+        self.node::<P>(node.id, "PrefixOpCall", None, None, |s| {
+            walk_prefix_op_call(s, node)
+        });
+    }
+
+    fn visit_section(&mut self, node: &Section<P>) {
+        self.node::<P>(node.id, "Section", None, None, |s| walk_section(s, node));
+    }
+
+    fn visit_func_call(&mut self, node: &FuncCall<P>) {
+        self.node::<P>(node.id, "FuncCall", None, None, |s| walk_func_call(s, node));
+    }
+
+    fn visit_parenthesed(&mut self, node: &Paren<P>) {
+        self.node::<P>(node.id, "Paren", None, None, |s| walk_paren_expr(s, node));
+    }
+
+    fn visit_lit(&mut self, node: &Lit<P>) {
+        let val = match &node.kind {
+            LitKind::Int(val) => val.to_string(),
+            LitKind::Float(val) => format!("{:?}", val),
+            LitKind::Str(val) => format!("{:?}", val),
+            LitKind::Char(val) => format!("{:?}", val),
+        };
+        self.add(node.id, "Lit", Some(&val), Some(&node.span));
+    }
+
+    fn visit_ident(&mut self, node: &Ident<P>) {
+        self.add(node.id, "Ident", Some(&node.symbol), Some(&node.span));
+    }
+
+    fn visit_op(&mut self, node: &Op<P>) {
+        self.add(node.id, "Op", Some(&node.symbol), Some(&node.span));
+    }
+
+    fn visit_type(&mut self, node: &Type<P>) {
+        let lit = match node.kind {
+            TypeKind::Literal(LitType::Int) => Some("int"),
+            TypeKind::Literal(LitType::Float) => Some("float"),
+            TypeKind::Literal(LitType::Void) => Some("void"),
+            TypeKind::Literal(LitType::Str) => Some("str"),
+            TypeKind::Literal(LitType::Char) => Some("char"),
+            TypeKind::Function(_) => None,
+        };
+        self.node(node.id, "Type", lit, Some(&node.span), |s| {
+            walk_type(s, node)
+        });
+    }
+
+    fn visit_func_type(&mut self, node: &FuncType<P>) {
+        self.node::<P>(node.id, "FuncType", None, None, |s| walk_func_type(s, node));
+    }
+}
+
+/// Writes the Graphviz DOT graph of the dependencies
+/// between the top level functions and infix operators.
+///
+/// There is an edge from every function or operator to
+/// each one it calls, uses as a value or applies as an infix.
+/// Functions which are only declared are drawn with dashed lines.
+/// Prefix operators are named `prefix op` to tell them apart
+/// from the infixes using the same operator.
+pub struct CallGraph {
+    res: String,
+
+    /// Top level names with the flag telling if they are defined.
+    names: BTreeMap<Symbol, bool>,
+    ops: BTreeSet<Symbol>,
+    prefixes: BTreeSet<Symbol>,
+    edges: BTreeSet<(Symbol, Symbol)>,
+
+    /// Name of the visited definition with its arguments.
+    scope: Option<(Symbol, Vec<Symbol>)>,
+
+    run_already: bool,
+}
+
+impl Default for CallGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallGraph {
+    /// Creates new pass ready to be run on
+    /// a syntax tree.
+    pub fn new() -> Self {
+        Self {
+            res: String::new(),
+            names: BTreeMap::new(),
+            ops: BTreeSet::new(),
+            prefixes: BTreeSet::new(),
+            edges: BTreeSet::new(),
+            scope: None,
+            run_already: false,
+        }
+    }
+
+    /// Returns the graph.
+    /// If the pass hasn't visited any syntax tree yet it panics.
+    pub fn get(&self) -> &str {
+        if !self.run_already {
+            panic!("CallGraph needs to visit a syntax tree before returning its output.")
+        }
+        &self.res
+    }
+
+    /// Writes the graph to the writer.
+    /// If the pass hasn't visited any syntax tree yet it panics.
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        write!(w, "{}", self.get())
+    }
+
+    fn collect_names<P: Pointer>(&mut self, node: &Module<P>) {
+        for decl in &node.decl {
+            match decl.kind {
+                TopLevelDeclKind::FunctionDecl(ref decl) => {
+                    self.names.entry(decl.ident.symbol.clone()).or_insert(false);
+                }
+                TopLevelDeclKind::FunctionDef(ref def) => {
+                    self.names.insert(def.decl.ident.symbol.clone(), true);
+                }
+                TopLevelDeclKind::InfixDef(ref infix) => {
+                    self.ops.insert(infix.op.symbol.clone());
+                }
+                TopLevelDeclKind::PrefixDef(ref prefix) => {
+                    self.prefixes.insert(prefix_name(prefix.op.symbol.clone()));
+                }
+            }
+        }
+    }
+
+    fn add_edge(&mut self, callee: Symbol) {
+        if let Some((caller, _)) = &self.scope {
+            self.edges.insert((caller.clone(), callee));
+        }
+    }
+
+    fn draw(&mut self) {
+        self.res = String::from("digraph calls {\n");
+        for (name, defined) in &self.names {
+            let style = if *defined { "" } else { ", style=dashed" };
+            self.res += &format!("    \"{}\" [shape=box{}];\n", escape(name), style);
+        }
+        for op in &self.ops {
+            self.res += &format!("    \"{}\" [shape=ellipse];\n", escape(op));
+        }
+        for prefix in &self.prefixes {
+            self.res += &format!("    \"{}\" [shape=ellipse];\n", escape(prefix));
+        }
+        for (caller, callee) in &self.edges {
+            self.res += &format!("    \"{}\" -> \"{}\";\n", escape(caller), escape(callee));
+        }
+        self.res += "}\n";
+    }
+}
+
+fn prefix_name(op: Symbol) -> Symbol {
+    Symbol::new(&format!("prefix {}", op))
+}
+
+impl<P: Pointer> Pass<'_, P> for CallGraph {
+    fn visit_module(&mut self, node: &Module<P>) {
+        self.run_already = true;
+        self.names.clear();
+        self.ops.clear();
+        self.prefixes.clear();
+        self.edges.clear();
+        self.collect_names(node);
+        walk_module(self, node);
+        self.draw();
+    }
+
+    fn visit_func_decl(&mut self, _node: &FuncDecl<P>) {}
+
+    fn visit_func_def(&mut self, node: &FuncDef<P>) {
+        let args = node.args.iter().map(|a| a.ident.symbol.clone()).collect();
+        self.scope = Some((node.decl.ident.symbol.clone(), args));
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_infix_def(&mut self, node: &InfixDef<P>) {
+        let args = vec![
+            node.args.0.ident.symbol.clone(),
+            node.args.1.ident.symbol.clone(),
+        ];
+        self.scope = Some((node.op.symbol.clone(), args));
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_prefix_def(&mut self, node: &PrefixDef<P>) {
+        let args = vec![node.arg.ident.symbol.clone()];
+        self.scope = Some((prefix_name(node.op.symbol.clone()), args));
+        self.visit_expr(&node.body);
+        self.scope = None;
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &PrefixOpCall<P>) {
+        let name = prefix_name(node.op.symbol.clone());
+        if self.prefixes.contains(&name) {
+            self.add_edge(name);
+        }
+        self.visit_expr(&node.expr);
+    }
+
+    fn visit_ident(&mut self, node: &Ident<P>) {
+        let is_arg = match self.scope {
+            Some((_, ref args)) => args.contains(&node.symbol),
+            None => false,
+        };
+        if !is_arg && self.names.contains_key(&node.symbol) {
+            self.add_edge(node.symbol.clone());
+        }
+    }
+
+    fn visit_op(&mut self, node: &Op<P>) {
+        if self.ops.contains(&node.symbol) {
+            self.add_edge(node.symbol.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_parser::test_utils::{parse, Ids};
+
+    const SOURCE: &str = r#"
+decl add int int [lang_add] : int
+decl test (int)int (int)int: (int int) int
+infix 5 + a b: @add a b
+infix 1 <> a b: a
+def foo a b: (a + b) `foo 2
+def bar a: @foo a (@baz 1) <> test
+def baz add: add
+prefix - a: a + a
+def qux a: -a
+def quux: @foo (1 <>) (+)
+"#;
+
+    #[test]
+    fn every_node_is_in_the_graph() {
+        let ast = parse(SOURCE);
+        let mut ids = Ids::default();
+        visit_ast(&mut ids, &ast);
+        let mut dot = Dot::new();
+        visit_ast(&mut dot, &ast);
+        let graph = dot.get();
+        for id in ids.0 {
+            assert!(
+                graph.contains(&format!("    n{} [label=", id)),
+                "missing n{}",
+                id
+            );
+        }
+        assert!(graph.starts_with("digraph ast {\n"));
+        assert!(graph.ends_with("}\n"));
+    }
+
+    #[test]
+    fn nodes_are_labelled_and_connected() {
+        let ast = parse("def f a: a + 1");
+        let mut dot = Dot::new();
+        visit_ast(&mut dot, &ast);
+        let graph = dot.get();
+        let body = match ast.root.decl[0].kind {
+            TopLevelDeclKind::FunctionDef(ref def) => &def.body,
+            _ => unreachable!(),
+        };
+        let call = match body.kind {
+            ExprKind::InfixOpCall(ref call) => call,
+            _ => unreachable!(),
+        };
+        assert!(graph.contains(&format!("    n{} [label=\"Expr\\n1:10-1:14\"];\n", body.id)));
+        assert!(graph.contains(&format!(
+            "    n{} [label=\"Op +\\n1:12-1:13\"];\n",
+            call.op.id
+        )));
+        assert!(graph.contains(&format!("    n{} -> n{};\n", body.id, call.id)));
+        assert!(graph.contains(&format!("    n{} -> n{};\n", call.id, call.op.id)));
+    }
+
+    #[test]
+    fn escaping_identifiers() {
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+    }
+
+    #[test]
+    fn call_graph() {
+        let ast = parse(SOURCE);
+        let mut calls = CallGraph::new();
+        visit_ast(&mut calls, &ast);
+        assert_eq!(
+            calls.get(),
+            r#"digraph calls {
+    "add" [shape=box, style=dashed];
+    "bar" [shape=box];
+    "baz" [shape=box];
+    "foo" [shape=box];
+    "quux" [shape=box];
+    "qux" [shape=box];
+    "test" [shape=box, style=dashed];
+    "+" [shape=ellipse];
+    "<>" [shape=ellipse];
+    "prefix -" [shape=ellipse];
+    "+" -> "add";
+    "bar" -> "<>";
+    "bar" -> "baz";
+    "bar" -> "foo";
+    "bar" -> "test";
+    "foo" -> "+";
+    "foo" -> "foo";
+    "prefix -" -> "+";
+    "quux" -> "+";
+    "quux" -> "<>";
+    "quux" -> "foo";
+    "qux" -> "prefix -";
+}
+"#
+        );
+    }
+}
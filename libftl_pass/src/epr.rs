@@ -3,6 +3,7 @@ use log::{debug, trace};
 use std::collections::HashMap;
 
 use ftl_error::LangError;
+use ftl_session::symbol::Symbol;
 use ftl_session::Session;
 
 use ftl_parser::ast::*;
@@ -11,6 +12,7 @@ use ftl_parser::visitor_mut::*;
 
 use ftl_source::Pointer;
 use ftl_source::Source;
+use ftl_source::Span;
 
 /// Error for when the pass couldn't find
 /// the precedence for the encountered operator.
@@ -19,7 +21,7 @@ use ftl_source::Source;
 pub struct UnknownPrecedense<P: Pointer> {
     pub e_beg: P,
     pub e_end: P,
-    pub ident: String,
+    pub ident: Symbol,
 }
 
 impl<P: Pointer> LangError for UnknownPrecedense<P> {
@@ -40,6 +42,9 @@ impl<P: Pointer> LangError for UnknownPrecedense<P> {
 
 /// Mutable pass reorganizing infix operator calls based
 /// on their precedence.
+///
+/// Before that the undeclared prefix operators
+/// in the parenthesis become the right operator sections.
 pub struct ExprPrecReassoc<'a, S: Source> {
     sess: &'a mut Session<S>,
 }
@@ -61,8 +66,12 @@ where
     S: Source<Pointer = P>,
 {
     fn visit_module(&mut self, node: &mut Module<P>) {
+        let mut prec = InfixPrec::new();
+        prec.visit_module(node);
+        let (_, prefix) = prec.get();
+        RightSections { prefix: &prefix }.visit_module(node);
         loop {
-            let mut epr = ExprReassocIteration::new(&mut self.sess);
+            let mut epr = ExprReassocIteration::new(self.sess);
             epr.visit_module(node);
             if let IterationRes::Done = epr.result() {
                 break;
@@ -71,6 +80,84 @@ where
     }
 }
 
+/// Turns the prefix calls in the parenthesis into the right
+/// operator sections if there is no such prefix operator declared,
+/// so `(+ a * b)` is the function adding `a * b` to its argument.
+///
+/// Parser leaves the operands of the infix calls
+/// not yet reassociated on their left side, so the prefix
+/// is looked for at the leftmost operand of the infix calls chain.
+struct RightSections<'a> {
+    prefix: &'a HashMap<Symbol, usize>,
+}
+
+impl<'a> RightSections<'a> {
+    /// Returns the operator of the prefix call being
+    /// the leftmost operand of the expression.
+    fn leading_prefix<P: Pointer>(expr: &Expr<P>) -> Option<Symbol> {
+        match expr.kind {
+            ExprKind::InfixOpCall(ref call) => Self::leading_prefix(&call.lhs),
+            ExprKind::InfixFuncCall(ref call) => Self::leading_prefix(&call.lhs),
+            ExprKind::PrefixOpCall(ref call) => Some(call.op.symbol.clone()),
+            _ => None,
+        }
+    }
+
+    /// Replaces the leading prefix call with its operand
+    /// returning the prefix operator.
+    fn strip_leading_prefix<P: Pointer>(expr: Expr<P>) -> (Op<P>, Expr<P>) {
+        let (op, kind) = match expr.kind {
+            ExprKind::PrefixOpCall(call) => return (call.op, *call.expr),
+            ExprKind::InfixOpCall(mut call) => {
+                let (op, lhs) = Self::strip_leading_prefix(*call.lhs);
+                call.lhs = Box::new(lhs);
+                (op, ExprKind::InfixOpCall(call))
+            }
+            ExprKind::InfixFuncCall(mut call) => {
+                let (op, lhs) = Self::strip_leading_prefix(*call.lhs);
+                call.lhs = Box::new(lhs);
+                (op, ExprKind::InfixFuncCall(call))
+            }
+            _ => unreachable!(),
+        };
+        let beg = match kind {
+            ExprKind::InfixOpCall(ref call) => call.lhs.span.beg.clone(),
+            ExprKind::InfixFuncCall(ref call) => call.lhs.span.beg.clone(),
+            _ => unreachable!(),
+        };
+        let span = Span {
+            beg,
+            end: expr.span.end,
+        };
+        (
+            op,
+            Expr {
+                id: expr.id,
+                kind,
+                span,
+            },
+        )
+    }
+}
+
+impl<'a, 'ast, P: Pointer> MutPass<'ast, P> for RightSections<'a> {
+    fn visit_expr(&mut self, node: &'ast mut Expr<P>) {
+        if let ExprKind::Parenthesed(ref paren) = node.kind {
+            let prefix = Self::leading_prefix(&paren.expr);
+            if prefix.is_some_and(|op| !self.prefix.contains_key(&op)) {
+                let (op, rhs) = Self::strip_leading_prefix(*paren.expr.clone());
+                node.kind = ExprKind::Section(Section {
+                    id: paren.id,
+                    op,
+                    lhs: None,
+                    rhs: Some(Box::new(rhs)),
+                });
+            }
+        }
+        noop_expr(self, node);
+    }
+}
+
 /// Single iteration of reassociation.
 /// In its run it can only surface expressions one level to the top
 /// if there is some deeply nested, low precedence expression
@@ -82,7 +169,8 @@ where
 /// If next iteration is needed this pass should be
 /// created anew and not reused.
 struct ExprReassocIteration<'a, S: Source> {
-    op: HashMap<String, usize>,
+    op: HashMap<Symbol, usize>,
+    prefix: HashMap<Symbol, usize>,
     sess: &'a mut Session<S>,
     result: IterationRes,
 }
@@ -107,6 +195,7 @@ where
     pub fn new(sess: &'a mut Session<S>) -> Self {
         ExprReassocIteration {
             op: HashMap::new(),
+            prefix: HashMap::new(),
             sess,
             result: IterationRes::Done,
         }
@@ -135,7 +224,7 @@ where
     /// Returns precedence for the given expression.
     /// Fatals if the precedence could not be found.
     fn get_expr_prec(&mut self, expr: &Expr<P>) -> usize {
-        let mut prec = InferPrec::new(&self.op);
+        let mut prec = InferPrec::new(&self.op, &self.prefix);
         prec.visit_expr(expr);
         match prec.get() {
             (Some(ident), None) => {
@@ -154,67 +243,64 @@ where
     /// and its lhs are reversed if so swaps them and returns true
     /// otherwise returns false.
     fn try_prec_switch(&mut self, node: &mut Expr<P>) -> bool {
-        // FIXME: Refactor
-        match node.kind {
+        let (prec, lhs) = match node.kind {
             ExprKind::InfixOpCall(ref infix_call) => {
-                let op_prec = self.get_op_prec(&infix_call.op);
-                let lhs_prec = self.get_expr_prec(&infix_call.lhs);
-                trace!(
-                    "Op {}, prec: {}, lhs_prec: {}",
-                    infix_call.op.symbol,
-                    op_prec,
-                    lhs_prec
-                );
-                if lhs_prec < op_prec {
-                    let mut new_kind = infix_call.lhs.kind.clone();
-                    if let ExprKind::InfixOpCall(ref mut new_infix_call) = new_kind {
-                        new_infix_call.rhs = Box::new(Expr {
-                            id: infix_call.lhs.id, // we dropped this id earlier so we can reuse it now
-                            kind: ExprKind::InfixOpCall(InfixOpCall {
-                                lhs: new_infix_call.rhs.clone(),
-                                ..infix_call.clone()
-                            }),
-                            span: node.span.clone(),
-                        });
-                    } else {
-                        unreachable!();
-                    }
-                    node.kind = new_kind;
-                    true
-                } else {
-                    false
-                }
+                (self.get_op_prec(&infix_call.op), &infix_call.lhs)
             }
-            ExprKind::InfixFuncCall(ref infix_call) => {
-                let expr_prec = self.get_expr_prec(&node);
-                let lhs_prec = self.get_expr_prec(&infix_call.lhs);
-                trace!(
-                    "Op {}, prec: {}, lhs_prec: {}",
-                    infix_call.ident.symbol,
-                    expr_prec,
-                    lhs_prec
-                );
-                if lhs_prec < expr_prec {
-                    let mut new_kind = infix_call.lhs.kind.clone();
-                    if let ExprKind::InfixOpCall(ref mut new_infix_call) = new_kind {
-                        new_infix_call.rhs = Box::new(Expr {
-                            id: infix_call.lhs.id, // we dropped this id earlier so we can reuse it now
-                            kind: ExprKind::InfixFuncCall(InfixFuncCall {
-                                lhs: new_infix_call.rhs.clone(),
-                                ..infix_call.clone()
-                            }),
-                            span: node.span.clone(),
-                        });
-                    } else {
-                        unreachable!();
+            ExprKind::InfixFuncCall(ref infix_call) => (self.get_expr_prec(node), &infix_call.lhs),
+            _ => return false,
+        };
+        let lhs_prec = self.get_expr_prec(lhs);
+        trace!("Prec: {}, lhs_prec: {}", prec, lhs_prec);
+        if lhs_prec < prec {
+            node.kind = Self::rotate(node);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves the infix call into the last operand of its lhs,
+    /// so `(a + b) * c` becomes `a + (b * c)` and
+    /// `(-a) * b` becomes `-(a * b)`.
+    fn rotate(node: &Expr<P>) -> ExprKind<P> {
+        let lhs = match node.kind {
+            ExprKind::InfixOpCall(ref infix_call) => &infix_call.lhs,
+            ExprKind::InfixFuncCall(ref infix_call) => &infix_call.lhs,
+            _ => unreachable!(),
+        };
+        let with_lhs = |new_lhs: Box<Expr<P>>| {
+            Box::new(Expr {
+                id: lhs.id, // we dropped this id earlier so we can reuse it now
+                span: Span {
+                    beg: new_lhs.span.beg.clone(),
+                    end: node.span.end.clone(),
+                },
+                kind: match node.kind {
+                    ExprKind::InfixOpCall(ref infix_call) => ExprKind::InfixOpCall(InfixOpCall {
+                        lhs: new_lhs,
+                        ..infix_call.clone()
+                    }),
+                    ExprKind::InfixFuncCall(ref infix_call) => {
+                        ExprKind::InfixFuncCall(InfixFuncCall {
+                            lhs: new_lhs,
+                            ..infix_call.clone()
+                        })
                     }
-                    node.kind = new_kind;
-                    true
-                } else {
-                    false
-                }
+                    _ => unreachable!(),
+                },
+            })
+        };
+        match lhs.kind.clone() {
+            ExprKind::InfixOpCall(mut infix_call) => {
+                infix_call.rhs = with_lhs(infix_call.rhs);
+                ExprKind::InfixOpCall(infix_call)
             }
-            _ => false,
+            ExprKind::PrefixOpCall(mut prefix_call) => {
+                prefix_call.expr = with_lhs(prefix_call.expr);
+                ExprKind::PrefixOpCall(prefix_call)
+            }
+            _ => unreachable!(),
         }
     }
 }
@@ -227,8 +313,10 @@ where
     fn visit_module(&mut self, node: &'a mut Module<P>) {
         debug!("Running EPR Pass iteration");
         let mut prec = InfixPrec::new();
-        prec.visit_module(&node);
-        self.op = prec.get();
+        prec.visit_module(node);
+        let (op, prefix) = prec.get();
+        self.op = op;
+        self.prefix = prefix;
 
         noop_module(self, node);
     }
@@ -248,7 +336,11 @@ where
 /// creating map mapping operator to its precedence.
 struct InfixPrec {
     /// Map operator symbols to their precedence.
-    op: HashMap<String, usize>,
+    op: HashMap<Symbol, usize>,
+
+    /// Map prefix operator symbols to their precedence.
+    /// Prefixes declared without one get usize::MAX.
+    prefix: HashMap<Symbol, usize>,
 
     /// If the pass has visited any syntax tree already.
     run_already: bool,
@@ -259,25 +351,28 @@ impl InfixPrec {
     pub fn new() -> Self {
         Self {
             op: HashMap::new(),
+            prefix: HashMap::new(),
             run_already: false,
         }
     }
 
-    /// Clears operators map.
+    /// Clears operators maps.
     fn clear(&mut self) {
         self.op.clear();
+        self.prefix.clear();
     }
 
-    /// Consumes record returning map of operators with their precedences.
+    /// Consumes record returning maps of infix and prefix operators
+    /// with their precedences.
     /// If run before visiting a syntax tree it panics.
-    pub fn get(self) -> HashMap<String, usize> {
+    pub fn get(self) -> (HashMap<Symbol, usize>, HashMap<Symbol, usize>) {
         if !self.run_already {
             panic!(
                 "InfixPrec pass needs to be run on a syntax tree
                 before trying to access its value"
             );
         }
-        self.op
+        (self.op, self.prefix)
     }
 }
 
@@ -289,9 +384,15 @@ impl<P: Pointer> Pass<'_, P> for InfixPrec {
     }
 
     fn visit_infix_def(&mut self, node: &InfixDef<P>) {
-        let op = node.op.symbol.clone();
+        let op = &node.op.symbol;
         let prec = node.precedence;
-        self.op.insert(op, prec);
+        self.op.insert(op.clone(), prec);
+    }
+
+    fn visit_prefix_def(&mut self, node: &PrefixDef<P>) {
+        let op = &node.op.symbol;
+        let prec = node.precedence.unwrap_or(usize::MAX);
+        self.prefix.insert(op.clone(), prec);
     }
 }
 
@@ -299,18 +400,20 @@ impl<P: Pointer> Pass<'_, P> for InfixPrec {
 /// Visited node should be an expression or any of its kinds.
 /// Trying to visit any other node panics.
 struct InferPrec<'a> {
-    op: &'a HashMap<String, usize>,
+    op: &'a HashMap<Symbol, usize>,
+    prefix: &'a HashMap<Symbol, usize>,
     prec: Option<usize>,
-    symbol: Option<String>,
+    symbol: Option<Symbol>,
 
     run_already: bool,
 }
 
 impl<'a> InferPrec<'a> {
     /// Returns new pass ready to visit a node.
-    pub fn new(op: &'a HashMap<String, usize>) -> Self {
+    pub fn new(op: &'a HashMap<Symbol, usize>, prefix: &'a HashMap<Symbol, usize>) -> Self {
         Self {
             op,
+            prefix,
             prec: None,
             symbol: None,
             run_already: false,
@@ -322,7 +425,7 @@ impl<'a> InferPrec<'a> {
     /// the operator call.
     /// Second being its precedence which is usize::max() for any
     /// literal value and function calls and precedence set in
-    /// infix declaration for the operator call.
+    /// infix or prefix declaration for the operator call.
     ///
     /// If the precedence for the operator call could not be found
     /// the precedence part of the returned value is None while the
@@ -330,7 +433,7 @@ impl<'a> InferPrec<'a> {
     ///
     /// This method should be called only after a syntax tree
     /// has been visited already. If not the method panics.
-    pub fn get(&self) -> (Option<String>, Option<usize>) {
+    pub fn get(&self) -> (Option<Symbol>, Option<usize>) {
         if !self.run_already {
             panic!(
                 "Getting the result of the InferPrec pass should be done
@@ -360,29 +463,40 @@ impl<'a, P: Pointer> Pass<'a, P> for InferPrec<'a> {
         }
     }
 
+    fn visit_prefix_op_call(&mut self, node: &PrefixOpCall<P>) {
+        self.run_already = true;
+        self.prec = self.prefix.get(&node.op.symbol).copied();
+        self.symbol = Some(node.op.symbol.clone());
+    }
+
     fn visit_func_call(&mut self, _: &FuncCall<P>) {
         self.run_already = true;
-        self.prec = Some(usize::max_value());
+        self.prec = Some(usize::MAX);
     }
 
     fn visit_lit(&mut self, _: &Lit<P>) {
         self.run_already = true;
-        self.prec = Some(usize::max_value());
+        self.prec = Some(usize::MAX);
     }
 
     fn visit_parenthesed(&mut self, _: &Paren<P>) {
         self.run_already = true;
-        self.prec = Some(usize::max_value());
+        self.prec = Some(usize::MAX);
+    }
+
+    fn visit_section(&mut self, _: &Section<P>) {
+        self.run_already = true;
+        self.prec = Some(usize::MAX);
     }
 
     fn visit_ident(&mut self, _: &Ident<P>) {
         self.run_already = true;
-        self.prec = Some(usize::max_value());
+        self.prec = Some(usize::MAX);
     }
 
     fn visit_infix_func_call(&mut self, _: &InfixFuncCall<P>) {
         self.run_already = true;
-        self.prec = Some(usize::max_value());
+        self.prec = Some(usize::MAX);
     }
 
     fn visit_module(&mut self, _node: &Module<P>) {
@@ -405,6 +519,10 @@ impl<'a, P: Pointer> Pass<'a, P> for InferPrec<'a> {
         self.wrong_node();
     }
 
+    fn visit_prefix_def(&mut self, _node: &PrefixDef<P>) {
+        self.wrong_node();
+    }
+
     fn visit_func_arg(&mut self, _node: &FuncArg<P>) {
         self.wrong_node();
     }
@@ -429,3 +547,133 @@ impl<'a, P: Pointer> Pass<'a, P> for InferPrec<'a> {
         self.wrong_node();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_parser::diff::diff;
+    use ftl_parser::test_utils::parse;
+    use ftl_source::{string, synthetic::Synthetic};
+
+    use crate::pp::Printer;
+
+    fn reassoc<S: 'static + Source>(src: S, ast: &mut AST<S>) -> String {
+        let mut sess = Session::new(src);
+        let mut epr = ExprPrecReassoc::new(&mut sess);
+        visit_ast_mut(&mut epr, ast);
+        let mut p = Printer::new();
+        ftl_parser::visitor::visit_ast(&mut p, ast);
+        let mut out = Vec::new();
+        p.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn reassociating_tree_loaded_from_json() {
+        let src = "infix 5 + a b: a\ninfix 10 * a b: b\ndef f: 1 + 2 * 3 * 4 + 5";
+        let mut parsed = parse(src);
+        let json = serde_json::to_string(&parsed).unwrap();
+        let mut loaded: AST<Synthetic> = serde_json::from_str(&json).unwrap();
+
+        let expected = reassoc(string::String::from(src), &mut parsed);
+        assert_eq!(reassoc(Synthetic::new(), &mut loaded), expected);
+    }
+
+    /// Returns body of the last definition in the source after reassociation.
+    fn reassoc_body(src: &str) -> Expr<string::Pointer> {
+        let mut ast = parse(src);
+        let mut sess = Session::new(string::String::from(src));
+        visit_ast_mut(&mut ExprPrecReassoc::new(&mut sess), &mut ast);
+        match ast.root.decl.pop().unwrap().kind {
+            TopLevelDeclKind::FunctionDef(def) => def.body,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn reassociating_by_precedence() {
+        use ftl_parser::utility::*;
+
+        let infixes = "infix 5 + a b: a\ninfix 10 * a b: b\n";
+        let cases = vec![
+            ("1 + 2 * 3", op("+", int(1), op("*", int(2), int(3)))),
+            ("1 * 2 + 3", op("+", op("*", int(1), int(2)), int(3))),
+            (
+                "1 * 2 + 3 * 4 + 5",
+                op(
+                    "+",
+                    op("+", op("*", int(1), int(2)), op("*", int(3), int(4))),
+                    int(5),
+                ),
+            ),
+            (
+                "(1 + 2) * 3",
+                op("*", paren(op("+", int(1), int(2))), int(3)),
+            ),
+            (
+                "1 * (2 + 3)",
+                op("*", int(1), paren(op("+", int(2), int(3)))),
+            ),
+        ];
+        for (src, expected) in cases {
+            let src = format!("{}def f: {}", infixes, src);
+            let expected = expected.build(&mut NodeIds::new());
+            assert_eq!(diff(&reassoc_body(&src), &expected), None, "{}", src);
+        }
+    }
+
+    #[test]
+    fn reassociating_prefix_calls_by_precedence() {
+        use ftl_parser::utility::*;
+
+        let decls = "infix 5 + a b: a\ninfix 10 * a b: b\nprefix 7 - a: a\nprefix ! a: a\n";
+        let neg = |expr| prefix_op("-", expr);
+        let cases = vec![
+            ("-x + 2", op("+", neg(id("x")), int(2))),
+            ("-x * 2", neg(op("*", id("x"), int(2)))),
+            ("-x * 2 + 3", op("+", neg(op("*", id("x"), int(2))), int(3))),
+            ("1 + -x * 3", op("+", int(1), neg(op("*", id("x"), int(3))))),
+            ("- -x * 2", neg(neg(op("*", id("x"), int(2))))),
+            ("!1 * 2", op("*", prefix_op("!", int(1)), int(2))),
+            ("-x `f 2", neg(infix_call("f", id("x"), int(2)))),
+            ("!1 `f 2", infix_call("f", prefix_op("!", int(1)), int(2))),
+            ("(-x) * 2", op("*", paren(neg(id("x"))), int(2))),
+            ("-1 * 2", op("*", int(-1), int(2))),
+        ];
+        for (src, expected) in cases {
+            let src = format!("{}def f: {}", decls, src);
+            let expected = expected.build(&mut NodeIds::new());
+            assert_eq!(diff(&reassoc_body(&src), &expected), None, "{}", src);
+        }
+    }
+
+    #[test]
+    fn turning_undeclared_prefixes_in_parenthesis_into_sections() {
+        use ftl_parser::utility::*;
+
+        let src = "infix 5 + a b: a\n\
+                   infix 10 * a b: b\n\
+                   prefix 7 - a: a\n\
+                   def f: @g (+ 1 * 2) (- 1 * 2) (* 1 + 2) (2 +) (1 + -x)";
+        let mut ast = parse(src);
+        let mut sess = Session::new(string::String::from(src));
+        visit_ast_mut(&mut ExprPrecReassoc::new(&mut sess), &mut ast);
+        let expected = ASTBuilder::new()
+            .infix(infix(5, "+", "a", "b").body(id("a")))
+            .infix(infix(10, "*", "a", "b").body(id("b")))
+            .prefix(prefix("-", "a").precedence(7).body(id("a")))
+            .def(def("f").body(call(
+                id("g"),
+                vec![
+                    right_section("+", op("*", int(1), int(2))),
+                    paren(prefix_op("-", op("*", int(1), int(2)))),
+                    right_section("*", op("+", int(1), int(2))),
+                    left_section(int(2), "+"),
+                    paren(op("+", int(1), prefix_op("-", id("x")))),
+                ],
+            )))
+            .build();
+        assert_eq!(diff(&ast, &expected), None);
+    }
+}
@@ -0,0 +1,745 @@
+use std::io;
+use std::io::Write;
+
+use ftl_parser::ast::*;
+use ftl_parser::visitor::*;
+
+use ftl_source::Pointer;
+
+/// Default maximal width of the formatted line.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Indentation of the wrapped function body.
+const BODY_INDENT: &str = "    ";
+/// Indentation of the wrapped operator call continuation.
+const CONT_INDENT: &str = "        ";
+
+/// Comment found in the source.
+#[derive(Clone, Debug)]
+pub struct Comment {
+    /// Line, starting from one, the comment begins in.
+    pub line: usize,
+    /// Line the comment ends in, differs from `line`
+    /// only for the block comments spanning many lines.
+    pub end_line: usize,
+    /// Comment text including the leading `#`.
+    pub text: String,
+    /// True if there is no code before the comment in its line.
+    pub own_line: bool,
+}
+
+/// Returns every comment in the source text.
+///
+/// The parser doesn't keep comments in the syntax tree
+/// so the formatter needs to get them from the source.
+pub fn collect_comments(text: &str) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    let mut line = 1;
+    let mut line_beg = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((pos, ch)) = chars.next() {
+        match ch {
+            '\n' => {
+                line += 1;
+                line_beg = pos + 1;
+            }
+            '"' | '\'' => {
+                // Quoted literals end at the new line if not closed.
+                while let Some(&(_, c)) = chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    chars.next();
+                    if c == ch {
+                        break;
+                    }
+                    if c == '\\' {
+                        chars.next();
+                    }
+                }
+            }
+            '#' => {
+                let own_line = text[line_beg..pos].trim().is_empty();
+                let beg_line = line;
+                let end = if let Some(&(_, '{')) = chars.peek() {
+                    chars.next();
+                    skip_block_comment(&mut chars, &mut line).unwrap_or(text.len())
+                } else {
+                    text[pos..].find('\n').map_or(text.len(), |len| pos + len)
+                };
+                while chars.peek().is_some_and(|&(p, _)| p < end) {
+                    chars.next();
+                }
+                comments.push(Comment {
+                    line: beg_line,
+                    end_line: line,
+                    text: text[pos..end].trim_end().to_owned(),
+                    own_line,
+                });
+            }
+            _ => (),
+        }
+    }
+    comments
+}
+
+/// Skips the block comment past its closing `}#` counting
+/// the lines in it and returns the position after it,
+/// if the comment is closed.
+fn skip_block_comment(
+    chars: &mut std::iter::Peekable<std::str::CharIndices>,
+    line: &mut usize,
+) -> Option<usize> {
+    let mut depth = 1;
+    let mut prev = None;
+    for (pos, ch) in chars.by_ref() {
+        prev = match (prev, ch) {
+            (Some('#'), '{') => {
+                depth += 1;
+                None
+            }
+            (Some('}'), '#') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos + 1);
+                }
+                None
+            }
+            (_, '\n') => {
+                *line += 1;
+                Some(ch)
+            }
+            _ => Some(ch),
+        };
+    }
+    None
+}
+
+/// Returns FTL representation of the type.
+pub fn type_repr<P: Pointer>(ty: &Type<P>) -> String {
+    match ty.kind {
+        TypeKind::Literal(LitType::Int) => String::from("int"),
+        TypeKind::Literal(LitType::Float) => String::from("float"),
+        TypeKind::Literal(LitType::Void) => String::from("void"),
+        TypeKind::Literal(LitType::Str) => String::from("str"),
+        TypeKind::Literal(LitType::Char) => String::from("char"),
+        TypeKind::Function(ref func_t) => format!(
+            "({}) {}",
+            func_t
+                .args
+                .iter()
+                .map(type_repr)
+                .collect::<Vec<_>>()
+                .join(" "),
+            type_repr(&func_t.ret)
+        ),
+    }
+}
+
+/// Returns FTL representation of the attribute with its arguments.
+///
+/// Arguments are put in the parenthesis only if
+/// there is an identifier among them, as in `inline(always)`,
+/// literals alone follow the name, as in `lang "add"`.
+pub fn attr_repr<P: Pointer>(attr: &FuncAttr<P>) -> String {
+    attr_with_lits(attr, Formatter::lit_value)
+}
+
+/// Returns FTL representation of the attribute
+/// writing its literal arguments with the given function.
+fn attr_with_lits<P: Pointer>(attr: &FuncAttr<P>, lit: impl Fn(&Lit<P>) -> String) -> String {
+    let args: Vec<String> = attr
+        .args
+        .iter()
+        .map(|arg| match arg {
+            AttrArg::Lit(l) => lit(l),
+            AttrArg::Ident(ident) => ident.symbol.to_string(),
+        })
+        .collect();
+    let parens = attr.args.iter().any(|arg| matches!(arg, AttrArg::Ident(_)));
+    if args.is_empty() {
+        attr.ident.symbol.to_string()
+    } else if parens {
+        format!("{}({})", attr.ident.symbol, args.join(" "))
+    } else {
+        format!("{} {}", attr.ident.symbol, args.join(" "))
+    }
+}
+
+/// Formats visited syntax tree as the canonical FTL source.
+///
+/// Formatting rules are:
+/// * every top level declaration is in its own line, blank lines
+///   between them are collapsed to at most one;
+/// * operators, infix calls and function arguments are separated with single spaces;
+/// * attributes are put in brackets right before the colon, separated with spaces;
+/// * if the declaration doesn't fit the line width its body is moved
+///   to the next line and, if it still doesn't fit, the top level
+///   operator calls are wrapped before the operator;
+/// * comments in their own lines are kept before the declaration following them,
+///   comment after the last line of the declaration stays there,
+///   any other comment inside the declaration is put in its own line
+///   between the head and the body, which is then moved to the next line,
+///   or before the declaration if it has no body.
+///
+/// Literals are written as they are spelled in the source, like
+/// `0xFF` or `1e10`, if it has been given with
+/// [`source`](struct.Formatter.html#method.source),
+/// otherwise they are written as their values.
+///
+/// Visiting syntax tree without the parenthesis, as left by
+/// the [`ExprPrecReassoc`](../epr/struct.ExprPrecReassoc.html) pass,
+/// still results in the source parsing to the same syntax tree
+/// after reassociation.
+pub struct Formatter {
+    res: String,
+    width: usize,
+    comments: Vec<Comment>,
+    source: Option<String>,
+
+    /// Last line of the source which has already been formatted.
+    last_line: Option<usize>,
+
+    run_already: bool,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Formatter {
+    /// Creates new pass formatting lines to the
+    /// [`DEFAULT_WIDTH`](constant.DEFAULT_WIDTH.html) without comments.
+    pub fn new() -> Self {
+        Self {
+            res: String::new(),
+            width: DEFAULT_WIDTH,
+            comments: Vec::new(),
+            source: None,
+            last_line: None,
+            run_already: false,
+        }
+    }
+
+    /// Sets maximal line width.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets comments to be kept in the output,
+    /// as returned by [`collect_comments`](fn.collect_comments.html).
+    pub fn comments(mut self, mut comments: Vec<Comment>) -> Self {
+        comments.sort_by_key(|c| c.line);
+        self.comments = comments;
+        self
+    }
+
+    /// Sets the source text the visited syntax tree has
+    /// been parsed from, so the literals keep their spelling.
+    pub fn source(mut self, text: &str) -> Self {
+        self.source = Some(text.to_owned());
+        self
+    }
+
+    /// Returns the formatted source.
+    /// If the pass hasn't visited any syntax tree yet it panics.
+    pub fn get(&self) -> &str {
+        if !self.run_already {
+            panic!("Formatter needs to visit a syntax tree before returning its output.")
+        }
+        &self.res
+    }
+
+    /// Writes the formatted source to the writer.
+    /// If the pass hasn't visited any syntax tree yet it panics.
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        write!(w, "{}", self.get())
+    }
+
+    /// Adds line to the output preceding it with the
+    /// blank line if there was one in the source.
+    fn add_line(&mut self, line: usize, s: &str) {
+        if let Some(last) = self.last_line {
+            if line > last + 1 {
+                self.res += "\n";
+            }
+        }
+        self.res += s;
+        self.res += "\n";
+    }
+
+    /// Takes comments from the beginning of the list which are
+    /// before the given line.
+    fn take_comments_before(&mut self, line: usize) -> Vec<Comment> {
+        let count = self.comments.iter().take_while(|c| c.line < line).count();
+        self.comments.drain(..count).collect()
+    }
+
+    fn add_comments(&mut self, comments: Vec<Comment>) {
+        for c in comments {
+            self.add_line(c.line, &c.text);
+            self.last_line = Some(c.end_line);
+        }
+    }
+
+    /// Adds formatted top level declaration with its comments.
+    fn add_decl(&mut self, span: &(usize, usize), head: String, body: Option<String>) {
+        let (beg, end) = *span;
+        let leading = self.take_comments_before(beg);
+        self.add_comments(leading);
+        let mut inner = self.take_comments_before(end + 1);
+        let trailing = match inner.last() {
+            Some(c) if c.line == end && !c.own_line => inner.pop(),
+            _ => None,
+        };
+        let mut decl = match body {
+            Some(body) if inner.is_empty() => self.join_body(head, body),
+            Some(body) => {
+                let mut decl = format!("{}:", head);
+                for c in inner {
+                    decl += &format!("\n{}{}", BODY_INDENT, c.text);
+                }
+                decl + &format!("\n{}{}", BODY_INDENT, body)
+            }
+            None => {
+                for c in inner {
+                    self.add_line(beg, &c.text);
+                    self.last_line = Some(beg);
+                }
+                head
+            }
+        };
+        if let Some(c) = trailing {
+            decl += &format!(" {}", c.text);
+        }
+        self.add_line(beg, &decl);
+        self.last_line = Some(end);
+    }
+
+    /// Joins declaration head with its body wrapping
+    /// the body if its too long.
+    fn join_body(&self, head: String, body: String) -> String {
+        let line = format!("{}: {}", head, body);
+        if line.chars().count() <= self.width {
+            return line;
+        }
+        format!("{}:\n{}{}", head, BODY_INDENT, body)
+    }
+
+    /// Returns formatted expression.
+    /// Operator calls chain in the top level of the expression
+    /// is wrapped to fit the line width.
+    fn body<P: Pointer>(&self, expr: &Expr<P>) -> String {
+        let flat = self.expr(expr);
+        if BODY_INDENT.len() + flat.chars().count() <= self.width {
+            return flat;
+        }
+        let mut parts = Vec::new();
+        let first = self.flatten_infix(expr, &mut parts);
+        let mut res = first.clone();
+        let mut line_len = BODY_INDENT.len() + first.chars().count();
+        for part in parts {
+            let len = part.chars().count();
+            if line_len + 1 + len > self.width {
+                res += &format!("\n{}{}", CONT_INDENT, part);
+                line_len = CONT_INDENT.len() + len;
+            } else {
+                res += &format!(" {}", part);
+                line_len += 1 + len;
+            }
+        }
+        res
+    }
+
+    /// Splits left associated chain of the infix calls into
+    /// its first operand and the rest of the chain
+    /// as the operator with its right hand side pairs.
+    fn flatten_infix<P: Pointer>(&self, expr: &Expr<P>, parts: &mut Vec<String>) -> String {
+        let (lhs, part) = match expr.kind {
+            ExprKind::InfixOpCall(ref call) => (
+                &call.lhs,
+                format!("{} {}", call.op.symbol, self.expr(&call.rhs)),
+            ),
+            ExprKind::InfixFuncCall(ref call) => (
+                &call.lhs,
+                format!("`{} {}", call.ident.symbol, self.expr(&call.rhs)),
+            ),
+            _ => return self.expr(expr),
+        };
+        let first = self.flatten_infix(lhs, parts);
+        parts.push(part);
+        first
+    }
+
+    /// Returns expression in one line.
+    fn expr<P: Pointer>(&self, expr: &Expr<P>) -> String {
+        match expr.kind {
+            ExprKind::Literal(ref lit) => self.lit(lit),
+            ExprKind::Identifier(ref ident) => ident.symbol.to_string(),
+            ExprKind::Parenthesed(ref paren) => format!("({})", self.expr(&paren.expr)),
+            ExprKind::InfixOpCall(ref call) => format!(
+                "{} {} {}",
+                self.expr(&call.lhs),
+                call.op.symbol,
+                self.expr(&call.rhs)
+            ),
+            ExprKind::InfixFuncCall(ref call) => format!(
+                "{} `{} {}",
+                self.expr(&call.lhs),
+                call.ident.symbol,
+                self.expr(&call.rhs)
+            ),
+            ExprKind::PrefixOpCall(ref call) => {
+                let operand = self.expr(&call.expr);
+                // Operand starting with the operator character, like
+                // the nested prefix call or `@`, would be read as
+                // the part of this operator, and the number right
+                // after `-` would be read as the negative literal.
+                let negative_lit =
+                    call.op.symbol == "-" && operand.starts_with(|ch: char| ch.is_ascii_digit());
+                let glued = !negative_lit
+                    && operand.starts_with(|ch: char| {
+                        ch.is_alphanumeric() || matches!(ch, '_' | '(' | '"' | '\'')
+                    });
+                if glued {
+                    format!("{}{}", call.op.symbol, operand)
+                } else {
+                    format!("{} {}", call.op.symbol, operand)
+                }
+            }
+            ExprKind::Section(ref section) => match (&section.lhs, &section.rhs) {
+                (Some(lhs), _) => format!("({} {})", self.expr(lhs), section.op.symbol),
+                (_, Some(rhs)) => format!("({} {})", section.op.symbol, self.expr(rhs)),
+                _ => format!("({})", section.op.symbol),
+            },
+            ExprKind::FunctionCall(ref call) => {
+                let mut repr = format!("@{}", self.primary_expr(&call.lhs));
+                for arg in &call.args {
+                    repr += &format!(" {}", self.primary_expr(arg));
+                }
+                repr
+            }
+        }
+    }
+
+    /// Returns expression which can be used as the function call
+    /// argument, surrounding it with parenthesis if needed.
+    fn primary_expr<P: Pointer>(&self, expr: &Expr<P>) -> String {
+        match expr.kind {
+            ExprKind::Literal(_)
+            | ExprKind::Identifier(_)
+            | ExprKind::Parenthesed(_)
+            | ExprKind::Section(_) => self.expr(expr),
+            _ => format!("({})", self.expr(expr)),
+        }
+    }
+
+    /// Returns the literal as spelled in the source, if it is known.
+    fn lit<P: Pointer>(&self, lit: &Lit<P>) -> String {
+        let (beg, end) = (lit.span.beg.offset(), lit.span.end.offset());
+        match self.source.as_ref().and_then(|src| src.get(beg..end)) {
+            Some(spelling) if !spelling.is_empty() => spelling.to_owned(),
+            _ => Self::lit_value(lit),
+        }
+    }
+
+    fn lit_value<P: Pointer>(lit: &Lit<P>) -> String {
+        match &lit.kind {
+            LitKind::Int(val) => val.to_string(),
+            // Debug output of floats always has the fractional part or
+            // the exponent so they are not read back as integers.
+            LitKind::Float(val) => format!("{:?}", val),
+            // Debug output escapes only with the sequences FTL supports.
+            LitKind::Str(val) => format!("{:?}", val),
+            LitKind::Char(val) => format!("{:?}", val),
+        }
+    }
+
+    fn attrs<P: Pointer>(&self, attrs: &[FuncAttr<P>]) -> String {
+        if attrs.is_empty() {
+            return String::new();
+        }
+        let attrs: Vec<String> = attrs
+            .iter()
+            .map(|attr| attr_with_lits(attr, |lit| self.lit(lit)))
+            .collect();
+        format!(" [{}]", attrs.join(" "))
+    }
+
+    fn lines<P: Pointer>(node: &TopLevelDecl<P>) -> (usize, usize) {
+        (node.span.beg.line(), node.span.end.line())
+    }
+}
+
+impl<P: Pointer> Pass<'_, P> for Formatter {
+    fn visit_module(&mut self, node: &Module<P>) {
+        self.run_already = true;
+        self.res.clear();
+        self.last_line = None;
+        walk_module(self, node);
+        let rest = std::mem::take(&mut self.comments);
+        self.add_comments(rest);
+    }
+
+    fn visit_top_level_decl(&mut self, node: &TopLevelDecl<P>) {
+        let lines = Self::lines(node);
+        let (head, body) = match node.kind {
+            TopLevelDeclKind::FunctionDecl(ref decl) => {
+                let mut head = format!("decl {}", decl.ident.symbol);
+                let mut ret = None;
+                if let Some(Type {
+                    kind: TypeKind::Function(ref func_t),
+                    ..
+                }) = decl.ty
+                {
+                    for arg in &func_t.args {
+                        head += &format!(" {}", type_repr(arg));
+                    }
+                    ret = Some(type_repr(&func_t.ret));
+                }
+                head += &self.attrs(&decl.attrs);
+                (head, ret)
+            }
+            TopLevelDeclKind::FunctionDef(ref def) => {
+                let mut head = format!("def {}", def.decl.ident.symbol);
+                for arg in &def.args {
+                    head += &format!(" {}", arg.ident.symbol);
+                }
+                head += &self.attrs(&def.decl.attrs);
+                (head, Some(self.body(&def.body)))
+            }
+            TopLevelDeclKind::InfixDef(ref infix) => (
+                format!(
+                    "infix {} {} {} {}",
+                    infix.precedence,
+                    infix.op.symbol,
+                    infix.args.0.ident.symbol,
+                    infix.args.1.ident.symbol
+                ),
+                Some(self.body(&infix.body)),
+            ),
+            TopLevelDeclKind::PrefixDef(ref prefix) => {
+                let mut head = String::from("prefix");
+                if let Some(prec) = prefix.precedence {
+                    head += &format!(" {}", prec);
+                }
+                head += &format!(" {} {}", prefix.op.symbol, prefix.arg.ident.symbol);
+                (head, Some(self.body(&prefix.body)))
+            }
+        };
+        self.add_decl(&lines, head, body);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_parser::test_utils::parse;
+    use ftl_parser::visitor::visit_ast;
+
+    use crate::pp::Printer;
+
+    const SOURCE: &str = r#"
+# Example from the README
+decl nop [lang_nop] : void
+
+decl add int int [lang_add inline] : int
+decl mult int int [lang_mult] : int
+
+infix 5 @@ a b: a + b
+infix 10 $ func expr: @func expr
+infix 50 - a b: @sub a b
+infix 50 + a b: @add a b
+infix 100 * a b: @mult a b
+
+def multiple a b c: a + b + c
+def call_mult: @multiple 1 2 3 + 2
+
+decl foo int int : int
+def foo a b: a+b # trailing comment
+
+def bar: 1 - 2 + 3 `foo_bar 4 $ 5 * 0
+
+def foo_bar: @bar @@ 1 + 2 + @foo 3 (2+2*2) $ 2
+def test: 2 + 2 * 2
+decl test (int)int (int)int: (int int) int
+def test3 [test4 test1] : 2+2*2*2*2*2*2
+# last comment
+"#;
+
+    fn format(src: &str, width: usize) -> String {
+        let ast = parse(src);
+        let mut f = Formatter::new()
+            .width(width)
+            .comments(collect_comments(src))
+            .source(src);
+        visit_ast(&mut f, &ast);
+        f.get().to_owned()
+    }
+
+    fn tree(src: &str) -> String {
+        let ast = parse(src);
+        let mut p = Printer::new();
+        visit_ast(&mut p, &ast);
+        let mut out = Vec::new();
+        p.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn formatted_source_parses_to_the_same_tree() {
+        for width in &[DEFAULT_WIDTH, 30, 10] {
+            let formatted = format(SOURCE, *width);
+            assert_eq!(tree(&formatted), tree(SOURCE));
+        }
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let formatted = format(SOURCE, DEFAULT_WIDTH);
+        assert_eq!(format(&formatted, DEFAULT_WIDTH), formatted);
+    }
+
+    #[test]
+    fn formatting_declarations() {
+        assert_eq!(
+            format(
+                "decl  test (int)int [b a]:(int int) int\ndef f a  b[x]:a+b* @g a (1)",
+                DEFAULT_WIDTH
+            ),
+            "decl test (int) int [b a]: (int int) int\ndef f a b [x]: a + b * @g a (1)\n"
+        );
+        assert_eq!(
+            format("infix 5   <==> a b :1 `foo 2", DEFAULT_WIDTH),
+            "infix 5 <==> a b: 1 `foo 2\n"
+        );
+    }
+
+    #[test]
+    fn formatting_attribute_arguments() {
+        assert_eq!(
+            format(
+                "decl f [lang(\"add\")  inline( always ) x 1 2]:int",
+                DEFAULT_WIDTH
+            ),
+            "decl f [lang \"add\" inline(always) x 1 2]: int\n"
+        );
+    }
+
+    #[test]
+    fn blank_lines_are_collapsed() {
+        assert_eq!(
+            format("def a: 1\n\n\n\ndef b: 2\ndef c: 3\n", DEFAULT_WIDTH),
+            "def a: 1\n\ndef b: 2\ndef c: 3\n"
+        );
+    }
+
+    #[test]
+    fn comments_are_kept() {
+        assert_eq!(
+            format(
+                "# first\n\n# second\ndef a:\n 1 + # inner\n 2 # trailing\n\n# last",
+                DEFAULT_WIDTH
+            ),
+            "# first\n\n# second\ndef a:\n    # inner\n    1 + 2 # trailing\n\n# last\n"
+        );
+    }
+
+    #[test]
+    fn comments_stay_inside_their_declarations() {
+        let src = "def f a:\n  # inside\n  a + 1\ndecl g # type\n  [x]: int";
+        let formatted = format(src, DEFAULT_WIDTH);
+        assert_eq!(
+            formatted,
+            "def f a:\n    # inside\n    a + 1\ndecl g [x]:\n    # type\n    int\n"
+        );
+        assert_eq!(tree(&formatted), tree(src));
+        assert_eq!(format(&formatted, DEFAULT_WIDTH), formatted);
+    }
+
+    #[test]
+    fn block_and_doc_comments_are_kept() {
+        assert_eq!(
+            format(
+                "#{ first\n #{ nested }#\n}#\n\n## doc\ndef a: \"#\" #{ inner }#\ndef b: '#'",
+                DEFAULT_WIDTH
+            ),
+            "#{ first\n #{ nested }#\n}#\n\n## doc\ndef a: \"#\" #{ inner }#\ndef b: '#'\n"
+        );
+    }
+
+    #[test]
+    fn long_bodies_are_wrapped() {
+        assert_eq!(
+            format("def function a b: a + b", 20),
+            "def function a b:\n    a + b\n"
+        );
+        assert_eq!(
+            format("def f: 1111 + 2222 + 3333 `foo 4444", 20),
+            "def f:\n    1111 + 2222\n        + 3333\n        `foo 4444\n"
+        );
+    }
+
+    #[test]
+    fn formatting_prefix_operators() {
+        assert_eq!(
+            format(
+                "prefix  - a: @neg a\nprefix 3 ! a : a\ndef f a: - a+ - -a * ! @g 1",
+                DEFAULT_WIDTH
+            ),
+            "prefix - a: @neg a\nprefix 3 ! a: a\ndef f a: -a + - -a * ! @g 1\n"
+        );
+    }
+
+    #[test]
+    fn negative_literals_are_not_prefix_calls() {
+        let source = "def f a: -1 - - 1 * (- 2) + -0x_1F * (-2.5)\n";
+        assert_eq!(format(source, DEFAULT_WIDTH), source);
+        assert_eq!(tree(&format(source, DEFAULT_WIDTH)), tree(source));
+    }
+
+    #[test]
+    fn formatting_operator_sections() {
+        assert_eq!(
+            format("def f: @map ( + ) (a+1 -)  (2*)", DEFAULT_WIDTH),
+            "def f: @map (+) (a + 1 -) (2 *)\n"
+        );
+    }
+
+    #[test]
+    fn literals_keep_their_spelling() {
+        let source = "def f: 0xFF + 0b1010 + 0o17 + 1_000_000 `g 0x_dead_BEEF\n\
+                      def g: 1.50 +. 2e10 . 1_0.0e-1 `g 1E-7\n";
+        assert_eq!(format(source, DEFAULT_WIDTH), source);
+        assert_eq!(tree(&format(source, DEFAULT_WIDTH)), tree(source));
+    }
+
+    #[test]
+    fn literals_without_source_are_written_as_values() {
+        let src = "def f: 0xFF + 1_000 `g 1.50 +. 2e10 . 1_0.0e-1 `g 1e-7";
+        let mut f = Formatter::new();
+        visit_ast(&mut f, &parse(src));
+        assert_eq!(
+            f.get(),
+            "def f: 255 + 1000 `g 1.5 +. 20000000000.0 . 1.0 `g 1e-7\n"
+        );
+    }
+
+    #[test]
+    fn formatting_string_and_char_literals() {
+        let source = r#"decl p str char: void
+def f:  @p "a\tb\"c\u{7}" '\''
+def g: @p "\u{48}'" '"'"#;
+        assert_eq!(
+            format(source, DEFAULT_WIDTH),
+            r#"decl p str char: void
+def f: @p "a\tb\"c\u{7}" '\''
+def g: @p "\u{48}'" '"'
+"#
+        );
+    }
+}
@@ -117,6 +117,20 @@ impl<'a, P: Pointer> Pass<'a, P> for GetNode<'a, InfixDef<P>> {
     }
 }
 
+impl<'a, P: Pointer> Pass<'a, P> for GetNode<'a, PrefixDef<P>> {
+    fn visit_module(&mut self, _node: &'a Module<P>) {
+        self.run_already = true;
+    }
+
+    fn visit_prefix_def(&mut self, node: &'a PrefixDef<P>) {
+        if node.id == self.id {
+            self.set_node(node);
+            return;
+        }
+        walk_prefix_def(self, node);
+    }
+}
+
 impl<'a, P: Pointer> Pass<'a, P> for GetNode<'a, FuncArg<P>> {
     fn visit_module(&mut self, _node: &'a Module<P>) {
         self.run_already = true;
@@ -195,6 +209,34 @@ impl<'a, P: Pointer> Pass<'a, P> for GetNode<'a, InfixOpCall<P>> {
     }
 }
 
+impl<'a, P: Pointer> Pass<'a, P> for GetNode<'a, PrefixOpCall<P>> {
+    fn visit_module(&mut self, _node: &'a Module<P>) {
+        self.run_already = true;
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &'a PrefixOpCall<P>) {
+        if node.id == self.id {
+            self.set_node(node);
+            return;
+        }
+        walk_prefix_op_call(self, node);
+    }
+}
+
+impl<'a, P: Pointer> Pass<'a, P> for GetNode<'a, Section<P>> {
+    fn visit_module(&mut self, _node: &'a Module<P>) {
+        self.run_already = true;
+    }
+
+    fn visit_section(&mut self, node: &'a Section<P>) {
+        if node.id == self.id {
+            self.set_node(node);
+            return;
+        }
+        walk_section(self, node);
+    }
+}
+
 impl<'a, P: Pointer> Pass<'a, P> for GetNode<'a, FuncCall<P>> {
     fn visit_module(&mut self, _node: &'a Module<P>) {
         self.run_already = true;
@@ -245,7 +287,6 @@ impl<'a, P: Pointer> Pass<'a, P> for GetNode<'a, Op<P>> {
     fn visit_op(&mut self, node: &'a Op<P>) {
         if node.id == self.id {
             self.set_node(node);
-            return;
         }
     }
 }
@@ -1,5 +1,107 @@
+pub mod ac;
+pub mod av;
 pub mod dm;
+pub mod doc;
+pub mod dot;
 pub mod epr;
+pub mod fmt;
 pub mod ftd;
 pub mod gn;
+pub mod na;
+pub mod ni;
 pub mod pp;
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use ftl_lexer::{token, Lexer};
+    use ftl_parser::visitor_mut::visit_ast_mut;
+    use ftl_parser::Parser;
+    use ftl_session::Session;
+    use ftl_source::string;
+    use ftl_utility::RcRef;
+
+    use crate::dm::DeclarationMerge;
+    use crate::epr::ExprPrecReassoc;
+
+    /// Allocator counting allocations made by each thread,
+    /// so the tests running in parallel don't disturb each other.
+    struct Counting;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATIONS.with(Cell::get);
+        let res = f();
+        (res, ALLOCATIONS.with(Cell::get) - before)
+    }
+
+    /// Module with the given number of declarations
+    /// reusing a handful of names and operators.
+    fn module(decls: usize) -> String {
+        let mut src = String::from("infix 5 + a b: a\ninfix 10 * a b: b\n");
+        for i in 0..decls {
+            let name = format!("f{}", i % 10);
+            if i % 2 == 0 {
+                src += &format!("decl {}: (int int) int\n", name);
+            } else {
+                src += &format!("def {} a b: a + b * @{} b a + 1\n", name, name);
+            }
+        }
+        src
+    }
+
+    /// Lexes, parses, reassociates and merges a module of a few
+    /// thousand declarations. Symbols owning a `String` took an
+    /// allocation for every one lexed or cloned, the interned ones
+    /// allocate only for the distinct strings.
+    #[test]
+    fn pipeline_allocations() {
+        let src = module(4000);
+        let sess = RcRef::new(Session::new(string::String::from(src.as_str())));
+        let (symbols, lexing) = allocations(|| {
+            Lexer::new(sess.clone())
+                .filter(|tok| matches!(tok.value, token::Value::Symbol(_)))
+                .count()
+        });
+        assert!(
+            lexing * 4 < symbols,
+            "{} allocations lexing {} symbols",
+            lexing,
+            symbols
+        );
+
+        let sess = RcRef::new(Session::new(string::String::from(src.as_str())));
+        let mut ast = Parser::new(Lexer::new(sess.clone()), sess.clone()).parse();
+        assert_eq!(sess.borrow().handler.error_msg(), None);
+        let ((), passes) = allocations(|| {
+            visit_ast_mut(&mut ExprPrecReassoc::new(&mut sess.borrow_mut()), &mut ast);
+            visit_ast_mut(&mut DeclarationMerge::new(), &mut ast);
+        });
+        assert_eq!(sess.borrow().handler.error_msg(), None);
+        assert!(
+            passes < symbols,
+            "{} allocations running EPR and DM on {} symbols",
+            passes,
+            symbols
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use ftl_parser::ast::*;
+use ftl_parser::visitor::*;
+
+use ftl_source::{Pointer, Span};
+
+use crate::ni::NodeRef;
+
+/// Traverses the syntax tree in search of
+/// the innermost node whose span contains the given pointer.
+///
+/// Spans are treated as half open ranges, the node contains
+/// every pointer from its beginning up to, but not including, its end.
+/// Nodes without their own span, like calls or definitions,
+/// are never the innermost ones but are part of the enclosing chain.
+///
+/// # Example
+///
+/// ```ignore
+/// let ptr = sess.borrow().src.borrow().ptr_from_utf16(12, 7).unwrap();
+/// let mut pass = NodeAt::new(ptr);
+/// visit_ast(&mut pass, &ast);
+/// if let Some(NodeRef::Ident(ident)) = pass.get() {
+///     // ...
+/// }
+/// ```
+pub struct NodeAt<'ast, P: Pointer> {
+    ptr: P,
+    /// Nodes being visited, from the module down.
+    path: Vec<NodeRef<'ast, P>>,
+    /// Path to the innermost node found so far.
+    found: Vec<NodeRef<'ast, P>>,
+
+    run_already: bool,
+}
+
+impl<'ast, P: Pointer> NodeAt<'ast, P> {
+    /// Returns new NodeAt pass which upon visiting the
+    /// syntax tree will search for the node at the pointer.
+    pub fn new(ptr: P) -> Self {
+        Self {
+            ptr,
+            path: Vec::new(),
+            found: Vec::new(),
+            run_already: false,
+        }
+    }
+
+    /// Returns the innermost node containing the pointer
+    /// or None if no node contains it.
+    ///
+    /// This method should be run only after a syntax tree
+    /// has already been visited. Otherwise it panics.
+    pub fn get(&self) -> Option<NodeRef<'ast, P>> {
+        self.chain().last().cloned()
+    }
+
+    /// Returns the innermost node containing the pointer preceded
+    /// by all the nodes enclosing it, starting with the module.
+    /// Empty if no node contains the pointer.
+    ///
+    /// This method should be run only after a syntax tree
+    /// has already been visited. Otherwise it panics.
+    pub fn chain(&self) -> &[NodeRef<'ast, P>] {
+        if !self.run_already {
+            panic!(
+                "Accessing result of the NodeAt pass should only be done after
+                visiting a sytax tree."
+            );
+        }
+        &self.found
+    }
+
+    /// Returns iterator over the nodes enclosing the innermost
+    /// node containing the pointer, starting with its parent.
+    ///
+    /// This method should be run only after a syntax tree
+    /// has already been visited. Otherwise it panics.
+    pub fn ancestors(&self) -> impl Iterator<Item = NodeRef<'ast, P>> + '_ {
+        self.chain().iter().rev().skip(1).cloned()
+    }
+
+    fn contains(&self, span: &Span<P>) -> bool {
+        let offset = self.ptr.offset();
+        span.beg.offset() <= offset && offset < span.end.offset()
+    }
+
+    /// Visits children of the node with `f` if the node
+    /// has no span or its span contains the pointer.
+    fn enter(&mut self, node: NodeRef<'ast, P>, f: impl FnOnce(&mut Self)) {
+        let span = node.span();
+        if let Some(span) = span {
+            if !self.contains(span) {
+                return;
+            }
+        }
+        self.path.push(node);
+        if span.is_some() {
+            self.found = self.path.clone();
+        }
+        f(self);
+        self.path.pop();
+    }
+}
+
+impl<'ast, P: Pointer> Pass<'ast, P> for NodeAt<'ast, P> {
+    fn visit_module(&mut self, node: &'ast Module<P>) {
+        self.run_already = true;
+        self.path.clear();
+        self.found.clear();
+        self.enter(NodeRef::Module(node), |s| walk_module(s, node));
+    }
+
+    fn visit_top_level_decl(&mut self, node: &'ast TopLevelDecl<P>) {
+        self.enter(NodeRef::TopLevelDecl(node), |s| {
+            walk_top_level_decl(s, node)
+        });
+    }
+
+    fn visit_func_decl(&mut self, node: &'ast FuncDecl<P>) {
+        self.enter(NodeRef::FuncDecl(node), |s| walk_func_decl(s, node));
+    }
+
+    fn visit_func_def(&mut self, node: &'ast FuncDef<P>) {
+        self.enter(NodeRef::FuncDef(node), |s| walk_func_def(s, node));
+    }
+
+    fn visit_infix_def(&mut self, node: &'ast InfixDef<P>) {
+        self.enter(NodeRef::InfixDef(node), |s| walk_infix_def(s, node));
+    }
+
+    fn visit_prefix_def(&mut self, node: &'ast PrefixDef<P>) {
+        self.enter(NodeRef::PrefixDef(node), |s| walk_prefix_def(s, node));
+    }
+
+    fn visit_func_arg(&mut self, node: &'ast FuncArg<P>) {
+        self.enter(NodeRef::FuncArg(node), |s| walk_func_arg(s, node));
+    }
+
+    fn visit_func_attr(&mut self, node: &'ast FuncAttr<P>) {
+        self.enter(NodeRef::FuncAttr(node), |s| walk_func_attr(s, node));
+    }
+
+    fn visit_expr(&mut self, node: &'ast Expr<P>) {
+        self.enter(NodeRef::Expr(node), |s| walk_expr(s, node));
+    }
+
+    fn visit_infix_func_call(&mut self, node: &'ast InfixFuncCall<P>) {
+        self.enter(NodeRef::InfixFuncCall(node), |s| {
+            walk_infix_func_call(s, node)
+        });
+    }
+
+    fn visit_infix_op_call(&mut self, node: &'ast InfixOpCall<P>) {
+        self.enter(NodeRef::InfixOpCall(node), |s| walk_infix_op_call(s, node));
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &'ast PrefixOpCall<P>) {
+        self.enter(NodeRef::PrefixOpCall(node), |s| {
+            walk_prefix_op_call(s, node)
+        });
+    }
+
+    fn visit_section(&mut self, node: &'ast Section<P>) {
+        self.enter(NodeRef::Section(node), |s| walk_section(s, node));
+    }
+
+    fn visit_func_call(&mut self, node: &'ast FuncCall<P>) {
+        self.enter(NodeRef::FuncCall(node), |s| walk_func_call(s, node));
+    }
+
+    fn visit_parenthesed(&mut self, node: &'ast Paren<P>) {
+        self.enter(NodeRef::Paren(node), |s| walk_paren_expr(s, node));
+    }
+
+    fn visit_lit(&mut self, node: &'ast Lit<P>) {
+        self.enter(NodeRef::Lit(node), |_| ());
+    }
+
+    fn visit_ident(&mut self, node: &'ast Ident<P>) {
+        self.enter(NodeRef::Ident(node), |_| ());
+    }
+
+    fn visit_op(&mut self, node: &'ast Op<P>) {
+        self.enter(NodeRef::Op(node), |_| ());
+    }
+
+    fn visit_type(&mut self, node: &'ast Type<P>) {
+        self.enter(NodeRef::Type(node), |s| walk_type(s, node));
+    }
+
+    fn visit_func_type(&mut self, node: &'ast FuncType<P>) {
+        self.enter(NodeRef::FuncType(node), |s| walk_func_type(s, node));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_parser::test_utils::parse;
+    use ftl_source::string::{Pointer as Ptr, String as Src};
+    use ftl_source::Source;
+
+    const SOURCE: &str = r#"
+decl add int int [lang_add] : int
+infix 5 + a b: @add a b
+def foo a b: (a + b) `foo 25
+"#;
+
+    fn node_at<'a>(ast: &'a AST<Src>, src: &Src, line: usize, col: usize) -> NodeAt<'a, Ptr> {
+        let mut pass = NodeAt::new(src.ptr_from_utf16(line, col).unwrap());
+        visit_ast(&mut pass, ast);
+        pass
+    }
+
+    fn kind(node: &NodeRef<'_, Ptr>) -> &'static str {
+        match node {
+            NodeRef::Module(_) => "Module",
+            NodeRef::TopLevelDecl(_) => "TopLevelDecl",
+            NodeRef::FuncDecl(_) => "FuncDecl",
+            NodeRef::FuncDef(_) => "FuncDef",
+            NodeRef::InfixDef(_) => "InfixDef",
+            NodeRef::PrefixDef(_) => "PrefixDef",
+            NodeRef::FuncArg(_) => "FuncArg",
+            NodeRef::FuncAttr(_) => "FuncAttr",
+            NodeRef::Expr(_) => "Expr",
+            NodeRef::InfixFuncCall(_) => "InfixFuncCall",
+            NodeRef::InfixOpCall(_) => "InfixOpCall",
+            NodeRef::PrefixOpCall(_) => "PrefixOpCall",
+            NodeRef::Section(_) => "Section",
+            NodeRef::FuncCall(_) => "FuncCall",
+            NodeRef::Paren(_) => "Paren",
+            NodeRef::Lit(_) => "Lit",
+            NodeRef::Ident(_) => "Ident",
+            NodeRef::Op(_) => "Op",
+            NodeRef::Type(_) => "Type",
+            NodeRef::FuncType(_) => "FuncType",
+        }
+    }
+
+    #[test]
+    fn innermost_node_and_its_chain() {
+        let (ast, src) = (parse(SOURCE), Src::from(SOURCE));
+        // `b` in `(a + b)`
+        let pass = node_at(&ast, &src, 4, 19);
+        match pass.get() {
+            Some(NodeRef::Ident(ident)) => assert_eq!(ident.symbol, "b"),
+            _ => panic!("expected identifier"),
+        }
+        let chain: Vec<&str> = pass.chain().iter().map(kind).collect();
+        assert_eq!(
+            chain,
+            vec![
+                "Module",
+                "TopLevelDecl",
+                "FuncDef",
+                "Expr",
+                "InfixFuncCall",
+                "Expr",
+                "Paren",
+                "Expr",
+                "InfixOpCall",
+                "Expr",
+                "Ident"
+            ]
+        );
+        let ancestors: Vec<&str> = pass.ancestors().map(|n| kind(&n)).collect();
+        assert_eq!(ancestors.first(), Some(&"Expr"));
+        assert_eq!(ancestors.last(), Some(&"Module"));
+    }
+
+    #[test]
+    fn every_column_of_token_finds_it() {
+        let (ast, src) = (parse(SOURCE), Src::from(SOURCE));
+        // `25`
+        for col in 27..29 {
+            match node_at(&ast, &src, 4, col).get() {
+                Some(NodeRef::Lit(Lit {
+                    kind: LitKind::Int(25),
+                    ..
+                })) => (),
+                _ => panic!("expected literal at column {}", col),
+            }
+        }
+        // `+` in infix definition
+        match node_at(&ast, &src, 3, 9).get() {
+            Some(NodeRef::Op(op)) => assert_eq!(op.symbol, "+"),
+            _ => panic!("expected operator"),
+        }
+    }
+
+    #[test]
+    fn nodes_without_spans_are_reached() {
+        let (ast, src) = (parse(SOURCE), Src::from(SOURCE));
+        // `lang_add` attribute
+        let pass = node_at(&ast, &src, 2, 20);
+        let chain: Vec<&str> = pass.chain().iter().map(kind).collect();
+        assert_eq!(
+            chain,
+            vec!["Module", "TopLevelDecl", "FuncDecl", "FuncAttr", "Ident"]
+        );
+        // argument `b`
+        let pass = node_at(&ast, &src, 4, 11);
+        let chain: Vec<&str> = pass.chain().iter().map(kind).collect();
+        assert_eq!(
+            chain,
+            vec!["Module", "TopLevelDecl", "FuncDef", "FuncArg", "Ident"]
+        );
+    }
+
+    #[test]
+    fn position_outside_of_nodes() {
+        let (ast, src) = (parse(SOURCE), Src::from(SOURCE));
+        // Empty first line.
+        let pass = node_at(&ast, &src, 1, 1);
+        assert!(pass.get().is_none());
+        assert!(pass.chain().is_empty());
+        // Whitespace between the declarations's tokens.
+        let pass = node_at(&ast, &src, 4, 8);
+        let chain: Vec<&str> = pass.chain().iter().map(kind).collect();
+        assert_eq!(chain, vec!["Module", "TopLevelDecl"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn accessing_result_before_visiting_panics() {
+        let src = Src::from("def f: 1");
+        NodeAt::<Ptr>::new(src.curr_ptr()).get();
+    }
+}
@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+
+use ftl_parser::ast::*;
+use ftl_parser::visitor::*;
+
+use ftl_source::{Pointer, Span};
+
+/// Reference to any syntax tree node having its own id.
+pub enum NodeRef<'ast, P: Pointer> {
+    Module(&'ast Module<P>),
+    TopLevelDecl(&'ast TopLevelDecl<P>),
+    FuncDecl(&'ast FuncDecl<P>),
+    FuncDef(&'ast FuncDef<P>),
+    InfixDef(&'ast InfixDef<P>),
+    PrefixDef(&'ast PrefixDef<P>),
+    FuncArg(&'ast FuncArg<P>),
+    FuncAttr(&'ast FuncAttr<P>),
+    Expr(&'ast Expr<P>),
+    InfixFuncCall(&'ast InfixFuncCall<P>),
+    InfixOpCall(&'ast InfixOpCall<P>),
+    PrefixOpCall(&'ast PrefixOpCall<P>),
+    Section(&'ast Section<P>),
+    FuncCall(&'ast FuncCall<P>),
+    Paren(&'ast Paren<P>),
+    Lit(&'ast Lit<P>),
+    Ident(&'ast Ident<P>),
+    Op(&'ast Op<P>),
+    Type(&'ast Type<P>),
+    FuncType(&'ast FuncType<P>),
+}
+
+// Derived implementations would require `P: Copy`.
+impl<P: Pointer> Clone for NodeRef<'_, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Pointer> Copy for NodeRef<'_, P> {}
+
+impl<'ast, P: Pointer> NodeRef<'ast, P> {
+    pub fn id(&self) -> NodeId {
+        match self {
+            NodeRef::Module(node) => node.id,
+            NodeRef::TopLevelDecl(node) => node.id,
+            NodeRef::FuncDecl(node) => node.id,
+            NodeRef::FuncDef(node) => node.id,
+            NodeRef::InfixDef(node) => node.id,
+            NodeRef::PrefixDef(node) => node.id,
+            NodeRef::FuncArg(node) => node.id,
+            NodeRef::FuncAttr(node) => node.id,
+            NodeRef::Expr(node) => node.id,
+            NodeRef::InfixFuncCall(node) => node.id,
+            NodeRef::InfixOpCall(node) => node.id,
+            NodeRef::PrefixOpCall(node) => node.id,
+            NodeRef::Section(node) => node.id,
+            NodeRef::FuncCall(node) => node.id,
+            NodeRef::Paren(node) => node.id,
+            NodeRef::Lit(node) => node.id,
+            NodeRef::Ident(node) => node.id,
+            NodeRef::Op(node) => node.id,
+            NodeRef::Type(node) => node.id,
+            NodeRef::FuncType(node) => node.id,
+        }
+    }
+
+    /// Returns span of the node, if it has one.
+    pub fn span(&self) -> Option<&'ast Span<P>> {
+        match self {
+            NodeRef::TopLevelDecl(node) => Some(&node.span),
+            NodeRef::FuncArg(node) => Some(&node.span),
+            NodeRef::Expr(node) => Some(&node.span),
+            NodeRef::Lit(node) => Some(&node.span),
+            NodeRef::Ident(node) => Some(&node.span),
+            NodeRef::Op(node) => Some(&node.span),
+            NodeRef::Type(node) => Some(&node.span),
+            _ => None,
+        }
+    }
+}
+
+/// Syntax tree node type which can be
+/// returned by the [`NodeIndex::get_as`](struct.NodeIndex.html#method.get_as).
+pub trait IndexedNode<'ast, P: Pointer>: Sized {
+    /// Returns the referenced node if it is of this type.
+    fn from_ref(node: NodeRef<'ast, P>) -> Option<&'ast Self>;
+}
+
+macro_rules! indexed_node {
+    ($($node:ident),*) => {
+        $(
+            impl<'ast, P: Pointer> IndexedNode<'ast, P> for $node<P> {
+                fn from_ref(node: NodeRef<'ast, P>) -> Option<&'ast Self> {
+                    match node {
+                        NodeRef::$node(node) => Some(node),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+indexed_node!(
+    Module,
+    TopLevelDecl,
+    FuncDecl,
+    FuncDef,
+    InfixDef,
+    PrefixDef,
+    FuncArg,
+    FuncAttr,
+    Expr,
+    InfixFuncCall,
+    InfixOpCall,
+    PrefixOpCall,
+    Section,
+    FuncCall,
+    Paren,
+    Lit,
+    Ident,
+    Op,
+    Type,
+    FuncType
+);
+
+struct Entry<'ast, P: Pointer> {
+    node: NodeRef<'ast, P>,
+    parent: Option<NodeId>,
+}
+
+/// Maps every node id in the visited syntax tree
+/// to the node and the id of its parent.
+///
+/// Unlike [`GetNode`](../gn/struct.GetNode.html), which walks the tree
+/// for each lookup, the index is built in one traversal after which
+/// every lookup is done in constant time.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut index = NodeIndex::new();
+/// visit_ast(&mut index, &ast);
+/// let expr: Option<&Expr<_>> = index.get_as(id);
+/// let parent = index.parent(id).and_then(|id| index.get(id));
+/// ```
+pub struct NodeIndex<'ast, P: Pointer> {
+    nodes: HashMap<NodeId, Entry<'ast, P>>,
+    /// Ids of the nodes being visited, the last one is the parent
+    /// of the next added node.
+    parents: Vec<NodeId>,
+}
+
+impl<P: Pointer> Default for NodeIndex<'_, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'ast, P: Pointer> NodeIndex<'ast, P> {
+    /// Creates empty index ready to be filled
+    /// by visiting a syntax tree.
+    pub fn new() -> Self {
+        Self {
+            nodes: HashMap::new(),
+            parents: Vec::new(),
+        }
+    }
+
+    /// Returns node with the given id.
+    pub fn get(&self, id: NodeId) -> Option<NodeRef<'ast, P>> {
+        self.nodes.get(&id).map(|entry| entry.node)
+    }
+
+    /// Returns node with the given id if it is of the requested type.
+    pub fn get_as<T: IndexedNode<'ast, P>>(&self, id: NodeId) -> Option<&'ast T> {
+        self.get(id).and_then(T::from_ref)
+    }
+
+    /// Returns id of the parent of the node with the given id.
+    /// Module, as the root, has no parent.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes.get(&id).and_then(|entry| entry.parent)
+    }
+
+    /// Returns iterator over the ids of the nodes enclosing
+    /// the node with the given id, starting with its parent.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent(id), move |id| self.parent(*id))
+    }
+
+    /// Returns number of the indexed nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds the node and visits its children with `f`.
+    fn add(&mut self, node: NodeRef<'ast, P>, f: impl FnOnce(&mut Self)) {
+        let id = node.id();
+        self.nodes.insert(
+            id,
+            Entry {
+                node,
+                parent: self.parents.last().cloned(),
+            },
+        );
+        self.parents.push(id);
+        f(self);
+        self.parents.pop();
+    }
+}
+
+impl<'ast, P: Pointer> Pass<'ast, P> for NodeIndex<'ast, P> {
+    fn visit_module(&mut self, node: &'ast Module<P>) {
+        self.nodes.clear();
+        self.parents.clear();
+        self.add(NodeRef::Module(node), |s| walk_module(s, node));
+    }
+
+    fn visit_top_level_decl(&mut self, node: &'ast TopLevelDecl<P>) {
+        self.add(NodeRef::TopLevelDecl(node), |s| {
+            walk_top_level_decl(s, node)
+        });
+    }
+
+    fn visit_func_decl(&mut self, node: &'ast FuncDecl<P>) {
+        self.add(NodeRef::FuncDecl(node), |s| walk_func_decl(s, node));
+    }
+
+    fn visit_func_def(&mut self, node: &'ast FuncDef<P>) {
+        self.add(NodeRef::FuncDef(node), |s| walk_func_def(s, node));
+    }
+
+    fn visit_infix_def(&mut self, node: &'ast InfixDef<P>) {
+        self.add(NodeRef::InfixDef(node), |s| walk_infix_def(s, node));
+    }
+
+    fn visit_prefix_def(&mut self, node: &'ast PrefixDef<P>) {
+        self.add(NodeRef::PrefixDef(node), |s| walk_prefix_def(s, node));
+    }
+
+    fn visit_func_arg(&mut self, node: &'ast FuncArg<P>) {
+        self.add(NodeRef::FuncArg(node), |s| walk_func_arg(s, node));
+    }
+
+    fn visit_func_attr(&mut self, node: &'ast FuncAttr<P>) {
+        self.add(NodeRef::FuncAttr(node), |s| walk_func_attr(s, node));
+    }
+
+    fn visit_expr(&mut self, node: &'ast Expr<P>) {
+        self.add(NodeRef::Expr(node), |s| walk_expr(s, node));
+    }
+
+    fn visit_infix_func_call(&mut self, node: &'ast InfixFuncCall<P>) {
+        self.add(NodeRef::InfixFuncCall(node), |s| {
+            walk_infix_func_call(s, node)
+        });
+    }
+
+    fn visit_infix_op_call(&mut self, node: &'ast InfixOpCall<P>) {
+        self.add(NodeRef::InfixOpCall(node), |s| walk_infix_op_call(s, node));
+    }
+
+    fn visit_prefix_op_call(&mut self, node: &'ast PrefixOpCall<P>) {
+        self.add(NodeRef::PrefixOpCall(node), |s| {
+            walk_prefix_op_call(s, node)
+        });
+    }
+
+    fn visit_section(&mut self, node: &'ast Section<P>) {
+        self.add(NodeRef::Section(node), |s| walk_section(s, node));
+    }
+
+    fn visit_func_call(&mut self, node: &'ast FuncCall<P>) {
+        self.add(NodeRef::FuncCall(node), |s| walk_func_call(s, node));
+    }
+
+    fn visit_parenthesed(&mut self, node: &'ast Paren<P>) {
+        self.add(NodeRef::Paren(node), |s| walk_paren_expr(s, node));
+    }
+
+    fn visit_lit(&mut self, node: &'ast Lit<P>) {
+        self.add(NodeRef::Lit(node), |_| ());
+    }
+
+    fn visit_ident(&mut self, node: &'ast Ident<P>) {
+        self.add(NodeRef::Ident(node), |_| ());
+    }
+
+    fn visit_op(&mut self, node: &'ast Op<P>) {
+        self.add(NodeRef::Op(node), |_| ());
+    }
+
+    fn visit_type(&mut self, node: &'ast Type<P>) {
+        self.add(NodeRef::Type(node), |s| walk_type(s, node));
+    }
+
+    fn visit_func_type(&mut self, node: &'ast FuncType<P>) {
+        self.add(NodeRef::FuncType(node), |s| walk_func_type(s, node));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ftl_parser::test_utils::parse;
+    use ftl_source::string::Pointer as Ptr;
+
+    const SOURCE: &str = r#"
+decl add int int [lang_add] : int
+decl test (int)int (int)int: (int int) int
+infix 5 + a b: @add a b
+def foo a b [inline]: (a + b) `foo 2
+"#;
+
+    fn find_ident<'a>(index: &NodeIndex<'a, Ptr>, symbol: &str) -> Vec<&'a Ident<Ptr>> {
+        (0..=index.len() * 2)
+            .filter_map(|id| index.get_as::<Ident<Ptr>>(id))
+            .filter(|ident| ident.symbol == symbol)
+            .collect()
+    }
+
+    #[test]
+    fn every_node_is_indexed() {
+        // Module, TopLevelDecl, FuncDef, FuncDecl, Ident f,
+        // FuncAttr, Ident x, FuncArg, Ident a, Expr, InfixOpCall,
+        // Op, Expr, Ident a, Expr, Lit
+        let ast = parse("def f a [x]: a + 1");
+        let mut index = NodeIndex::new();
+        visit_ast(&mut index, &ast);
+        assert_eq!(index.len(), 16);
+
+        let ast = parse(SOURCE);
+        let mut index = NodeIndex::new();
+        visit_ast(&mut index, &ast);
+        let ids: Vec<NodeId> = (0..=index.len() * 2)
+            .filter(|id| index.get(*id).is_some())
+            .collect();
+        assert_eq!(ids.len(), index.len());
+        for id in ids {
+            let node = index.get(id).unwrap();
+            assert_eq!(node.id(), id);
+            if let NodeRef::Module(_) = node {
+                assert_eq!(index.parent(id), None);
+            } else {
+                assert_eq!(index.ancestors(id).last(), Some(ast.root.id));
+            }
+        }
+    }
+
+    #[test]
+    fn typed_lookup() {
+        let ast = parse(SOURCE);
+        let mut index = NodeIndex::new();
+        visit_ast(&mut index, &ast);
+        let decl = match ast.root.decl[0].kind {
+            TopLevelDeclKind::FunctionDecl(ref decl) => decl,
+            _ => unreachable!(),
+        };
+        assert!(index.get_as::<FuncDecl<Ptr>>(decl.id).is_some());
+        assert!(index.get_as::<Expr<Ptr>>(decl.id).is_none());
+        assert!(index.get_as::<FuncDecl<Ptr>>(usize::MAX).is_none());
+
+        let attr = &decl.attrs[0];
+        assert_eq!(
+            index.get_as::<FuncAttr<Ptr>>(attr.id).unwrap().ident.symbol,
+            "lang_add"
+        );
+        assert_eq!(index.parent(attr.ident.id), Some(attr.id));
+        assert_eq!(index.parent(attr.id), Some(decl.id));
+        let ty = decl.ty.as_ref().unwrap();
+        assert!(index.get_as::<Type<Ptr>>(ty.id).is_some());
+    }
+
+    #[test]
+    fn parents_of_expressions() {
+        let ast = parse(SOURCE);
+        let mut index = NodeIndex::new();
+        visit_ast(&mut index, &ast);
+
+        let b = find_ident(&index, "b");
+        // infix and def arguments and their uses
+        assert_eq!(b.len(), 4);
+        let used = b
+            .iter()
+            .rfind(|b| {
+                matches!(
+                    index.parent(b.id).and_then(|id| index.get(id)),
+                    Some(NodeRef::Expr(_))
+                )
+            })
+            .unwrap();
+        let chain: Vec<&str> = index
+            .ancestors(used.id)
+            .map(|id| match index.get(id).unwrap() {
+                NodeRef::Expr(_) => "Expr",
+                NodeRef::InfixOpCall(_) => "InfixOpCall",
+                NodeRef::Paren(_) => "Paren",
+                NodeRef::InfixFuncCall(_) => "InfixFuncCall",
+                NodeRef::FuncDef(_) => "FuncDef",
+                NodeRef::TopLevelDecl(_) => "TopLevelDecl",
+                NodeRef::Module(_) => "Module",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(
+            chain,
+            vec![
+                "Expr",
+                "InfixOpCall",
+                "Expr",
+                "Paren",
+                "Expr",
+                "InfixFuncCall",
+                "Expr",
+                "FuncDef",
+                "TopLevelDecl",
+                "Module"
+            ]
+        );
+    }
+}
@@ -6,8 +6,11 @@ use std::collections::BTreeSet;
 use ftl_parser::ast::*;
 use ftl_parser::visitor::*;
 
+use ftl_session::symbol::Symbol;
 use ftl_source::Pointer;
 
+use crate::fmt::{attr_repr, type_repr};
+
 /// Pretty prints visited syntax tree.
 /// UTF-8 support in terminal is required.
 #[derive(Default)]
@@ -93,24 +96,6 @@ impl Printer {
     fn stop_line_at(&mut self, indent: usize) {
         self.draw_line_at_indent.remove(&indent);
     }
-
-    /// Returns string representation of the type node.
-    fn strfy_type<P: Pointer>(ty: &Type<P>) -> String {
-        match ty.kind {
-            TypeKind::Literal(ref lit) => match lit {
-                LitType::Int => String::from("int"),
-                LitType::Void => String::from("void"),
-            },
-            TypeKind::Function(ref func_t) => {
-                let mut repr = String::from("(");
-                for arg in &func_t.args {
-                    repr += &format!(" {}", Self::strfy_type(arg));
-                }
-                repr += &format!("): {}", Self::strfy_type(&func_t.ret));
-                repr
-            }
-        }
-    }
 }
 
 impl<P: Pointer> Pass<'_, P> for Printer {
@@ -134,13 +119,13 @@ impl<P: Pointer> Pass<'_, P> for Printer {
             "FuncDecl {} type({}) attrs(",
             node.ident.symbol,
             if let Some(ref ty) = node.ty {
-                Self::strfy_type(&ty)
+                type_repr(ty)
             } else {
                 String::from("")
             }
         );
         for attr in &node.attrs {
-            repr += &format!(" {},", &attr.ident.symbol)
+            repr += &format!(" {},", attr_repr(attr))
         }
         repr += ")";
         self.add(&repr);
@@ -170,6 +155,21 @@ impl<P: Pointer> Pass<'_, P> for Printer {
         self.indent -= 1;
     }
 
+    fn visit_prefix_def(&mut self, node: &PrefixDef<P>) {
+        let prec = match node.precedence {
+            Some(prec) => format!("({})", prec),
+            None => String::new(),
+        };
+        let repr = format!(
+            "Prefix{} {} arg({})",
+            prec, node.op.symbol, node.arg.ident.symbol
+        );
+        self.add(&repr);
+        self.indent += 1;
+        self.visit_expr(&node.body);
+        self.indent -= 1;
+    }
+
     fn visit_expr(&mut self, node: &Expr<P>) {
         walk_expr(self, node);
     }
@@ -208,6 +208,27 @@ impl<P: Pointer> Pass<'_, P> for Printer {
         self.indent -= 1;
     }
 
+    fn visit_prefix_op_call(&mut self, node: &PrefixOpCall<P>) {
+        self.add(&format!("PrefixOp {}", node.op.symbol));
+        self.indent += 1;
+        self.visit_expr(&node.expr);
+        self.indent -= 1;
+    }
+
+    fn visit_section(&mut self, node: &Section<P>) {
+        let kind = match (&node.lhs, &node.rhs) {
+            (Some(_), _) => "LeftSection",
+            (_, Some(_)) => "RightSection",
+            _ => "Section",
+        };
+        self.add(&format!("{} {}", kind, node.op.symbol));
+        self.indent += 1;
+        for operand in node.lhs.iter().chain(node.rhs.iter()) {
+            self.visit_expr(operand);
+        }
+        self.indent -= 1;
+    }
+
     fn visit_parenthesed(&mut self, node: &Paren<P>) {
         self.add("Parenthesed");
         self.indent += 1;
@@ -215,10 +236,22 @@ impl<P: Pointer> Pass<'_, P> for Printer {
         self.indent -= 1;
     }
 
-    fn visit_int_lit(&mut self, val: u64) {
+    fn visit_int_lit(&mut self, val: i64) {
         self.add(&format!("Int: {}", val))
     }
 
+    fn visit_float_lit(&mut self, val: f64) {
+        self.add(&format!("Float: {:?}", val))
+    }
+
+    fn visit_str_lit(&mut self, val: Symbol) {
+        self.add(&format!("Str: {:?}", val))
+    }
+
+    fn visit_char_lit(&mut self, val: char) {
+        self.add(&format!("Char: {:?}", val))
+    }
+
     fn visit_ident(&mut self, node: &Ident<P>) {
         self.add(&format!("Ident: {}", node.symbol))
     }
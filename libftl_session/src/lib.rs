@@ -8,9 +8,14 @@ use ftl_error::{Handler, LangError};
 use ftl_source::{Pointer, Source};
 use ftl_utility::RcRef;
 
+use symbol::{Interner, Symbol};
+
+pub mod symbol;
+
 pub struct Session<S: Source> {
     pub handler: Handler<S>,
     pub src: RcRef<S>,
+    pub symbols: Interner,
 }
 
 impl<S, P> Session<S>
@@ -23,9 +28,15 @@ where
         Session {
             src: src.clone(),
             handler: Handler::new(src.clone()),
+            symbols: Interner::new(),
         }
     }
 
+    /// Returns symbol of the string interned by this session.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        self.symbols.intern(s)
+    }
+
     pub fn err(&mut self, err: Box<dyn LangError<Ptr = P>>) {
         self.handler.err(err);
     }
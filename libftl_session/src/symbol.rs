@@ -0,0 +1,277 @@
+//! Interning of the identifiers, operators and string literals.
+//!
+//! Every [`Session`](../struct.Session.html) owns an
+//! [`Interner`](struct.Interner.html) which stores each distinct
+//! string it has seen once and hands out cheap
+//! [`Symbol`](struct.Symbol.html) handles to it. Strings are reference
+//! counted, so they are freed once the session and all the tokens and
+//! trees holding its symbols are dropped. Cloning a symbol only bumps
+//! the reference count. Symbols sharing the string are found equal
+//! without comparing it, any others compare their strings, so the
+//! symbols of different sessions, or created without any, are still
+//! equal if their strings are. Hashing always hashes the string.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// Handle to the interned string.
+///
+/// # Examples
+///
+/// ```
+/// use ftl_session::symbol::{Interner, Symbol};
+///
+/// let mut interner = Interner::new();
+/// let foo = interner.intern("foo");
+/// assert_eq!(foo, interner.intern(&String::from("foo")));
+/// assert_ne!(foo, interner.intern("bar"));
+/// assert_eq!(foo, Symbol::new("foo"));
+/// assert_eq!(foo, "foo");
+/// assert_eq!(foo.len(), 3);
+/// ```
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    /// Returns symbol of the string which is not shared with
+    /// any interner, for the symbols created outside of the session.
+    pub fn new(s: &str) -> Self {
+        Symbol(s.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Set of the strings interned by the session.
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns symbol of the string, interning it
+    /// if it has not been seen yet.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(interned) = self.strings.get(s) {
+            return Symbol(interned.clone());
+        }
+        let interned: Rc<str> = s.into();
+        self.strings.insert(interned.clone());
+        Symbol(interned)
+    }
+
+    /// Returns number of the distinct strings interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(s: String) -> Self {
+        Self::new(&s)
+    }
+}
+
+// Symbols sharing the string are equal without comparing
+// it, the unequal ones always have to compare the strings.
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other
+    }
+}
+
+/// Symbols are ordered by their strings.
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::collections::HashMap;
+
+    /// Allocator counting allocations made by each thread,
+    /// so the tests running in parallel don't disturb each other.
+    struct Counting;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    fn allocations(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(Cell::get);
+        f();
+        ALLOCATIONS.with(Cell::get) - before
+    }
+
+    #[test]
+    fn equal_strings_are_interned_once() {
+        let mut interner = Interner::new();
+        let a = interner.intern("interned_once");
+        let b = interner.intern(&(String::from("interned_") + "once"));
+        assert_eq!(a, b);
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn interning_seen_strings_does_not_allocate() {
+        let names: Vec<String> = (0..100).map(|i| format!("name_{}", i)).collect();
+        let mut interner = Interner::new();
+        let mut symbols = Vec::with_capacity(100 * names.len());
+        let first = allocations(|| {
+            for name in &names {
+                symbols.push(interner.intern(name));
+            }
+        });
+        assert!(first >= names.len());
+        let repeated = allocations(|| {
+            for _ in 1..100 {
+                for name in &names {
+                    symbols.push(interner.intern(name));
+                }
+            }
+        });
+        assert_eq!(repeated, 0);
+        assert_eq!(interner.len(), names.len());
+    }
+
+    #[test]
+    fn strings_are_freed_with_the_symbols() {
+        let mut interner = Interner::new();
+        let symbol = interner.intern("freed");
+        let weak = Rc::downgrade(&symbol.0);
+        drop(interner);
+        assert_eq!(symbol, "freed");
+        drop(symbol);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn symbols_of_different_interners() {
+        let a = Interner::new().intern("a");
+        let b = Interner::new().intern("a");
+        assert_eq!(a, b);
+        assert_eq!(a, Symbol::new("a"));
+        assert_ne!(a, Symbol::new("b"));
+    }
+
+    #[test]
+    fn symbols_as_keys() {
+        let mut interner = Interner::new();
+        let mut map = HashMap::new();
+        map.insert(interner.intern("+"), 1);
+        map.insert(interner.intern("++"), 2);
+        assert_eq!(map.get(&interner.intern("+")), Some(&1));
+        assert_eq!(map.get(&Symbol::new("++")), Some(&2));
+        assert_eq!(map.get(&interner.intern("+++")), None);
+    }
+
+    #[test]
+    fn symbols_are_ordered_by_strings() {
+        let mut symbols: Vec<Symbol> = ["b", "ab", "a", "ba"]
+            .iter()
+            .map(|s| Symbol::new(s))
+            .collect();
+        symbols.sort();
+        assert_eq!(symbols, vec!["a", "ab", "b", "ba"]);
+        assert_eq!(format!("{} {:?}", symbols[0], symbols[1]), "a \"ab\"");
+    }
+}
@@ -8,6 +8,7 @@ use std::fs;
 use std::io;
 use std::io::{Seek, SeekFrom};
 
+use crate::tracker::{Location, Tracker};
 use crate::Source;
 use ftl_utility::utf8;
 
@@ -15,12 +16,8 @@ pub struct File {
     reader: RefCell<utf8::Reader<fs::File>>,
 
     current: Option<char>,
+    tracker: Tracker,
 
-    curr_line: usize,
-    curr_pos: usize,
-
-    index: u64,
-    switch_line: bool,
     eof_reached: bool,
 }
 
@@ -41,16 +38,13 @@ impl File {
     fn _new(f: fs::File) -> Self {
         let mut s = Self {
             reader: RefCell::new(utf8::Reader::new(f)),
-            curr_line: 1,
-            curr_pos: 0,
-            index: 0,
-            switch_line: false,
+            tracker: Tracker::new(None),
             current: None,
             eof_reached: false,
         };
-        s.current = s.next_char();
-        s.curr_pos = 1;
-        s.index = 0;
+        s.current = s.try_next_char();
+        s.eof_reached = s.current.is_none();
+        s.tracker = Tracker::new(s.current);
         s
     }
 
@@ -68,14 +62,18 @@ impl File {
     }
 
     fn curr_seek_pos(&self) -> u64 {
-        self.reader.borrow_mut().seek(SeekFrom::Current(0)).unwrap()
+        self.reader.borrow_mut().stream_position().unwrap()
     }
 
-    fn update_index(&mut self) {
-        self.index += match self.current {
-            None => 0,
-            Some(ch) => ch.len_utf8() as u64,
-        }
+    /// Calls `f` with the reader set to the beginning of the file
+    /// restoring its position afterwards.
+    fn with_rewinded_reader<R>(&self, f: impl FnOnce(&mut utf8::Reader<fs::File>) -> R) -> R {
+        let saved_pos = self.curr_seek_pos();
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        let res = f(&mut reader);
+        reader.seek(SeekFrom::Start(saved_pos)).unwrap();
+        res
     }
 }
 
@@ -98,65 +96,65 @@ impl Source for File {
         }
         let peek = self.try_next_char();
         self.eof_reached = peek.is_none();
-        self.update_index();
-        if self.switch_line {
-            self.switch_line = false;
-            self.curr_line += 1;
-            self.curr_pos = 0;
-        }
-        if let Some(ch) = peek {
-            if ch == '\n' {
-                self.switch_line = true;
-            }
-            self.curr_pos += 1;
-        }
+        self.tracker.advance(peek);
         self.current = peek;
         self.current
     }
 
     fn curr_ptr(&self) -> Self::Pointer {
         Self::Pointer {
-            index: self.index,
-            curr_line: self.curr_line,
-            curr_pos: self.curr_pos,
+            loc: self.tracker.loc.clone(),
         }
     }
 
     fn source_between(&self, begin: &Self::Pointer, end: &Self::Pointer) -> std::string::String {
-        let mut s = String::new();
-        let saved_pos = self.curr_seek_pos();
-        self.reader
-            .borrow_mut()
-            .seek(SeekFrom::Start(begin.index))
-            .unwrap();
-        while self.curr_seek_pos() < end.index {
-            s.push(match self.reader.borrow_mut().read_utf8_char() {
-                Ok(ch) => ch,
-                _ => unreachable!(),
-            });
-        }
-        self.reader
-            .borrow_mut()
-            .seek(SeekFrom::Start(saved_pos))
-            .unwrap();
-        s
+        let (begin, end) = (begin.loc.offset as u64, end.loc.offset as u64);
+        self.with_rewinded_reader(|reader| {
+            let mut s = String::new();
+            reader.seek(SeekFrom::Start(begin)).unwrap();
+            while reader.stream_position().unwrap() < end {
+                s.push(match reader.read_utf8_char() {
+                    Ok(ch) => ch,
+                    _ => unreachable!(),
+                });
+            }
+            s
+        })
+    }
+
+    fn ptr_from_utf16(&self, line: usize, col: usize) -> Option<Self::Pointer> {
+        self.with_rewinded_reader(|reader| {
+            let chars = std::iter::from_fn(|| reader.read_utf8_char().ok());
+            Tracker::locate_utf16(chars, line, col)
+        })
+        .map(|loc| Self::Pointer { loc })
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Pointer {
-    index: u64,
-    curr_line: usize,
-    curr_pos: usize,
+    loc: Location,
 }
 
 impl crate::Pointer for Pointer {
     fn line(&self) -> usize {
-        self.curr_line
+        self.loc.line
     }
 
     fn position(&self) -> usize {
-        self.curr_pos
+        self.loc.position
+    }
+
+    fn utf16_position(&self) -> usize {
+        self.loc.utf16_in_line + 1
+    }
+
+    fn byte_position(&self) -> usize {
+        self.loc.bytes_in_line + 1
+    }
+
+    fn offset(&self) -> usize {
+        self.loc.offset
     }
 }
 
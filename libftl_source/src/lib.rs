@@ -5,12 +5,19 @@
 //! Source is any unit that could hold a source code of
 //! the program written in the FTL language.
 //!
-//! Module comes with two out of the box implementations
+//! Module comes with few out of the box implementations
 //! of the source units. [`File`](file/struct.File.html)
 //! being file in the filesystem,
-//! and [`String`](string/struct.String.html)
+//! [`String`](string/struct.String.html)
 //! being incode, hardcoded string, which is
-//! nice for testing.
+//! nice for testing,
+//! [`Stream`](stream/struct.Stream.html) reading
+//! any byte stream like the standard input,
+//! [`vfs::File`](vfs/struct.File.html) being
+//! file opened from the in-memory file system
+//! and [`Synthetic`](synthetic/struct.Synthetic.html)
+//! being the source of the syntax trees which haven't been
+//! parsed from any text.
 //!
 //! Rest of the FTL libraries use
 //! [`Source`](trait.Source.html) trait
@@ -24,7 +31,12 @@
 use log::info;
 
 pub mod file;
+pub mod stream;
 pub mod string;
+pub mod synthetic;
+pub mod vfs;
+
+mod tracker;
 
 /// Represents source containing program source code.
 ///
@@ -59,6 +71,42 @@ pub trait Source {
     fn source_with_span(&self, span: &Span<Self::Pointer>) -> String {
         self.source_between(&span.beg, &span.end)
     }
+
+    /// Returns pointer to the character in the given line
+    /// starting at the given utf-16 column, both counted from one.
+    /// This is the way editors (and the language server protocol)
+    /// refer to places in the source.
+    ///
+    /// Line lengths count the new line character so the column
+    /// right after the last character of the line points to the new line.
+    ///
+    /// None should be returned if there is no such character,
+    /// or the column points into the middle of a surrogate pair.
+    fn ptr_from_utf16(&self, line: usize, col: usize) -> Option<Self::Pointer>;
+
+    /// Returns column, starting from one, of the place the pointer
+    /// points to as seen in the editor with tab stops every `tab_width`
+    /// columns. Every other character is counted as one column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tab_width` is 0.
+    fn visual_position(&self, ptr: &Self::Pointer, tab_width: usize) -> usize {
+        assert!(tab_width > 0, "Tab width needs to be positive");
+        let line_beg = match self.ptr_from_utf16(ptr.line(), 1) {
+            Some(beg) => beg,
+            None => return ptr.position(),
+        };
+        let mut col = 0;
+        for ch in self.source_between(&line_beg, ptr).chars() {
+            if ch == '\t' {
+                col += tab_width - col % tab_width;
+            } else {
+                col += 1;
+            }
+        }
+        col + 1
+    }
 }
 
 /// Pointer represent place in corresponding source.
@@ -87,6 +135,21 @@ pub trait Pointer: Clone {
     /// starting from one, from the place in the source
     /// the pointer is pointing to.
     fn position(&self) -> usize;
+
+    /// Returns column, starting from one, of the place in the
+    /// source the pointer is pointing to counted in utf-16 code units.
+    ///
+    /// Characters outside of the basic multilingual plane
+    /// take two columns.
+    fn utf16_position(&self) -> usize;
+
+    /// Returns column, starting from one, of the place in the
+    /// source the pointer is pointing to counted in utf-8 bytes.
+    fn byte_position(&self) -> usize;
+
+    /// Returns number of utf-8 bytes from the beginning of
+    /// the source to the place the pointer is pointing to.
+    fn offset(&self) -> usize;
 }
 
 /// Represents range in source.
@@ -109,7 +172,7 @@ pub trait Pointer: Clone {
 ///     Ident {
 ///         id: ident.id,
 ///         span: ident.span.clone(),
-///         symbol: ident.symbol.chars().rev().collect(),
+///         symbol: Symbol::new(&ident.symbol.chars().rev().collect::<String>()),
 ///     }
 /// }
 /// ```
@@ -140,7 +203,7 @@ pub mod tests {
     ///
     /// # Arguments
     /// * creator - factory function creating instance of the
-    ///     tested source from the passed `&str` content.
+    ///   tested source from the passed `&str` content.
     pub fn source_tests<T: Source>(creator: &dyn Fn(&str) -> T) {
         info!("subtest source::tests::string_source_from_empty_str");
         string_source_from_empty_str(creator);
@@ -166,6 +229,41 @@ pub mod tests {
         getting_all_source_with_two_ptr(creator);
         info!("subtest source::tests::getting_source_fragment_with_two_ptr");
         getting_source_fragment_with_two_ptr(creator);
+        info!("subtest source::tests::columns_of_multibyte_characters");
+        columns_of_multibyte_characters(creator);
+        info!("subtest source::tests::columns_reset_in_new_line");
+        columns_reset_in_new_line(creator);
+        info!("subtest source::tests::getting_multibyte_source_fragment_with_two_ptr");
+        getting_multibyte_source_fragment_with_two_ptr(creator);
+        info!("subtest source::tests::pointer_from_utf16_column");
+        pointer_from_utf16_column(creator);
+        info!("subtest source::tests::pointer_from_invalid_utf16_column");
+        pointer_from_invalid_utf16_column(creator);
+        info!("subtest source::tests::visual_position_expands_tabs");
+        visual_position_expands_tabs(creator);
+    }
+
+    /// Asserts columns and offset of the currently pointed character.
+    ///
+    /// Checks, in order, the utf-16 column, byte column
+    /// and byte offset from the beginning of the source.
+    pub fn assert_columns<T: Source>(s: &T, utf16: usize, byte: usize, offset: usize) {
+        let ptr = s.curr_ptr();
+        assert_eq!(
+            ptr.utf16_position(),
+            utf16,
+            "Wrong utf-16 column (right is expected one)"
+        );
+        assert_eq!(
+            ptr.byte_position(),
+            byte,
+            "Wrong byte column (right is expected one)"
+        );
+        assert_eq!(
+            ptr.offset(),
+            offset,
+            "Wrong byte offset (right is expected one)"
+        );
     }
 
     /// Asserts source state equals the one passed in the arguments.
@@ -218,7 +316,7 @@ pub mod tests {
 
     fn reading_all_characters_from_source<T: Source>(creator: &dyn Fn(&str) -> T) {
         let raw = "abcde";
-        let mut s = creator(&raw);
+        let mut s = creator(raw);
         for (i, ch) in raw.chars().enumerate() {
             assert_source(&s, Some(ch), 1, i + 1);
             s.next_char();
@@ -259,7 +357,7 @@ c"#,
 
     fn reading_white_spaces<T: Source>(creator: &dyn Fn(&str) -> T) {
         let raw = "a b";
-        let mut s = creator(&raw);
+        let mut s = creator(raw);
         s.next_char();
         assert_source(&s, Some(' '), 1, 2);
     }
@@ -270,7 +368,7 @@ c"#,
         asdas
         fdsfsdfds
         "#;
-        let mut s = creator(&raw);
+        let mut s = creator(raw);
         for _ in raw.chars() {
             s.next_char();
         }
@@ -291,7 +389,7 @@ c"#,
         gtrhdrthytj ydtj ukf 
         ey ste e 
         a"#;
-        let mut s = creator(&raw);
+        let mut s = creator(raw);
         let beg = s.curr_ptr();
         for _ in raw.chars() {
             s.next_char();
@@ -305,7 +403,7 @@ c"#,
         gtrhdrthytj ydtj ukf 
         ey ste e 
         a"#;
-        let mut s = creator(&raw);
+        let mut s = creator(raw);
         for _ in raw.chars().take(10) {
             s.next_char();
         }
@@ -319,4 +417,82 @@ c"#,
             raw.chars().skip(10).take(10).collect::<String>()
         );
     }
+
+    fn columns_of_multibyte_characters<T: Source>(creator: &dyn Fn(&str) -> T) {
+        let mut s = creator("💣ℝßA");
+        assert_source(&s, Some('💣'), 1, 1);
+        assert_columns(&s, 1, 1, 0);
+        s.next_char();
+        assert_source(&s, Some('ℝ'), 1, 2);
+        assert_columns(&s, 3, 5, 4);
+        s.next_char();
+        assert_source(&s, Some('ß'), 1, 3);
+        assert_columns(&s, 4, 8, 7);
+        s.next_char();
+        assert_source(&s, Some('A'), 1, 4);
+        assert_columns(&s, 5, 10, 9);
+        s.next_char();
+        assert_columns(&s, 6, 11, 10);
+    }
+
+    fn columns_reset_in_new_line<T: Source>(creator: &dyn Fn(&str) -> T) {
+        let mut s = creator("ß\n€a");
+        s.next_char();
+        assert_columns(&s, 2, 3, 2);
+        s.next_char();
+        assert_source(&s, Some('€'), 2, 1);
+        assert_columns(&s, 1, 1, 3);
+        s.next_char();
+        assert_source(&s, Some('a'), 2, 2);
+        assert_columns(&s, 2, 4, 6);
+    }
+
+    fn getting_multibyte_source_fragment_with_two_ptr<T: Source>(creator: &dyn Fn(&str) -> T) {
+        let mut s = creator("a💣ℝ\nßA");
+        s.next_char();
+        let beg = s.curr_ptr();
+        for _ in 0..4 {
+            s.next_char();
+        }
+        let end = s.curr_ptr();
+        assert_eq!(s.source_between(&beg, &end), "💣ℝ\nß");
+    }
+
+    fn pointer_from_utf16_column<T: Source>(creator: &dyn Fn(&str) -> T) {
+        let mut s = creator("a💣b\n\tℝc");
+        for _ in 0..7 {
+            s.next_char();
+        }
+        let beg = s.ptr_from_utf16(1, 4).unwrap();
+        assert_eq!(beg.line(), 1);
+        assert_eq!(beg.position(), 3);
+        assert_eq!(beg.offset(), 5);
+        let end = s.ptr_from_utf16(2, 3).unwrap();
+        assert_eq!(end.line(), 2);
+        assert_eq!(end.position(), 3);
+        assert_eq!(end.utf16_position(), 3);
+        assert_eq!(s.source_between(&beg, &end), "b\n\tℝ");
+        let new_line = s.ptr_from_utf16(1, 5).unwrap();
+        assert_eq!(s.source_between(&new_line, &end), "\n\tℝ");
+    }
+
+    fn pointer_from_invalid_utf16_column<T: Source>(creator: &dyn Fn(&str) -> T) {
+        let mut s = creator("a💣b\nc");
+        for _ in 0..6 {
+            s.next_char();
+        }
+        assert!(s.ptr_from_utf16(1, 3).is_none());
+        assert!(s.ptr_from_utf16(1, 6).is_none());
+        assert!(s.ptr_from_utf16(3, 1).is_none());
+    }
+
+    fn visual_position_expands_tabs<T: Source>(creator: &dyn Fn(&str) -> T) {
+        let mut s = creator("x\n\ta\tb");
+        for _ in 0..5 {
+            s.next_char();
+        }
+        assert_source(&s, Some('b'), 2, 4);
+        assert_eq!(s.visual_position(&s.curr_ptr(), 4), 9);
+        assert_eq!(s.visual_position(&s.curr_ptr(), 1), 4);
+    }
 }
@@ -0,0 +1,175 @@
+//! Holds the implementation of the
+//! [`Stream`](struct.Stream.html) source,
+//! reading program from any
+//! [`io::Read`](https://doc.rust-lang.org/std/io/trait.Read.html)
+//! implementor, and its corresponding pointer.
+
+use std::io;
+use std::io::Read;
+
+use crate::tracker::{Location, Tracker};
+use crate::Source;
+use ftl_utility::utf8;
+
+/// Program source read from the stream of bytes
+/// like the standard input or a pipe.
+///
+/// Streams cannot be rewinded so every character read
+/// from the underlying reader is kept in the buffer.
+/// That way [`source_between`](../trait.Source.html#tymethod.source_between)
+/// can be answered for any pointer returned by this source.
+///
+/// # Panics
+///
+/// Reading stream that is not a valid utf-8 text panics.
+///
+/// # Examples
+///
+/// Reading source from the standard input:
+///
+/// ```no_run
+/// use ftl_source::stream::Stream;
+///
+/// let source = Stream::stdin();
+/// ```
+///
+/// Reading source from anything implementing `Read`:
+///
+/// ```
+/// use ftl_source::{stream::Stream, Source};
+///
+/// let source = Stream::new("def foo: 1".as_bytes());
+/// assert_eq!(source.curr_char(), Some('d'));
+/// ```
+pub struct Stream<R: Read> {
+    reader: utf8::Reader<R>,
+
+    /// Every character read so far.
+    buff: Vec<char>,
+
+    current: Option<char>,
+    tracker: Tracker,
+
+    eof_reached: bool,
+}
+
+impl Stream<io::Stdin> {
+    /// Creates new `Stream` source reading from the
+    /// standard input.
+    pub fn stdin() -> Self {
+        Self::new(io::stdin())
+    }
+}
+
+impl<R: Read> Stream<R> {
+    /// Creates new `Stream` source reading from `inner`.
+    ///
+    /// First character is read immediately.
+    pub fn new(inner: R) -> Self {
+        let mut s = Self {
+            reader: utf8::Reader::new(inner),
+            buff: Vec::new(),
+            tracker: Tracker::new(None),
+            current: None,
+            eof_reached: false,
+        };
+        s.current = s.try_next_char();
+        s.eof_reached = s.current.is_none();
+        s.tracker = Tracker::new(s.current);
+        s
+    }
+
+    fn try_next_char(&mut self) -> Option<char> {
+        match self.reader.read_utf8_char() {
+            Ok(ch) => {
+                self.buff.push(ch);
+                Some(ch)
+            }
+            Err(err) => {
+                if let io::ErrorKind::UnexpectedEof = err.kind() {
+                    None
+                } else {
+                    panic!("Not a valid utf-8 character!");
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Source for Stream<R> {
+    type Pointer = Pointer;
+
+    fn curr_char(&self) -> Option<char> {
+        self.current
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        if self.eof_reached {
+            return None;
+        }
+        let peek = self.try_next_char();
+        self.eof_reached = peek.is_none();
+        self.tracker.advance(peek);
+        self.current = peek;
+        self.current
+    }
+
+    fn curr_ptr(&self) -> Self::Pointer {
+        Self::Pointer {
+            loc: self.tracker.loc.clone(),
+        }
+    }
+
+    fn source_between(&self, begin: &Self::Pointer, end: &Self::Pointer) -> std::string::String {
+        self.buff[begin.loc.index..end.loc.index].iter().collect()
+    }
+
+    /// Only the part of the stream which has already been
+    /// read can be searched.
+    fn ptr_from_utf16(&self, line: usize, col: usize) -> Option<Self::Pointer> {
+        Tracker::locate_utf16(self.buff.iter().cloned(), line, col).map(|loc| Self::Pointer { loc })
+    }
+}
+
+/// Source pointer for the [`Stream`](struct.Stream.html)
+/// source.
+///
+/// Holds location of the character in the streams buffer.
+#[derive(Clone, Debug)]
+pub struct Pointer {
+    loc: Location,
+}
+
+impl crate::Pointer for Pointer {
+    fn line(&self) -> usize {
+        self.loc.line
+    }
+
+    fn position(&self) -> usize {
+        self.loc.position
+    }
+
+    fn utf16_position(&self) -> usize {
+        self.loc.utf16_in_line + 1
+    }
+
+    fn byte_position(&self) -> usize {
+        self.loc.bytes_in_line + 1
+    }
+
+    fn offset(&self) -> usize {
+        self.loc.offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{stream::*, tests::*};
+
+    #[test]
+    fn source_tests_for_stream_source() {
+        source_tests(&|raw| Stream::new(Cursor::new(raw.to_owned())));
+    }
+}
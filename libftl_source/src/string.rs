@@ -2,6 +2,7 @@
 //! [`String`](struct.String.html)
 //!  source and its corresponding pointer.
 
+use crate::tracker::{Location, Tracker};
 use crate::Source;
 use std::convert::From;
 
@@ -19,31 +20,17 @@ use std::convert::From;
 /// Source keeps raw program source as the
 /// [`std::string::String`](https://doc.rust-lang.org/std/string/struct.String.html).
 ///
-/// Current position in based on the index, which actually
-/// doesn't index raw string directly (because of the possible
-/// multibyte unicode characters). Instead it represents
-/// how many characters should be skipped, as the characters
-/// are retrieved through
-/// [`chars`](https://doc.rust-lang.org/std/string/struct.String.html#method.chars)
-///  method and iterators
-/// [`skip`](https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.skip)
-/// method.
+/// Current position is based on the byte offset of the current
+/// character in the raw string. Line and column numbers are
+/// tracked alongside as its not possible to know them based
+/// only on the offset.
 ///
-/// Some gymnastics need to be done when dealing with the new lines
-/// so the source tracks them correctly but thats about it.
-///  
 /// # Pointer
 ///
-/// As stated before pointer implementation for this struct
-/// hold hom many characters should be skipped using
-/// [`skip`](https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.skip)
-/// method od characters iterator.
-///
-/// Additionaly informations about line number and
-/// number of the character in line pointer points
-/// to are also being kept as fields of the pointer
-/// struct as its not possible to know them based
-/// only on the character index.   
+/// Pointer keeps the offset as well as the line and columns
+/// of the character it points to so
+/// [`source_between`](../trait.Source.html#tymethod.source_between)
+/// is just slicing of the raw string.
 ///
 /// # Examples
 ///
@@ -82,47 +69,19 @@ pub struct String {
     /// Raw source as String.
     raw: std::string::String,
 
-    curr_line: usize,
-    curr_pos: usize,
-
-    /// Current pointer position.
-    /// Corresponds to the current character, starting at 0.
-    index: usize,
-
-    /// Should the curr_line be incremented in the next
-    /// pointer shift.
-    switch_line: bool,
+    tracker: Tracker,
 }
 
 impl From<&'_ str> for String {
     fn from(raw: &str) -> Self {
-        let mut s = Self {
-            raw: std::string::String::from(raw),
-            curr_line: 1,
-            curr_pos: 1,
-            index: 0,
-            switch_line: false,
-        };
-        if let Some('\n') = s.curr_char() {
-            s.switch_line = true;
-        }
-        s
+        Self::from(std::string::String::from(raw))
     }
 }
 
 impl From<std::string::String> for String {
     fn from(raw: std::string::String) -> Self {
-        let mut s = Self {
-            raw,
-            curr_line: 1,
-            curr_pos: 1,
-            index: 0,
-            switch_line: false,
-        };
-        if let Some('\n') = s.curr_char() {
-            s.switch_line = true;
-        }
-        s
+        let tracker = Tracker::new(raw.chars().next());
+        Self { raw, tracker }
     }
 }
 
@@ -130,43 +89,30 @@ impl Source for String {
     type Pointer = Pointer;
 
     fn curr_char(&self) -> Option<char> {
-        self.raw.chars().nth(self.index)
+        self.raw[self.tracker.loc.offset..].chars().next()
     }
 
     fn next_char(&mut self) -> Option<char> {
-        if self.index == self.raw.len() {
-            return None;
-        }
-        self.index += 1;
-        let opt = self.curr_char();
-        if self.switch_line {
-            self.switch_line = false;
-            self.curr_line += 1;
-            self.curr_pos = 0;
-        }
-        if let Some(ch) = opt {
-            if ch == '\n' {
-                self.switch_line = true;
-            }
-            self.curr_pos += 1;
-        }
-        opt
+        let curr = self.curr_char()?;
+        let next = self.raw[self.tracker.loc.offset + curr.len_utf8()..]
+            .chars()
+            .next();
+        self.tracker.advance(next);
+        next
     }
 
     fn curr_ptr(&self) -> Self::Pointer {
         Self::Pointer {
-            index: self.index,
-            curr_line: self.curr_line,
-            curr_pos: self.curr_pos,
+            loc: self.tracker.loc.clone(),
         }
     }
 
     fn source_between(&self, begin: &Self::Pointer, end: &Self::Pointer) -> std::string::String {
-        self.raw
-            .chars()
-            .skip(begin.index)
-            .take(end.index - begin.index)
-            .collect()
+        std::string::String::from(&self.raw[begin.loc.offset..end.loc.offset])
+    }
+
+    fn ptr_from_utf16(&self, line: usize, col: usize) -> Option<Self::Pointer> {
+        Tracker::locate_utf16(self.raw.chars(), line, col).map(|loc| Self::Pointer { loc })
     }
 }
 
@@ -177,19 +123,28 @@ impl Source for String {
 /// information about implementation.
 #[derive(Clone, Debug)]
 pub struct Pointer {
-    index: usize,
-
-    curr_line: usize,
-    curr_pos: usize,
+    loc: Location,
 }
 
 impl crate::Pointer for Pointer {
     fn line(&self) -> usize {
-        self.curr_line
+        self.loc.line
     }
 
     fn position(&self) -> usize {
-        self.curr_pos
+        self.loc.position
+    }
+
+    fn utf16_position(&self) -> usize {
+        self.loc.utf16_in_line + 1
+    }
+
+    fn byte_position(&self) -> usize {
+        self.loc.bytes_in_line + 1
+    }
+
+    fn offset(&self) -> usize {
+        self.loc.offset
     }
 }
 
@@ -0,0 +1,105 @@
+//! Holds the implementation of the synthetic source,
+//! having no content, and its pointer which can point anywhere.
+//!
+//! Synthetic source stands in for the source of the syntax
+//! trees which haven't been parsed from any text, like the
+//! ones generated by the tools or loaded from other formats.
+
+use crate::Source;
+
+/// Source without any content.
+///
+/// # Examples
+///
+/// ```
+/// use ftl_source::{synthetic::Synthetic, Source};
+///
+/// let source = Synthetic::new();
+/// assert_eq!(source.curr_char(), None);
+/// ```
+#[derive(Default)]
+pub struct Synthetic;
+
+impl Synthetic {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Source for Synthetic {
+    type Pointer = Pointer;
+
+    fn curr_char(&self) -> Option<char> {
+        None
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        None
+    }
+
+    fn curr_ptr(&self) -> Self::Pointer {
+        Pointer::default()
+    }
+
+    fn source_between(&self, _begin: &Self::Pointer, _end: &Self::Pointer) -> String {
+        String::new()
+    }
+
+    fn ptr_from_utf16(&self, _line: usize, _col: usize) -> Option<Self::Pointer> {
+        None
+    }
+}
+
+/// Pointer with freely set position.
+///
+/// Default pointer points to the beginning of the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pointer {
+    pub line: usize,
+    pub position: usize,
+    pub utf16_position: usize,
+    pub byte_position: usize,
+    pub offset: usize,
+}
+
+impl Pointer {
+    /// Creates pointer to the place in the line
+    /// of the source having only single byte characters.
+    pub fn ascii(line: usize, position: usize, offset: usize) -> Self {
+        Self {
+            line,
+            position,
+            utf16_position: position,
+            byte_position: position,
+            offset,
+        }
+    }
+}
+
+impl Default for Pointer {
+    fn default() -> Self {
+        Self::ascii(1, 1, 0)
+    }
+}
+
+impl crate::Pointer for Pointer {
+    fn line(&self) -> usize {
+        self.line
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    fn utf16_position(&self) -> usize {
+        self.utf16_position
+    }
+
+    fn byte_position(&self) -> usize {
+        self.byte_position
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+}
@@ -0,0 +1,99 @@
+//! Position bookkeeping shared by the out of the box sources.
+
+/// Place of the character in the source.
+///
+/// Every out of the box pointer is a thin wrapper
+/// around it.
+#[derive(Clone, Debug)]
+pub(crate) struct Location {
+    /// Number of characters before this one.
+    pub index: usize,
+    /// Number of utf-8 bytes before this character.
+    pub offset: usize,
+
+    pub line: usize,
+    pub position: usize,
+    /// Number of utf-16 code units before this character in its line.
+    pub utf16_in_line: usize,
+    /// Number of utf-8 bytes before this character in its line.
+    pub bytes_in_line: usize,
+}
+
+/// Follows characters read from the source keeping
+/// the [`Location`](struct.Location.html) of the current one.
+#[derive(Clone, Debug)]
+pub(crate) struct Tracker {
+    pub loc: Location,
+    current: Option<char>,
+
+    /// Should the line be incremented in the next shift.
+    switch_line: bool,
+}
+
+impl Tracker {
+    /// Creates tracker pointing at the first character of the source.
+    pub fn new(first: Option<char>) -> Self {
+        Self {
+            loc: Location {
+                index: 0,
+                offset: 0,
+                line: 1,
+                position: 1,
+                utf16_in_line: 0,
+                bytes_in_line: 0,
+            },
+            current: first,
+            switch_line: first == Some('\n'),
+        }
+    }
+
+    /// Shifts tracker to the next character.
+    /// Does nothing if the end of the source has already been reached.
+    pub fn advance(&mut self, next: Option<char>) {
+        let prev = match self.current {
+            Some(ch) => ch,
+            None => return,
+        };
+        self.loc.index += 1;
+        self.loc.offset += prev.len_utf8();
+        self.loc.utf16_in_line += prev.len_utf16();
+        self.loc.bytes_in_line += prev.len_utf8();
+        if self.switch_line {
+            self.switch_line = false;
+            self.loc.line += 1;
+            self.loc.position = 0;
+            self.loc.utf16_in_line = 0;
+            self.loc.bytes_in_line = 0;
+        }
+        if let Some(ch) = next {
+            if ch == '\n' {
+                self.switch_line = true;
+            }
+            self.loc.position += 1;
+        }
+        self.current = next;
+    }
+
+    /// Walks over the `chars` looking for the character in the given
+    /// line starting at the given utf-16 column (both counted from one).
+    ///
+    /// Returns None if there is no such character or the column
+    /// points into the middle of one.
+    pub fn locate_utf16(
+        mut chars: impl Iterator<Item = char>,
+        line: usize,
+        col: usize,
+    ) -> Option<Location> {
+        let mut t = Self::new(chars.next());
+        loop {
+            if t.loc.line > line || (t.loc.line == line && t.loc.utf16_in_line + 1 > col) {
+                return None;
+            }
+            if t.loc.line == line && t.loc.utf16_in_line + 1 == col {
+                return Some(t.loc);
+            }
+            t.current?;
+            t.advance(chars.next());
+        }
+    }
+}
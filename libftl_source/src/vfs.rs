@@ -0,0 +1,166 @@
+//! Holds the implementation of the in-memory
+//! [`Vfs`](struct.Vfs.html) file system and
+//! the [`File`](struct.File.html) source opened from it.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{string, Source};
+
+/// Virtual file system keeping files contents in memory.
+///
+/// Files are keyed by their path. Paths are not normalized
+/// so `a/b.ftl` and `./a/b.ftl` are two different files.
+///
+/// Mostly useful for tests which need more than one
+/// source file without touching the disk.
+///
+/// # Examples
+///
+/// ```
+/// use ftl_source::{vfs::Vfs, Source};
+///
+/// let mut fs = Vfs::new();
+/// fs.insert("main.ftl", "def main: @foo");
+/// fs.insert("foo.ftl", "def foo: 1");
+///
+/// let source = fs.open("foo.ftl").unwrap();
+/// assert_eq!(source.curr_char(), Some('d'));
+/// assert!(fs.open("bar.ftl").is_err());
+/// ```
+#[derive(Default)]
+pub struct Vfs {
+    files: HashMap<PathBuf, std::string::String>,
+}
+
+impl Vfs {
+    /// Creates new, empty file system.
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+        }
+    }
+
+    /// Adds file with the given content to the file system.
+    /// If the file already existed its content is replaced
+    /// and the old one is returned.
+    pub fn insert(
+        &mut self,
+        path: impl Into<PathBuf>,
+        content: impl Into<std::string::String>,
+    ) -> Option<std::string::String> {
+        self.files.insert(path.into(), content.into())
+    }
+
+    /// Removes file from the file system returning its content.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<std::string::String> {
+        self.files.remove(path.as_ref())
+    }
+
+    /// Returns true if the file exists.
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        self.files.contains_key(path.as_ref())
+    }
+
+    /// Returns iterator over paths of all the files
+    /// in the file system in no particular order.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Opens file with the given path as the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns error of the `NotFound` kind if there
+    /// is no such file.
+    pub fn open(&self, path: impl AsRef<Path>) -> io::Result<File> {
+        let path = path.as_ref();
+        match self.files.get(path) {
+            Some(content) => Ok(File {
+                path: path.to_owned(),
+                src: string::String::from(content.as_str()),
+            }),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such file in virtual file system: {}", path.display()),
+            )),
+        }
+    }
+}
+
+/// Source opened from the [`Vfs`](struct.Vfs.html).
+///
+/// Holds copy of the files content at the moment of opening it
+/// so later changes in the file system are not visible.
+/// Everything except the [`path`](#method.path) is delegated to the
+/// [`String`](../string/struct.String.html) source.
+pub struct File {
+    path: PathBuf,
+    src: string::String,
+}
+
+impl File {
+    /// Returns path the file was opened with.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Source for File {
+    type Pointer = string::Pointer;
+
+    fn curr_char(&self) -> Option<char> {
+        self.src.curr_char()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        self.src.next_char()
+    }
+
+    fn curr_ptr(&self) -> Self::Pointer {
+        self.src.curr_ptr()
+    }
+
+    fn source_between(&self, begin: &Self::Pointer, end: &Self::Pointer) -> std::string::String {
+        self.src.source_between(begin, end)
+    }
+
+    fn ptr_from_utf16(&self, line: usize, col: usize) -> Option<Self::Pointer> {
+        self.src.ptr_from_utf16(line, col)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::*, vfs::*};
+
+    #[test]
+    fn source_tests_for_vfs_file_source() {
+        source_tests(&|raw| {
+            let mut fs = Vfs::new();
+            fs.insert("test.ftl", raw);
+            fs.open("test.ftl").unwrap()
+        });
+    }
+
+    #[test]
+    fn opening_missing_file_fails() {
+        let fs = Vfs::new();
+        match fs.open("missing.ftl") {
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            Ok(_) => panic!("Opened file which does not exist"),
+        }
+    }
+
+    #[test]
+    fn opened_file_keeps_its_path() {
+        let mut fs = Vfs::new();
+        fs.insert("dir/a.ftl", "def a: 1");
+        fs.insert("dir/b.ftl", "def b: 2");
+        let f = fs.open("dir/b.ftl").unwrap();
+        assert_eq!(f.path(), Path::new("dir/b.ftl"));
+        assert_eq!(f.curr_char(), Some('d'));
+    }
+}
@@ -52,16 +52,11 @@ pub fn from_bytes(bytes: &[u8]) -> io::Result<char> {
     }
     verify_intermediate_bytes(&bytes[1..len])?;
     let cp = code_point(&bytes[..len]);
-    println!(
-        "Read bytes: {}, codepoint: {:X}",
-        bytes_repr(&bytes[..]),
-        cp
-    );
     Ok(std::char::from_u32(cp).unwrap_or_else(|| {
         panic!(
             "Verified utf-8 character could not be 
             parsed by the std library function: {}, codepoint {:X}",
-            bytes_repr(&bytes[..]),
+            bytes_repr(bytes),
             cp
         )
     }))
@@ -82,10 +77,10 @@ pub fn char_len(bytes: &[u8]) -> io::Result<usize> {
     if byte & 0b1111_1000 == 0b1111_0000 {
         return Ok(4);
     }
-    Err(io::Error::new(
-        io::ErrorKind::Other,
-        format!("Not an utf-8 character: {:X}", byte),
-    ))
+    Err(io::Error::other(format!(
+        "Not an utf-8 character: {:X}",
+        byte
+    )))
 }
 
 pub fn code_point(bytes: &[u8]) -> u32 {
@@ -102,10 +97,10 @@ pub fn code_point(bytes: &[u8]) -> u32 {
 fn verify_intermediate_bytes(bytes: &[u8]) -> io::Result<()> {
     for byte in bytes {
         if byte & 0b1100_0000 != 0b1000_0000 {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Not an utf-8 character: {}", bytes_repr(bytes)),
-            ));
+            return Err(io::Error::other(format!(
+                "Not an utf-8 character: {}",
+                bytes_repr(bytes)
+            )));
         }
     }
     Ok(())
@@ -191,10 +186,7 @@ mod tests {
     fn reading_past_returns_unexpected_eof() {
         let mut reader = Reader::new(&b""[..]);
         assert!(match reader.read_utf8_char() {
-            Err(err) => match err.kind() {
-                io::ErrorKind::UnexpectedEof => true,
-                _ => false,
-            },
+            Err(err) => err.kind() == io::ErrorKind::UnexpectedEof,
             _ => false,
         });
     }